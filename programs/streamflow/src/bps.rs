@@ -0,0 +1,65 @@
+//! Conversions between percentages and basis points
+//!
+//! Fees and weights are stored in basis points (1 bps = 0.01%), so 5% is 500,
+//! not 5. Integrators should convert with these helpers rather than by hand.
+
+use anchor_lang::prelude::*;
+use crate::errors::StreamFlowError;
+
+/// Basis points in 100%
+pub const MAX_BPS: u16 = 10_000;
+
+/// Convert a percentage (e.g. `2.5` for 2.5%) to basis points, rounding to
+/// the nearest basis point
+pub fn bps_from_percent(percent: f64) -> Result<u16> {
+    require!(
+        percent.is_finite() && (0.0..=100.0).contains(&percent),
+        StreamFlowError::InvalidBasisPoints
+    );
+    Ok((percent * 100.0).round() as u16)
+}
+
+/// Convert basis points to a percentage (e.g. `250` to `2.5`)
+pub fn percent_from_bps(bps: u16) -> f64 {
+    bps as f64 / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bps_from_percent() {
+        assert_eq!(bps_from_percent(0.0).unwrap(), 0);
+        assert_eq!(bps_from_percent(0.5).unwrap(), 50);
+        assert_eq!(bps_from_percent(5.0).unwrap(), 500);
+        assert_eq!(bps_from_percent(100.0).unwrap(), MAX_BPS);
+    }
+
+    #[test]
+    fn test_bps_from_percent_rounds_to_nearest() {
+        assert_eq!(bps_from_percent(0.125).unwrap(), 13);
+        assert_eq!(bps_from_percent(0.375).unwrap(), 38);
+        assert_eq!(bps_from_percent(1.0 / 3.0).unwrap(), 33);
+        assert_eq!(bps_from_percent(0.004).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_bps_from_percent_rejects_out_of_range() {
+        for percent in [-0.01, 100.01, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(
+                bps_from_percent(percent).unwrap_err(),
+                StreamFlowError::InvalidBasisPoints.into()
+            );
+        }
+    }
+
+    #[test]
+    fn test_percent_round_trip() {
+        assert_eq!(percent_from_bps(250), 2.5);
+        assert_eq!(percent_from_bps(MAX_BPS), 100.0);
+        for bps in [0, 1, 50, 499, 500, 9_999, MAX_BPS] {
+            assert_eq!(bps_from_percent(percent_from_bps(bps)).unwrap(), bps);
+        }
+    }
+}
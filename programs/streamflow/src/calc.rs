@@ -0,0 +1,189 @@
+//! Pure vesting math for StreamFlow
+//!
+//! Nothing in this module depends on Anchor or Solana types, so off-chain
+//! clients (including WASM builds) can compute exactly the amounts the program
+//! will release. The on-chain `Stream` calculators are thin wrappers over these
+//! functions.
+
+use core::fmt;
+
+/// Errors produced by the pure calculators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalcError {
+    /// An intermediate value did not fit in its integer type
+    Overflow,
+    /// A step interval or duration of zero was used as a divisor
+    DivisionByZero,
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::Overflow => write!(f, "arithmetic overflow"),
+            CalcError::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+pub type CalcResult<T> = core::result::Result<T, CalcError>;
+
+/// Amount released linearly between `start_time` and `end_time`
+///
+/// A zero-length stream releases everything at once.
+pub fn linear_amount(total: u64, start_time: i64, end_time: i64, current_time: i64) -> CalcResult<u64> {
+    if end_time <= start_time {
+        return Ok(total);
+    }
+    if current_time <= start_time {
+        return Ok(0);
+    }
+
+    let effective_time = current_time.min(end_time);
+    let elapsed = (effective_time - start_time) as u128;
+    let duration = (end_time - start_time) as u128;
+
+    let streamed = (total as u128)
+        .checked_mul(elapsed)
+        .ok_or(CalcError::Overflow)?
+        .checked_div(duration)
+        .ok_or(CalcError::DivisionByZero)?;
+
+    Ok((streamed as u64).min(total))
+}
+
+/// Amount released by a cliff stream: `cliff_amount` at `cliff_time`, with the
+/// remainder released linearly over `[cliff_time, end_time]`. Nothing of the
+/// remainder accrues before the cliff.
+pub fn cliff_amount(
+    total: u64,
+    cliff_amount: u64,
+    start_time: i64,
+    end_time: i64,
+    cliff_time: i64,
+    current_time: i64,
+) -> CalcResult<u64> {
+    if current_time < cliff_time || current_time < start_time {
+        return Ok(0);
+    }
+
+    let remaining = total.saturating_sub(cliff_amount);
+    let linear_start = cliff_time.max(start_time);
+    let linear = if current_time > linear_start && remaining > 0 {
+        linear_amount(remaining, linear_start, end_time, current_time)?
+    } else {
+        0
+    };
+
+    Ok(cliff_amount.saturating_add(linear))
+}
+
+/// Amount released in whole `rate_interval`-second steps of `rate_amount`
+pub fn step_amount(
+    total: u64,
+    rate_amount: u64,
+    rate_interval: u64,
+    start_time: i64,
+    current_time: i64,
+) -> CalcResult<u64> {
+    if current_time < start_time {
+        return Ok(0);
+    }
+
+    let elapsed = (current_time - start_time) as u64;
+    let intervals_passed = elapsed
+        .checked_div(rate_interval)
+        .ok_or(CalcError::DivisionByZero)?;
+
+    let released = intervals_passed
+        .checked_mul(rate_amount)
+        .ok_or(CalcError::Overflow)?;
+
+    Ok(released.min(total))
+}
+
+/// Amount released by a cliff-then-step stream: nothing before `cliff_time`,
+/// `cliff_amount` as a lump at `cliff_time`, then whole `rate_interval` steps
+/// of `rate_amount` counted from the cliff until the remainder is released
+pub fn cliff_step_amount(
+    total: u64,
+    cliff_amount: u64,
+    cliff_time: i64,
+    rate_amount: u64,
+    rate_interval: u64,
+    current_time: i64,
+) -> CalcResult<u64> {
+    if current_time < cliff_time {
+        return Ok(0);
+    }
+
+    let lump = cliff_amount.min(total);
+    let steps = step_amount(total - lump, rate_amount, rate_interval, cliff_time, current_time)?;
+
+    Ok(lump + steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_amount() {
+        assert_eq!(linear_amount(1_000, 100, 200, 50), Ok(0));
+        assert_eq!(linear_amount(1_000, 100, 200, 100), Ok(0));
+        assert_eq!(linear_amount(1_000, 100, 200, 150), Ok(500));
+        assert_eq!(linear_amount(1_000, 100, 200, 200), Ok(1_000));
+        assert_eq!(linear_amount(1_000, 100, 200, 10_000), Ok(1_000));
+    }
+
+    #[test]
+    fn test_linear_zero_duration_releases_everything() {
+        assert_eq!(linear_amount(1_000, 100, 100, 100), Ok(1_000));
+    }
+
+    #[test]
+    fn test_linear_large_amounts_do_not_overflow() {
+        assert_eq!(linear_amount(u64::MAX, 0, 2, 1), Ok(u64::MAX / 2));
+    }
+
+    #[test]
+    fn test_cliff_amount() {
+        // 1000 total, 200 at the cliff, remaining 800 linear over [120, 200]
+        assert_eq!(cliff_amount(1_000, 200, 100, 200, 120, 110), Ok(0));
+        assert_eq!(cliff_amount(1_000, 200, 100, 200, 120, 120), Ok(200));
+        assert_eq!(cliff_amount(1_000, 200, 100, 200, 120, 160), Ok(200 + 400));
+        assert_eq!(cliff_amount(1_000, 200, 100, 200, 120, 200), Ok(1_000));
+    }
+
+    #[test]
+    fn test_step_amount() {
+        assert_eq!(step_amount(1_000, 100, 10, 100, 99), Ok(0));
+        assert_eq!(step_amount(1_000, 100, 10, 100, 109), Ok(0));
+        assert_eq!(step_amount(1_000, 100, 10, 100, 110), Ok(100));
+        assert_eq!(step_amount(1_000, 100, 10, 100, 1_000), Ok(1_000));
+    }
+
+    #[test]
+    fn test_cliff_step_amount() {
+        // 1200 total, 400 lump at t=1000, then 100 every 10s
+        let at = |now| cliff_step_amount(1_200, 400, 1_000, 100, 10, now);
+        assert_eq!(at(0), Ok(0));
+        assert_eq!(at(999), Ok(0));
+        assert_eq!(at(1_000), Ok(400));
+        assert_eq!(at(1_009), Ok(400));
+        assert_eq!(at(1_010), Ok(500));
+        assert_eq!(at(1_035), Ok(700));
+        assert_eq!(at(1_080), Ok(1_200));
+        assert_eq!(at(5_000), Ok(1_200));
+    }
+
+    #[test]
+    fn test_step_zero_interval() {
+        assert_eq!(step_amount(1_000, 100, 0, 100, 150), Err(CalcError::DivisionByZero));
+    }
+
+    #[test]
+    fn test_step_overflow() {
+        assert_eq!(step_amount(u64::MAX, u64::MAX, 1, 0, 2), Err(CalcError::Overflow));
+    }
+}
+
@@ -374,6 +374,12 @@ pub enum StreamFlowError {
     
     #[msg("Unknown error occurred")]
     UnknownError,
+    
+    #[msg("Percentage must be between 0 and 100 (0 to 10000 bps)")]
+    InvalidBasisPoints,
+
+    #[msg("Interest rate exceeds the program's maximum")]
+    InterestRateExceedsMaximum,
 }
 
 impl From<StreamFlowError> for ProgramError {
@@ -444,7 +450,117 @@ impl StreamFlowError {
     }
 }
 
-pub type StreamFlowResult<T> = Result<T, StreamFlowError>;
+/// Errors raised by the stream instruction handlers
+#[error_code]
+pub enum StreamError {
+    #[msg("Invalid time range")]
+    InvalidTimeRange,
+    
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    
+    #[msg("Invalid cliff time")]
+    InvalidCliffTime,
+    
+    #[msg("Stream is paused")]
+    StreamPaused,
+    
+    #[msg("Stream is already paused")]
+    StreamAlreadyPaused,
+    
+    #[msg("Stream is not paused")]
+    StreamNotPaused,
+    
+    #[msg("Stream has been canceled")]
+    StreamCanceled,
+    
+    #[msg("Stream has already been canceled")]
+    StreamAlreadyCanceled,
+    
+    #[msg("Stream is not active")]
+    StreamNotActive,
+    
+    #[msg("Stream has not started yet")]
+    StreamNotStarted,
+    
+    #[msg("Unauthorized operation")]
+    Unauthorized,
+    
+    #[msg("Signer is not the stream sender")]
+    UnauthorizedSender,
+    
+    #[msg("Signer is not the stream recipient")]
+    UnauthorizedRecipient,
+    
+    #[msg("Signer is not allowed to cancel this stream")]
+    UnauthorizedCancel,
+    
+    #[msg("Signer is not allowed to transfer this stream")]
+    UnauthorizedTransfer,
+    
+    #[msg("Invalid recipient")]
+    InvalidRecipient,
+    
+    #[msg("Token account mint does not match the stream")]
+    InvalidMint,
+    
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+    
+    #[msg("Insufficient funds")]
+    InsufficientFunds,
+    
+    #[msg("No tokens available to withdraw")]
+    NoTokensToWithdraw,
+    
+    #[msg("Requested amount exceeds the withdrawable balance")]
+    InsufficientWithdrawableBalance,
+    
+    #[msg("Mathematical operation resulted in overflow")]
+    MathOverflow,
+    
+    #[msg("Escrow still holds tokens")]
+    EscrowNotEmpty,
+    
+    #[msg("Rent destination must be the sender or the stream's rent treasury")]
+    InvalidRentDestination,
+    
+    #[msg("Re-entrant call into a stream that is already being processed")]
+    ReentrancyDetected,
+    
+    #[msg("Escrow balance cannot cover the amounts owed")]
+    InsufficientBalance,
+    
+    #[msg("This stream requires a nonzero receipt hash on every withdrawal")]
+    MissingWithdrawalReceipt,
+    
+    #[msg("Automatic withdrawal frequency has not elapsed since the last withdrawal")]
+    WithdrawalFrequencyNotElapsed,
+    
+    #[msg("The recipient has not accepted this stream")]
+    StreamNotAccepted,
+    
+    #[msg("Stream has already been accepted")]
+    StreamAlreadyAccepted,
+    
+    #[msg("The stream has not been paused longer than its pause limit")]
+    PauseLimitNotReached,
+    
+    #[msg("The sender's rate cap account is required to end a stream that reserved part of it")]
+    RateCapRequired,
+}
+
+impl From<crate::calc::CalcError> for StreamFlowError {
+    fn from(err: crate::calc::CalcError) -> Self {
+        match err {
+            crate::calc::CalcError::Overflow => StreamFlowError::ArithmeticOverflow,
+            crate::calc::CalcError::DivisionByZero => StreamFlowError::DivisionByZero,
+        }
+    }
+}
+
+/// Result type for the pure calculators, composable with `?` into `anchor_lang::Result`
+pub type StreamFlowResult<T> = core::result::Result<T, StreamFlowError>;
 
 #[macro_export]
 macro_rules! require {
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+/// A recipient's explicit acceptance of their share of a split stream
+/// created with `require_acceptance`
+#[derive(Accounts)]
+pub struct AcceptSplitShare<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"split_stream",
+            split_stream.sender.as_ref(),
+            &split_stream.split_id.to_le_bytes(),
+        ],
+        bump = split_stream.bump,
+    )]
+    pub split_stream: Account<'info, SplitStream>,
+
+    pub recipient: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<AcceptSplitShare>) -> Result<()> {
+    let split_stream = &mut ctx.accounts.split_stream;
+    let recipient = ctx.accounts.recipient.key();
+    let current_time = Clock::get()?.unix_timestamp;
+
+    split_stream.accept(&recipient)?;
+
+    emit!(SplitShareAccepted {
+        split_stream: split_stream.key(),
+        recipient,
+        accepted_at: current_time,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct SplitShareAccepted {
+    pub split_stream: Pubkey,
+    pub recipient: Pubkey,
+    pub accepted_at: i64,
+}
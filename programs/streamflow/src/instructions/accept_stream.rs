@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::StreamError;
+
+/// Recipient's explicit acceptance of a stream created with `require_acceptance`
+#[derive(Accounts)]
+pub struct AcceptStream<'info> {
+    #[account(
+        mut,
+        has_one = recipient @ StreamError::InvalidRecipient,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    pub recipient: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<AcceptStream>) -> Result<()> {
+    let stream = &mut ctx.accounts.stream;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    stream.accept(current_time)?;
+
+    emit!(StreamAccepted {
+        stream: stream.key(),
+        recipient: stream.recipient,
+        accepted_at: current_time,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct StreamAccepted {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub accepted_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::simulate_withdraw::simulate_withdrawal;
+    use crate::state::stream::StreamBuilder;
+
+    #[test]
+    fn test_withdraw_gated_until_accepted() {
+        let mut stream = StreamBuilder::new().build();
+        stream.require_acceptance = true;
+        assert_eq!(stream.ensure_accepted().unwrap_err(), StreamError::StreamNotAccepted.into());
+        assert_eq!(
+            simulate_withdrawal(&stream, 150, None, [0; 32]).reason.as_deref(),
+            Some("StreamNotAccepted")
+        );
+
+        stream.accept(120).unwrap();
+        assert!(stream.accepted);
+        assert_eq!(stream.metadata.updated_at, 120);
+        assert!(stream.ensure_accepted().is_ok());
+        assert_eq!(simulate_withdrawal(&stream, 150, None, [0; 32]).plan.gross_amount, 500);
+
+        assert_eq!(stream.accept(130).unwrap_err(), StreamError::StreamAlreadyAccepted.into());
+    }
+
+    #[test]
+    fn test_ungated_stream_needs_no_acceptance() {
+        let stream = StreamBuilder::new().build();
+        assert!(!stream.accepted);
+        assert!(stream.ensure_accepted().is_ok());
+        assert_eq!(simulate_withdrawal(&stream, 150, None, [0; 32]).reason, None);
+    }
+
+    #[test]
+    fn test_cancelled_stream_cannot_be_accepted() {
+        let mut stream = StreamBuilder::new().status(StreamStatus::Cancelled).build();
+        stream.require_acceptance = true;
+        assert_eq!(stream.accept(120).unwrap_err(), StreamError::StreamCanceled.into());
+        assert!(!stream.accepted);
+    }
+
+    fn grant(start_on_acceptance: bool) -> Stream {
+        // 1,000 tokens over 100 seconds with a 20 second cliff
+        let mut stream = StreamBuilder::new()
+            .amount(1_000)
+            .status(StreamStatus::Scheduled)
+            .build();
+        stream.cliff_time = 120;
+        stream.require_acceptance = true;
+        stream.start_on_acceptance = start_on_acceptance;
+        stream
+    }
+
+    #[test]
+    fn test_acceptance_anchors_vesting_window() {
+        for accepted_at in [50, 100, 1_000] {
+            let mut stream = grant(true);
+            stream.accept(accepted_at).unwrap();
+            assert_eq!(stream.start_time, accepted_at);
+            assert_eq!(stream.end_time, accepted_at + 100);
+            assert_eq!(stream.cliff_time, accepted_at + 20);
+            assert_eq!(stream.last_withdrawn_at, accepted_at);
+            assert_eq!(stream.status, StreamStatus::Streaming);
+
+            assert_eq!(stream.calculate_withdrawable_amount(accepted_at + 50).unwrap(), 500);
+            assert_eq!(stream.calculate_withdrawable_amount(accepted_at + 100).unwrap(), 1_000);
+        }
+    }
+
+    #[test]
+    fn test_fixed_schedule_ignores_acceptance_time() {
+        let mut stream = grant(false);
+        stream.accept(1_000).unwrap();
+        assert_eq!((stream.start_time, stream.end_time, stream.cliff_time), (100, 200, 120));
+    }
+
+    #[test]
+    fn test_anchored_stream_rejects_withdrawal_before_acceptance() {
+        let stream = grant(true);
+        assert_eq!(stream.ensure_accepted().unwrap_err(), StreamError::StreamNotAccepted.into());
+        assert_eq!(
+            simulate_withdrawal(&stream, 150, None, [0; 32]).reason.as_deref(),
+            Some("StreamNotAccepted")
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::StreamFlowError;
+
+/// One approver's sign-off towards cancelling a stream with a cancel multisig
+#[derive(Accounts)]
+pub struct ApproveCancel<'info> {
+    #[account(
+        constraint = stream.cancel_threshold > 0 @ StreamFlowError::InvalidMultisigConfiguration,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        init_if_needed,
+        payer = approver,
+        space = CancelApproval::LEN,
+        seeds = [b"cancel_approval", stream.key().as_ref()],
+        bump
+    )]
+    pub cancel_approval: Account<'info, CancelApproval>,
+
+    #[account(mut)]
+    pub approver: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<ApproveCancel>) -> Result<()> {
+    let stream = &ctx.accounts.stream;
+    let approver = ctx.accounts.approver.key();
+    let approval = &mut ctx.accounts.cancel_approval;
+    approval.approve(stream.key(), stream, approver, ctx.bumps.cancel_approval)?;
+
+    emit!(CancelApproved {
+        stream: stream.key(),
+        approver,
+        approvals: approval.approvals.len() as u8,
+        threshold: stream.cancel_threshold,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct CancelApproved {
+    pub stream: Pubkey,
+    pub approver: Pubkey,
+    /// Approvals collected so far, including this one
+    pub approvals: u8,
+    pub threshold: u8,
+}
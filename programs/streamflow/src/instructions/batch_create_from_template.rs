@@ -0,0 +1,435 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, CreateAccount};
+use anchor_spl::token::{self, InitializeAccount3, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::state::utils::check_batch_size;
+use crate::errors::StreamFlowError;
+use super::create_stream::{received_amount, validate_start_time};
+
+/// Accounts each stream of a batch passes in `remaining_accounts`: the pair's
+/// stream counter, the stream PDA and its escrow PDA
+pub const ACCOUNTS_PER_BATCH_STREAM: usize = 3;
+
+/// Size of an SPL token account
+const TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// Create one stream per `(recipient, amount, start_time)` entry from a
+/// template's defaults, e.g. for a cohort of hires with different start dates.
+///
+/// For entry `i`, `remaining_accounts[3 * i..3 * i + 3]` holds the stream
+/// counter, stream and escrow PDAs. Entries that fail validation are skipped
+/// and reported in `TemplateBatchCreated` instead of aborting the batch.
+#[derive(Accounts)]
+pub struct BatchCreateFromTemplate<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"template",
+            authority.key().as_ref(),
+            &template.template_id.to_le_bytes(),
+        ],
+        bump = template.bump,
+        has_one = authority,
+        has_one = mint,
+    )]
+    pub template: Account<'info, StreamTemplate>,
+
+    /// Token account every deposit in the batch is drawn from
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = authority,
+    )]
+    pub funding_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = MintStats::LEN,
+        seeds = [b"mint_stats", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// One stream to create from the template
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TemplateEntry {
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub start_time: i64,
+}
+
+pub fn handler<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, BatchCreateFromTemplate<'info>>,
+    entries: Vec<TemplateEntry>,
+) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    check_batch_size(BatchOp::Create, entries.len())?;
+    require!(
+        ctx.remaining_accounts.len() == entries.len() * ACCOUNTS_PER_BATCH_STREAM,
+        StreamFlowError::InvalidBatchOperation
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let authority = ctx.accounts.authority.key();
+    let mut available = ctx.accounts.funding_token_account.amount;
+    let mut created: Vec<Pubkey> = Vec::with_capacity(entries.len());
+    let mut skipped: Vec<u8> = Vec::new();
+
+    ctx.accounts
+        .mint_stats
+        .initialize_if_needed(ctx.accounts.mint.key(), ctx.bumps.mint_stats);
+
+    for (index, (entry, accounts)) in entries
+        .iter()
+        .zip(ctx.remaining_accounts.chunks(ACCOUNTS_PER_BATCH_STREAM))
+        .enumerate()
+    {
+        let checked = check_template_entry(&authority, entry, current_time, available)
+            .and_then(|_| check_stream_accounts(&authority, &entry.recipient, accounts, ctx.program_id));
+        if let Err(err) = checked {
+            msg!("Skipping template entry {}: {:?}", index, err);
+            skipped.push(index as u8);
+            continue;
+        }
+
+        let stream = create_entry(&ctx, entry, accounts, current_time)?;
+        available -= entry.amount;
+        ctx.accounts.mint_stats.record_deposit(stream.deposited_amount)?;
+        created.push(stream.key());
+
+        ctx.accounts.template.record_stream()?;
+    }
+
+    emit!(TemplateBatchCreated {
+        template: ctx.accounts.template.key(),
+        created,
+        skipped,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Checks on an entry's arguments, made before any account is touched so a
+/// bad entry can be skipped. `available` is what is left in the funding account.
+pub fn check_template_entry(
+    authority: &Pubkey,
+    entry: &TemplateEntry,
+    current_time: i64,
+    available: u64,
+) -> Result<()> {
+    require!(entry.amount > 0, StreamFlowError::InvalidAmount);
+    require!(entry.amount <= available, StreamFlowError::InsufficientFunds);
+    require!(entry.recipient != *authority, StreamFlowError::InvalidRecipient);
+    validate_start_time(entry.start_time, current_time, false, false)
+}
+
+/// The stream's accounts must be the expected PDAs for `recipient`, with no
+/// stream there yet
+pub fn check_stream_accounts<'info>(
+    authority: &Pubkey,
+    recipient: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    program_id: &Pubkey,
+) -> Result<()> {
+    let (counter_info, stream_info, escrow_info) = (&accounts[0], &accounts[1], &accounts[2]);
+
+    let (counter, _) = Pubkey::find_program_address(
+        &[b"stream_counter", authority.as_ref(), recipient.as_ref()],
+        program_id,
+    );
+    require_keys_eq!(counter_info.key(), counter, StreamFlowError::InvalidBatchOperation);
+
+    let nonce = if counter_info.data_is_empty() {
+        0
+    } else {
+        Account::<StreamCounter>::try_from(counter_info)?.count
+    };
+    let (stream, _) = stream_address(authority, recipient, nonce, program_id);
+    require_keys_eq!(stream_info.key(), stream, StreamFlowError::InvalidBatchOperation);
+    require!(stream_info.data_is_empty(), StreamFlowError::StreamAlreadyInitialized);
+
+    let (escrow, _) = Pubkey::find_program_address(&[b"escrow", stream.as_ref()], program_id);
+    require_keys_eq!(escrow_info.key(), escrow, StreamFlowError::InvalidBatchOperation);
+    Ok(())
+}
+
+/// Accounts shared by every stream a batch creates
+pub struct BatchFunding<'a, 'info> {
+    /// Sender of every stream; pays rent and signs the deposits
+    pub authority: &'a AccountInfo<'info>,
+    pub funding_token_account: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+}
+
+/// Create a batch stream's counter (if needed), stream and escrow, and fund it
+/// with `amount`. The returned stream has its parties, escrow, deposit and
+/// seeds set; the caller fills in the schedule and writes it back.
+pub fn open_batch_stream<'info>(
+    funding: &BatchFunding<'_, 'info>,
+    recipient: &Pubkey,
+    amount: u64,
+    accounts: &'info [AccountInfo<'info>],
+    program_id: &Pubkey,
+) -> Result<Account<'info, Stream>> {
+    let (counter_info, stream_info, escrow_info) = (&accounts[0], &accounts[1], &accounts[2]);
+    let authority = funding.authority.key();
+
+    let (_, counter_bump) = Pubkey::find_program_address(
+        &[b"stream_counter", authority.as_ref(), recipient.as_ref()],
+        program_id,
+    );
+    if counter_info.data_is_empty() {
+        create_pda(
+            funding,
+            counter_info,
+            StreamCounter::LEN,
+            program_id,
+            &[b"stream_counter", authority.as_ref(), recipient.as_ref(), &[counter_bump]],
+        )?;
+    }
+    let mut counter = Account::<StreamCounter>::try_from_unchecked(counter_info)?;
+    let nonce = counter.claim_nonce(authority, *recipient, counter_bump)?;
+    counter.exit(program_id)?;
+
+    let (stream_key, stream_bump) = stream_address(&authority, recipient, nonce, program_id);
+    create_pda(
+        funding,
+        stream_info,
+        Stream::LEN,
+        program_id,
+        &[
+            b"stream",
+            authority.as_ref(),
+            recipient.as_ref(),
+            &nonce.to_le_bytes(),
+            &[stream_bump],
+        ],
+    )?;
+
+    let (_, escrow_bump) =
+        Pubkey::find_program_address(&[b"escrow", stream_key.as_ref()], program_id);
+    create_pda(
+        funding,
+        escrow_info,
+        TOKEN_ACCOUNT_LEN,
+        &token::ID,
+        &[b"escrow", stream_key.as_ref(), &[escrow_bump]],
+    )?;
+    token::initialize_account3(CpiContext::new(
+        funding.token_program.clone(),
+        InitializeAccount3 {
+            account: escrow_info.clone(),
+            mint: funding.mint.clone(),
+            authority: stream_info.clone(),
+        },
+    ))?;
+
+    token::transfer(
+        CpiContext::new(
+            funding.token_program.clone(),
+            Transfer {
+                from: funding.funding_token_account.clone(),
+                to: escrow_info.clone(),
+                authority: funding.authority.clone(),
+            },
+        ),
+        amount,
+    )?;
+    let escrow = Account::<TokenAccount>::try_from(escrow_info)?;
+    let deposited_amount = received_amount(0, escrow.amount)?;
+
+    let mut stream = Account::<Stream>::try_from_unchecked(stream_info)?;
+    stream.sender = authority;
+    stream.recipient = *recipient;
+    stream.mint = funding.mint.key();
+    stream.escrow_tokens = escrow_info.key();
+    stream.deposited_amount = deposited_amount;
+    stream.nonce = nonce;
+    stream.bump = stream_bump;
+    stream.escrow_bump = escrow_bump;
+    Ok(stream)
+}
+
+/// Create the entry's accounts and apply the template to its stream
+fn create_entry<'c: 'info, 'info>(
+    ctx: &Context<'_, '_, 'c, 'info, BatchCreateFromTemplate<'info>>,
+    entry: &TemplateEntry,
+    accounts: &'c [AccountInfo<'info>],
+    current_time: i64,
+) -> Result<Account<'info, Stream>> {
+    let funding = BatchFunding {
+        authority: &ctx.accounts.authority.to_account_info(),
+        funding_token_account: &ctx.accounts.funding_token_account.to_account_info(),
+        mint: &ctx.accounts.mint.to_account_info(),
+        token_program: &ctx.accounts.token_program.to_account_info(),
+        system_program: &ctx.accounts.system_program.to_account_info(),
+    };
+    let mut stream = open_batch_stream(&funding, &entry.recipient, entry.amount, accounts, ctx.program_id)?;
+    ctx.accounts.template.apply_to(&mut stream, entry.start_time)?;
+    stream.template = Some(ctx.accounts.template.key());
+    stream.set_created_at(current_time);
+    stream.validate()?;
+    stream.exit(ctx.program_id)?;
+
+    Ok(stream)
+}
+
+/// Allocate a rent-exempt account at a PDA owned by `owner`
+fn create_pda<'info>(
+    funding: &BatchFunding<'_, 'info>,
+    account: &AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            funding.system_program.clone(),
+            CreateAccount {
+                from: funding.authority.clone(),
+                to: account.clone(),
+            },
+            &[seeds],
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        owner,
+    )
+}
+
+#[event]
+pub struct TemplateBatchCreated {
+    pub template: Pubkey,
+    /// Streams created, in entry order
+    pub created: Vec<Pubkey>,
+    /// Indices of the entries that were skipped
+    pub skipped: Vec<u8>,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn template(authority: Pubkey) -> StreamTemplate {
+        StreamTemplate {
+            authority,
+            template_id: 0,
+            mint: Pubkey::new_unique(),
+            deposit_amount: 12_000,
+            duration: 1_000,
+            stream_type: StreamType::Cliff,
+            cliff_offset: 250,
+            cliff_amount: 3_000,
+            rate_amount: 0,
+            rate_interval_in_seconds: 0,
+            cancelable_by_sender: true,
+            cancelable_by_recipient: false,
+            fee_percentage: 0,
+            fee_recipient: None,
+            partner_fee_percentage: 0,
+            partner_fee_recipient: None,
+            onboarding_lamports: 0,
+            active_stream_count: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_template_applied_to_cohort_with_different_start_dates() {
+        let authority = Pubkey::new_unique();
+        let mut template = template(authority);
+        let entries = [
+            TemplateEntry { recipient: Pubkey::new_unique(), amount: 12_000, start_time: 2_000 },
+            TemplateEntry { recipient: Pubkey::new_unique(), amount: 8_000, start_time: 3_000 },
+            TemplateEntry { recipient: Pubkey::new_unique(), amount: 10_000, start_time: 4_500 },
+        ];
+
+        let mut available = 30_000;
+        let mut streams = Vec::new();
+        for entry in &entries {
+            check_template_entry(&authority, entry, 1_000, available).unwrap();
+            available -= entry.amount;
+
+            let mut stream = StreamBuilder::new().amount(entry.amount).build();
+            stream.recipient = entry.recipient;
+            template.apply_to(&mut stream, entry.start_time).unwrap();
+            streams.push(stream);
+            template.active_stream_count += 1;
+        }
+
+        assert_eq!(template.active_stream_count, 3);
+        for (stream, entry) in streams.iter().zip(&entries) {
+            assert_eq!(stream.recipient, entry.recipient);
+            assert_eq!(stream.deposited_amount, entry.amount);
+            assert_eq!(stream.start_time, entry.start_time);
+            assert_eq!(stream.end_time, entry.start_time + 1_000);
+            assert_eq!(stream.cliff_time, entry.start_time + 250);
+            assert_eq!(stream.status, StreamStatus::Scheduled);
+        }
+
+        // Each hire vests on their own schedule
+        assert_eq!(streams[0].calculate_streamed_amount(2_250).unwrap(), 3_000);
+        assert_eq!(streams[0].calculate_streamed_amount(2_625).unwrap(), 7_500);
+        assert_eq!(streams[1].calculate_streamed_amount(2_250).unwrap(), 0);
+        assert_eq!(streams[2].calculate_streamed_amount(5_500).unwrap(), 10_000);
+    }
+
+    #[test]
+    fn test_invalid_entries_are_rejected_individually() {
+        let authority = Pubkey::new_unique();
+        let entry = TemplateEntry { recipient: Pubkey::new_unique(), amount: 100, start_time: 2_000 };
+        assert!(check_template_entry(&authority, &entry, 1_000, 100).is_ok());
+
+        assert_eq!(
+            check_template_entry(&authority, &TemplateEntry { amount: 0, ..entry }, 1_000, 100)
+                .unwrap_err(),
+            StreamFlowError::InvalidAmount.into()
+        );
+        assert_eq!(
+            check_template_entry(&authority, &entry, 1_000, 99).unwrap_err(),
+            StreamFlowError::InsufficientFunds.into()
+        );
+        assert_eq!(
+            check_template_entry(&authority, &TemplateEntry { recipient: authority, ..entry }, 1_000, 100)
+                .unwrap_err(),
+            StreamFlowError::InvalidRecipient.into()
+        );
+        assert_eq!(
+            check_template_entry(&authority, &TemplateEntry { start_time: 500, ..entry }, 1_000, 100)
+                .unwrap_err(),
+            StreamFlowError::InvalidStartTime.into()
+        );
+    }
+
+    #[test]
+    fn test_batch_size_capped() {
+        assert!(check_batch_size(BatchOp::Create, 8).is_ok());
+        assert_eq!(
+            check_batch_size(BatchOp::Create, 9).unwrap_err(),
+            StreamFlowError::BatchOperationLimitExceeded.into()
+        );
+    }
+}
@@ -2,8 +2,9 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
-use crate::errors::StreamError;
-use crate::state::{Stream, StreamStatus};
+use crate::errors::{StreamError, StreamFlowError};
+use crate::state::{CancelApproval, MintStats, ProgramConfig, RecipientRateCap, Stream, StreamStatus};
+use super::withdraw::{load_pegged_accrual, PeggedAccrual};
 
 #[derive(Accounts)]
 pub struct CancelStream<'info> {
@@ -13,16 +14,21 @@ pub struct CancelStream<'info> {
             b"stream",
             stream.sender.as_ref(),
             stream.recipient.as_ref(),
-            &stream.start_time.to_le_bytes(),
+            &stream.nonce.to_le_bytes(),
         ],
         bump = stream.bump,
-        constraint = stream.status == StreamStatus::Active @ StreamError::StreamNotActive,
-        constraint = stream.sender == sender.key() @ StreamError::Unauthorized,
+        constraint = stream.can_cancel(&authority.key()) @ StreamError::Unauthorized,
     )]
     pub stream: Account<'info, Stream>,
 
-    #[account(mut)]
-    pub sender: Signer<'info>,
+    /// Either the sender or the recipient, as permitted by the stream's cancel flags
+    pub authority: Signer<'info>,
+
+    /// CHECK: This is the sender account, validated through the stream
+    #[account(
+        constraint = sender.key() == stream.sender @ StreamError::Unauthorized
+    )]
+    pub sender: AccountInfo<'info>,
 
     /// CHECK: This is the recipient account, validated through the stream
     #[account(
@@ -55,39 +61,109 @@ pub struct CancelStream<'info> {
     )]
     pub escrow_token_account: Account<'info, TokenAccount>,
 
+    /// Sender's aggregate rate cap for this recipient, if one is configured
+    #[account(
+        mut,
+        seeds = [
+            b"rate_cap",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+        ],
+        bump = rate_cap.bump,
+    )]
+    pub rate_cap: Option<Account<'info, RecipientRateCap>>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        mut,
+        seeds = [b"mint_stats", stream.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Approvals collected by `approve_cancel`; required when the stream has a cancel multisig
+    #[account(
+        seeds = [b"cancel_approval", stream.key().as_ref()],
+        bump = cancel_approval.bump,
+    )]
+    pub cancel_approval: Option<Account<'info, CancelApproval>>,
+
+    /// CHECK: Only read for the decimals of a USD-pegged stream
+    #[account(address = stream.mint @ StreamError::InvalidMint)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Pyth price account of a USD-pegged stream, decoded by `oracle::Price`
+    #[account(
+        address = stream.price_feed.unwrap_or_default() @ StreamFlowError::OraclePriceFeedNotFound,
+    )]
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> CancelStream<'info> {
     pub fn cancel_stream(&mut self) -> Result<()> {
-        let stream = &mut self.stream;
         let current_time = Clock::get()?.unix_timestamp;
 
-        // Ensure stream is still active
+        // Scheduled, streaming and paused streams may all be cancelled
         require!(
-            stream.status == StreamStatus::Active,
+            self.stream.can_cancel(&self.authority.key()),
             StreamError::StreamNotActive
         );
+        self.stream.check_transition(StreamStatus::Cancelled)?;
+        self.stream.check_cancel_approvals(self.cancel_approval.as_deref())?;
+
+        // Persist the guard so a re-entrant call through a CPI observes it
+        self.stream.begin_processing()?;
+        self.stream.exit(&crate::ID)?;
 
         // Calculate amounts to distribute
         let (streamed_amount, remaining_amount) = self.calculate_amounts(current_time)?;
 
-        // Transfer streamed amount to recipient if any
-        if streamed_amount > 0 {
-            self.transfer_to_recipient(streamed_amount)?;
-        }
+        // A failed CPI aborts the whole transaction, so a recipient account that
+        // would reject the transfer is detected up front instead of attempted
+        let payout = plan_cancel_payout(
+            streamed_amount,
+            remaining_amount,
+            !self.recipient_token_account.is_frozen(),
+        );
 
-        // Transfer remaining amount back to sender if any
-        if remaining_amount > 0 {
-            self.transfer_to_sender(remaining_amount)?;
-        }
+        // The sender's return never depends on the recipient's account
+        self.transfer_to_sender(payout.to_sender)?;
+        self.transfer_to_recipient(payout.to_recipient)?;
 
         // Update stream status
+        let stream = &mut self.stream;
+        stream.end_processing();
         stream.status = StreamStatus::Cancelled;
         stream.cancelled_at = Some(current_time);
-        stream.withdrawn_amount = stream.withdrawn_amount.checked_add(streamed_amount)
+        stream.withdrawn_amount = stream.withdrawn_amount.checked_add(payout.to_recipient)
+            .ok_or(StreamError::MathOverflow)?;
+        stream.parked_amount = stream.parked_amount.checked_add(payout.parked)
             .ok_or(StreamError::MathOverflow)?;
+        stream.check_withdrawn_within_vested(current_time)?;
+        stream.touch(current_time);
+        self.mint_stats.record_withdrawal(
+            payout.to_recipient.checked_add(payout.to_sender).ok_or(StreamError::MathOverflow)?,
+        )?;
+        stream.release_rate_cap(self.rate_cap.as_deref_mut())?;
+
+        if payout.parked > 0 {
+            emit!(RecipientPayoutParked {
+                stream: stream.key(),
+                recipient: stream.recipient,
+                amount: payout.parked,
+                timestamp: current_time,
+            });
+        }
 
         emit!(StreamCancelledEvent {
             stream: stream.key(),
@@ -95,6 +171,7 @@ impl<'info> CancelStream<'info> {
             recipient: stream.recipient,
             streamed_amount,
             remaining_amount,
+            cancelled_by: self.authority.key(),
             cancelled_at: current_time,
         });
 
@@ -102,74 +179,31 @@ impl<'info> CancelStream<'info> {
     }
 
     fn calculate_amounts(&self, current_time: i64) -> Result<(u64, u64)> {
-        let stream = &self.stream;
-        
-        // Calculate total streamed amount up to cancellation time
-        let elapsed_time = current_time.saturating_sub(stream.start_time);
-        let stream_duration = stream.end_time.saturating_sub(stream.start_time);
-        
-        let streamed_amount = if elapsed_time >= stream_duration {
-            // Stream has completed, all tokens should be streamed
-            stream.amount
-        } else if elapsed_time <= 0 {
-            // Stream hasn't started yet
-            0
-        } else {
-            // Calculate proportional amount based on time elapsed
-            let total_amount = stream.amount as u128;
-            let elapsed = elapsed_time as u128;
-            let duration = stream_duration as u128;
-            
-            ((total_amount * elapsed) / duration) as u64
-        };
-
-        // Subtract already withdrawn amount
-        let available_streamed = streamed_amount.saturating_sub(stream.withdrawn_amount);
-        
-        // Calculate remaining amount in escrow
-        let total_in_escrow = self.escrow_token_account.amount;
-        let remaining_amount = total_in_escrow.saturating_sub(available_streamed);
-
-        Ok((available_streamed, remaining_amount))
+        let pegged = load_pegged_accrual(&self.stream, self.price_feed.as_deref(), &self.mint, current_time)?;
+        cancel_split(&self.stream, current_time, self.escrow_token_account.amount, pegged.as_ref())
     }
 
     fn transfer_to_recipient(&self, amount: u64) -> Result<()> {
-        if amount == 0 {
-            return Ok(());
-        }
-
-        let stream = &self.stream;
-        let seeds = &[
-            b"escrow",
-            stream.key().as_ref(),
-            &[stream.escrow_bump],
-        ];
-        let signer_seeds = &[&seeds[..]];
-
-        let transfer_ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            Transfer {
-                from: self.escrow_token_account.to_account_info(),
-                to: self.recipient_token_account.to_account_info(),
-                authority: self.escrow_token_account.to_account_info(),
-            },
-            signer_seeds,
-        );
-
-        token::transfer(transfer_ctx, amount)?;
-        Ok(())
+        self.transfer_from_escrow(self.recipient_token_account.to_account_info(), amount)
     }
 
     fn transfer_to_sender(&self, amount: u64) -> Result<()> {
+        self.transfer_from_escrow(self.sender_token_account.to_account_info(), amount)
+    }
+
+    /// The escrow is owned by the stream PDA, so transfers are signed with the stream seeds
+    fn transfer_from_escrow(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
         if amount == 0 {
             return Ok(());
         }
 
         let stream = &self.stream;
         let seeds = &[
-            b"escrow",
-            stream.key().as_ref(),
-            &[stream.escrow_bump],
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &stream.nonce.to_le_bytes(),
+            &[stream.bump],
         ];
         let signer_seeds = &[&seeds[..]];
 
@@ -177,21 +211,98 @@ impl<'info> CancelStream<'info> {
             self.token_program.to_account_info(),
             Transfer {
                 from: self.escrow_token_account.to_account_info(),
-                to: self.sender_token_account.to_account_info(),
-                authority: self.escrow_token_account.to_account_info(),
+                to,
+                authority: stream.to_account_info(),
             },
             signer_seeds,
         );
 
-        token::transfer(transfer_ctx, amount)?;
-        Ok(())
+        token::transfer(transfer_ctx, amount)
     }
 }
 
 pub fn handler(ctx: Context<CancelStream>) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
     ctx.accounts.cancel_stream()
 }
 
+/// (recipient, sender) payouts for cancelling `stream` at `current_time`.
+///
+/// The recipient receives what has vested but not yet been withdrawn; the
+/// sender receives the unvested remainder of the deposit. Time spent paused,
+/// including a pause still in progress, does not count toward vesting. A
+/// USD-pegged stream pays the recipient what its `pegged` accrual buys and
+/// returns the rest of the deposit to the sender.
+pub fn cancel_split(
+    stream: &Stream,
+    current_time: i64,
+    escrow_balance: u64,
+    pegged: Option<&PeggedAccrual>,
+) -> Result<(u64, u64)> {
+    let (vested_unwithdrawn, unvested) = match pegged {
+        Some(accrual) => (
+            accrual.amount,
+            stream
+                .deposited_amount
+                .saturating_sub(stream.withdrawn_amount)
+                .saturating_sub(accrual.amount),
+        ),
+        None => {
+            require!(stream.usd_rate_per_second.is_none(), StreamFlowError::OraclePriceFeedNotFound);
+            let streamed_amount = stream.calculate_streamed_amount(current_time)?;
+            (
+                streamed_amount.saturating_sub(stream.withdrawn_amount),
+                stream.deposited_amount.saturating_sub(streamed_amount),
+            )
+        }
+    };
+
+    split_cancel_amounts(vested_unwithdrawn, unvested, escrow_balance)
+}
+
+/// Splits the escrow between recipient and sender on cancellation.
+///
+/// The recipient's vested, unwithdrawn portion is paid first and the sender
+/// receives whatever is left of the unvested share. If the escrow is short,
+/// the sender absorbs the shortfall; if it cannot even cover the recipient,
+/// the cancellation fails with `InsufficientBalance`.
+pub fn split_cancel_amounts(
+    vested_unwithdrawn: u64,
+    unvested: u64,
+    escrow_balance: u64,
+) -> Result<(u64, u64)> {
+    require!(
+        vested_unwithdrawn <= escrow_balance,
+        StreamError::InsufficientBalance
+    );
+    let sender_amount = unvested.min(escrow_balance - vested_unwithdrawn);
+
+    Ok((vested_unwithdrawn, sender_amount))
+}
+
+/// Where a cancellation's funds go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelPayout {
+    pub to_sender: u64,
+    pub to_recipient: u64,
+    /// Recipient's vested portion left in escrow to be claimed later
+    pub parked: u64,
+}
+
+/// Route the cancel payouts, parking the recipient's share in escrow when their
+/// token account cannot currently receive it (e.g. frozen)
+pub fn plan_cancel_payout(
+    recipient_amount: u64,
+    sender_amount: u64,
+    recipient_can_receive: bool,
+) -> CancelPayout {
+    if recipient_can_receive {
+        CancelPayout { to_sender: sender_amount, to_recipient: recipient_amount, parked: 0 }
+    } else {
+        CancelPayout { to_sender: sender_amount, to_recipient: 0, parked: recipient_amount }
+    }
+}
+
 #[event]
 pub struct StreamCancelledEvent {
     pub stream: Pubkey,
@@ -199,6 +310,145 @@ pub struct StreamCancelledEvent {
     pub recipient: Pubkey,
     pub streamed_amount: u64,
     pub remaining_amount: u64,
+    pub cancelled_by: Pubkey,
     pub cancelled_at: i64,
 }
-```
\ No newline at end of file
+
+#[event]
+pub struct RecipientPayoutParked {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    /// 1000 tokens streamed linearly over [100, 200], escrow holding the rest
+    fn stream(withdrawn: u64) -> Stream {
+        StreamBuilder::new()
+            .amount(1000)
+            .withdrawn(withdrawn)
+            .start_time(100)
+            .duration(100)
+            .build()
+    }
+
+    fn escrow_of(stream: &Stream) -> u64 {
+        stream.deposited_amount - stream.withdrawn_amount
+    }
+
+    #[test]
+    fn test_cancel_before_start_returns_everything_to_sender() {
+        let stream = stream(0);
+        assert_eq!(cancel_split(&stream, 50, escrow_of(&stream), None).unwrap(), (0, 1000));
+        assert_eq!(cancel_split(&stream, 100, escrow_of(&stream), None).unwrap(), (0, 1000));
+    }
+
+    #[test]
+    fn test_cancel_mid_stream_splits_proportionally() {
+        let stream = stream(0);
+        assert_eq!(cancel_split(&stream, 125, escrow_of(&stream), None).unwrap(), (250, 750));
+        assert_eq!(cancel_split(&stream, 150, escrow_of(&stream), None).unwrap(), (500, 500));
+    }
+
+    #[test]
+    fn test_cancel_after_end_vests_everything_to_recipient() {
+        let stream = stream(0);
+        assert_eq!(cancel_split(&stream, 200, escrow_of(&stream), None).unwrap(), (1000, 0));
+        assert_eq!(cancel_split(&stream, 10_000, escrow_of(&stream), None).unwrap(), (1000, 0));
+    }
+
+    #[test]
+    fn test_cancel_after_partial_withdrawals() {
+        // 300 already withdrawn; at the midpoint only 200 more is owed
+        let stream = stream(300);
+        assert_eq!(cancel_split(&stream, 150, escrow_of(&stream), None).unwrap(), (200, 500));
+
+        // Withdrawals are never paid out twice after the end either
+        assert_eq!(cancel_split(&stream, 250, escrow_of(&stream), None).unwrap(), (700, 0));
+    }
+
+    #[test]
+    fn test_cancel_split_ignores_escrow_surplus() {
+        // Extra tokens in escrow are not handed to the sender as "unvested"
+        let stream = stream(0);
+        assert_eq!(cancel_split(&stream, 150, 1_200, None).unwrap(), (500, 500));
+    }
+
+    #[test]
+    fn test_split_fully_funded_escrow() {
+        assert_eq!(split_cancel_amounts(400, 600, 1_000).unwrap(), (400, 600));
+    }
+
+    #[test]
+    fn test_split_never_exceeds_escrow() {
+        let (to_recipient, to_sender) = split_cancel_amounts(400, 600, 1_000).unwrap();
+        assert!(to_recipient + to_sender <= 1_000);
+    }
+
+    #[test]
+    fn test_split_underfunded_escrow_pays_recipient_first() {
+        // Escrow is 300 short; the sender's refund absorbs the shortfall
+        assert_eq!(split_cancel_amounts(400, 600, 700).unwrap(), (400, 300));
+        assert_eq!(split_cancel_amounts(400, 600, 400).unwrap(), (400, 0));
+    }
+
+    #[test]
+    fn test_split_escrow_cannot_cover_recipient() {
+        assert_eq!(
+            split_cancel_amounts(400, 600, 399).unwrap_err(),
+            StreamError::InsufficientBalance.into()
+        );
+    }
+
+    #[test]
+    fn test_payout_delivered_when_recipient_can_receive() {
+        assert_eq!(
+            plan_cancel_payout(400, 600, true),
+            CancelPayout { to_sender: 600, to_recipient: 400, parked: 0 }
+        );
+    }
+
+    #[test]
+    fn test_frozen_recipient_does_not_block_sender_return() {
+        let payout = plan_cancel_payout(400, 600, false);
+        assert_eq!(payout.to_sender, 600);
+        assert_eq!(payout.to_recipient, 0);
+        assert_eq!(payout.parked, 400);
+    }
+
+    #[test]
+    fn test_cancel_excludes_paused_time() {
+        // Paused for 20s of the first 70s: only 50s have truly accrued
+        let mut paused_earlier = stream(0);
+        paused_earlier.total_paused_duration = 20;
+        assert_eq!(cancel_split(&paused_earlier, 170, 1000, None).unwrap(), (500, 500));
+
+        // Still paused since t=150 when cancelled at t=180
+        let mut paused_now = stream(0);
+        paused_now.status = StreamStatus::Paused;
+        paused_now.paused_at = Some(150);
+        assert_eq!(cancel_split(&paused_now, 180, 1000, None).unwrap(), (500, 500));
+    }
+
+    #[test]
+    fn test_cancel_split_of_pegged_stream() {
+        // 10,000 tokens over [100, 200], 1,000 already withdrawn
+        let mut stream = StreamBuilder::new().amount(10_000).withdrawn(1_000).build();
+        stream.set_usd_peg(Some(1_000_000), Some(Pubkey::new_unique()), 60).unwrap();
+        let accrual = PeggedAccrual { amount: 100, since: 100, until: 150 };
+
+        // The recipient gets what the accrual buys; the sender the rest
+        assert_eq!(cancel_split(&stream, 150, 9_000, Some(&accrual)).unwrap(), (100, 8_900));
+        // The schedule alone never decides a pegged payout
+        assert_eq!(
+            cancel_split(&stream, 150, 9_000, None).unwrap_err(),
+            StreamFlowError::OraclePriceFeedNotFound.into()
+        );
+    }
+}
+```
@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::StreamError;
+
+/// Lets the recipient collect vested tokens that a cancel could not deliver
+#[derive(Accounts)]
+pub struct ClaimParked<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &stream.nonce.to_le_bytes(),
+        ],
+        bump = stream.bump,
+        has_one = recipient,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump = stream.escrow_bump,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = stream.mint,
+        token::authority = recipient,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    pub recipient: Signer<'info>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        mut,
+        seeds = [b"mint_stats", stream.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<ClaimParked>) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    let amount = ctx.accounts.stream.parked_amount;
+    require!(amount > 0, StreamError::NoTokensToWithdraw);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let stream = &mut ctx.accounts.stream;
+    stream.parked_amount = 0;
+    stream.withdrawn_amount = stream
+        .withdrawn_amount
+        .checked_add(amount)
+        .ok_or(StreamError::MathOverflow)?;
+    stream.touch(current_time);
+    ctx.accounts.mint_stats.record_withdrawal(amount)?;
+
+    let seeds = &[
+        b"stream",
+        stream.sender.as_ref(),
+        stream.recipient.as_ref(),
+        &stream.nonce.to_le_bytes(),
+        &[stream.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: ctx.accounts.recipient_token_account.to_account_info(),
+                authority: stream.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    emit!(ParkedPayoutClaimed {
+        stream: ctx.accounts.stream.key(),
+        recipient: ctx.accounts.recipient.key(),
+        amount,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct ParkedPayoutClaimed {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
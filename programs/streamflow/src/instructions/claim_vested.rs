@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use crate::state::*;
+
+/// Transfer everything vested and not yet claimed to the beneficiary
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"vesting",
+            vesting_schedule.beneficiary.as_ref(),
+            &vesting_schedule.index.to_le_bytes(),
+        ],
+        bump = vesting_schedule.bump,
+        has_one = beneficiary,
+    )]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vesting_escrow",
+            vesting_schedule.key().as_ref(),
+        ],
+        bump = vesting_schedule.escrow_bump,
+        token::mint = vesting_schedule.mint,
+        token::authority = vesting_schedule,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = vesting_schedule.mint,
+        token::authority = beneficiary,
+    )]
+    pub beneficiary_token_account: Account<'info, TokenAccount>,
+
+    pub beneficiary: Signer<'info>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<ClaimVested>) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    let current_time = Clock::get()?.unix_timestamp;
+    let schedule = &mut ctx.accounts.vesting_schedule;
+    let amount = schedule.record_claim(current_time)?;
+
+    let seeds = &[
+        b"vesting".as_ref(),
+        schedule.beneficiary.as_ref(),
+        &schedule.index.to_le_bytes(),
+        &[schedule.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: ctx.accounts.beneficiary_token_account.to_account_info(),
+                authority: schedule.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    emit!(VestedClaimed {
+        vesting_schedule: schedule.key(),
+        beneficiary: schedule.beneficiary,
+        amount,
+        claimed_amount: schedule.claimed_amount,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct VestedClaimed {
+    pub vesting_schedule: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    /// Total claimed from the schedule, including this claim
+    pub claimed_amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,253 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use crate::errors::{StreamError, StreamFlowError};
+use crate::state::{MintStats, ProgramConfig, RecipientRateCap, Stream, StreamStatus};
+use super::cancel_stream::{cancel_split, plan_cancel_payout, RecipientPayoutParked};
+use super::withdraw::load_pegged_accrual;
+
+/// Compliance reversal by the stream's clawback authority: unvested tokens
+/// return to the sender, vested ones still go to the recipient, whatever the
+/// stream's cancel flags say
+#[derive(Accounts)]
+pub struct Clawback<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &stream.nonce.to_le_bytes(),
+        ],
+        bump = stream.bump,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    pub clawback_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = sender_token_account.mint == stream.mint @ StreamError::InvalidMint,
+        constraint = sender_token_account.owner == stream.sender @ StreamError::InvalidTokenAccount,
+    )]
+    pub sender_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = recipient_token_account.mint == stream.mint @ StreamError::InvalidMint,
+        constraint = recipient_token_account.owner == stream.recipient @ StreamError::InvalidTokenAccount,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump = stream.escrow_bump,
+        constraint = escrow_token_account.mint == stream.mint @ StreamError::InvalidMint,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// Sender's aggregate rate cap for this recipient, if one is configured
+    #[account(
+        mut,
+        seeds = [
+            b"rate_cap",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+        ],
+        bump = rate_cap.bump,
+    )]
+    pub rate_cap: Option<Account<'info, RecipientRateCap>>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        mut,
+        seeds = [b"mint_stats", stream.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// CHECK: Only read for the decimals of a USD-pegged stream
+    #[account(address = stream.mint @ StreamError::InvalidMint)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Pyth price account of a USD-pegged stream, decoded by `oracle::Price`
+    #[account(
+        address = stream.price_feed.unwrap_or_default() @ StreamFlowError::OraclePriceFeedNotFound,
+    )]
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<Clawback>) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    let current_time = Clock::get()?.unix_timestamp;
+    ctx.accounts
+        .stream
+        .authorize_clawback(&ctx.accounts.clawback_authority.key())?;
+
+    // Persist the guard so a re-entrant call through a CPI observes it
+    ctx.accounts.stream.begin_processing()?;
+    ctx.accounts.stream.exit(ctx.program_id)?;
+
+    let pegged = load_pegged_accrual(
+        &ctx.accounts.stream,
+        ctx.accounts.price_feed.as_deref(),
+        &ctx.accounts.mint,
+        current_time,
+    )?;
+    let (vested, unvested) = cancel_split(
+        &ctx.accounts.stream,
+        current_time,
+        ctx.accounts.escrow_token_account.amount,
+        pegged.as_ref(),
+    )?;
+    let payout = plan_cancel_payout(vested, unvested, !ctx.accounts.recipient_token_account.is_frozen());
+
+    let accounts = &ctx.accounts;
+    transfer_from_escrow(accounts, accounts.sender_token_account.to_account_info(), payout.to_sender)?;
+    transfer_from_escrow(accounts, accounts.recipient_token_account.to_account_info(), payout.to_recipient)?;
+
+    let stream_key = ctx.accounts.stream.key();
+    let stream = &mut ctx.accounts.stream;
+    stream.end_processing();
+    stream.status = StreamStatus::Cancelled;
+    stream.cancelled_at = Some(current_time);
+    stream.withdrawn_amount = stream.withdrawn_amount.checked_add(payout.to_recipient)
+        .ok_or(StreamError::MathOverflow)?;
+    stream.parked_amount = stream.parked_amount.checked_add(payout.parked)
+        .ok_or(StreamError::MathOverflow)?;
+    stream.check_withdrawn_within_vested(current_time)?;
+    stream.touch(current_time);
+    ctx.accounts.mint_stats.record_withdrawal(
+        payout.to_recipient.checked_add(payout.to_sender).ok_or(StreamError::MathOverflow)?,
+    )?;
+    stream.release_rate_cap(ctx.accounts.rate_cap.as_deref_mut())?;
+
+    if payout.parked > 0 {
+        emit!(RecipientPayoutParked {
+            stream: stream_key,
+            recipient: stream.recipient,
+            amount: payout.parked,
+            timestamp: current_time,
+        });
+    }
+
+    emit!(StreamClawedBack {
+        stream: stream_key,
+        clawback_authority: ctx.accounts.clawback_authority.key(),
+        returned_to_sender: payout.to_sender,
+        released_to_recipient: vested,
+        clawed_back_at: current_time,
+    });
+
+    Ok(())
+}
+
+/// The escrow is owned by the stream PDA, so transfers are signed with the stream seeds
+fn transfer_from_escrow<'info>(accounts: &Clawback<'info>, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let stream = &accounts.stream;
+    let seeds = &[
+        b"stream",
+        stream.sender.as_ref(),
+        stream.recipient.as_ref(),
+        &stream.nonce.to_le_bytes(),
+        &[stream.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.escrow_token_account.to_account_info(),
+                to,
+                authority: stream.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )
+}
+
+#[event]
+pub struct StreamClawedBack {
+    pub stream: Pubkey,
+    pub clawback_authority: Pubkey,
+    /// Unvested tokens returned to the sender
+    pub returned_to_sender: u64,
+    /// Vested, unwithdrawn tokens paid or parked for the recipient
+    pub released_to_recipient: u64,
+    pub clawed_back_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn clawback_stream(authority: Pubkey) -> Stream {
+        let mut stream = StreamBuilder::new().amount(1_000).withdrawn(200).build();
+        stream.cancelable_by_sender = false;
+        stream.cancelable_by_recipient = false;
+        stream.clawback_authority = Some(authority);
+        stream
+    }
+
+    #[test]
+    fn test_clawback_splits_vested_and_unvested() {
+        let authority = Pubkey::new_unique();
+        let stream = clawback_stream(authority);
+        // Not cancelable by either party, but the clawback authority may still act
+        assert!(!stream.can_cancel(&stream.sender));
+        stream.authorize_clawback(&authority).unwrap();
+
+        // 600 vested at t=160, 200 of it already withdrawn
+        assert_eq!(cancel_split(&stream, 160, 800, None).unwrap(), (400, 400));
+        // Before the start everything goes back to the sender
+        let fresh = StreamBuilder::new().amount(1_000).build();
+        assert_eq!(cancel_split(&fresh, 100, 1_000, None).unwrap(), (0, 1_000));
+        // After the end the recipient keeps it all
+        assert_eq!(cancel_split(&stream, 500, 800, None).unwrap(), (800, 0));
+    }
+
+    #[test]
+    fn test_clawback_requires_authority() {
+        let authority = Pubkey::new_unique();
+        let stream = clawback_stream(authority);
+        for other in [stream.sender, stream.recipient, Pubkey::new_unique()] {
+            assert_eq!(
+                stream.authorize_clawback(&other).unwrap_err(),
+                StreamError::UnauthorizedCancel.into()
+            );
+        }
+
+        let mut no_authority = clawback_stream(authority);
+        no_authority.clawback_authority = None;
+        assert_eq!(
+            no_authority.authorize_clawback(&authority).unwrap_err(),
+            StreamError::UnauthorizedCancel.into()
+        );
+
+        let mut cancelled = clawback_stream(authority);
+        cancelled.status = StreamStatus::Cancelled;
+        assert_eq!(
+            cancelled.authorize_clawback(&authority).unwrap_err(),
+            StreamError::StreamNotActive.into()
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount};
+use crate::state::*;
+use crate::errors::{StreamError, StreamFlowError};
+
+#[derive(Accounts)]
+pub struct CloseStream<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &stream.nonce.to_le_bytes(),
+        ],
+        bump = stream.bump,
+        has_one = sender,
+        constraint = stream.is_closable() @ StreamError::StreamNotActive,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump = stream.escrow_bump,
+        constraint = escrow_token_account.amount == 0 @ StreamError::EscrowNotEmpty,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Only receives lamports; must be the sender or the stream's rent treasury.
+    /// Defaults to the sender when omitted.
+    #[account(
+        mut,
+        constraint = stream.is_valid_rent_destination(&rent_destination.key()) @ StreamError::InvalidRentDestination,
+    )]
+    pub rent_destination: Option<UncheckedAccount<'info>>,
+
+    /// Template the stream was created from; required when `stream.template` is set
+    #[account(mut)]
+    pub template: Option<Account<'info, StreamTemplate>>,
+
+    /// Index of the sender's open streams; the stream is dropped from it when given
+    #[account(
+        mut,
+        seeds = [b"stream_directory", stream.sender.as_ref()],
+        bump = stream_directory.bump,
+    )]
+    pub stream_directory: Option<Account<'info, StreamDirectory>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<CloseStream>) -> Result<()> {
+    // Free the stream's slot so the template can eventually be deleted
+    if let Some(template_key) = ctx.accounts.stream.template {
+        let template = ctx
+            .accounts
+            .template
+            .as_mut()
+            .ok_or(StreamFlowError::StreamTemplateNotFound)?;
+        require_keys_eq!(template.key(), template_key, StreamFlowError::StreamTemplateNotFound);
+        template.release_stream();
+    }
+
+    let stream_key = ctx.accounts.stream.key();
+    if let Some(stream_directory) = ctx.accounts.stream_directory.as_mut() {
+        stream_directory.remove(&stream_key);
+    }
+
+    let stream = &ctx.accounts.stream;
+
+    let destination = match &ctx.accounts.rent_destination {
+        Some(rent_destination) => rent_destination.to_account_info(),
+        None => ctx.accounts.sender.to_account_info(),
+    };
+
+    let reclaimed_lamports = stream
+        .to_account_info()
+        .lamports()
+        .checked_add(ctx.accounts.escrow_token_account.to_account_info().lamports())
+        .ok_or(StreamError::MathOverflow)?;
+
+    let seeds = &[
+        b"stream",
+        stream.sender.as_ref(),
+        stream.recipient.as_ref(),
+        &stream.nonce.to_le_bytes(),
+        &[stream.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.escrow_token_account.to_account_info(),
+            destination: destination.clone(),
+            authority: stream.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    ctx.accounts.stream.close(destination.clone())?;
+
+    emit!(StreamClosed {
+        stream: stream_key,
+        rent_destination: destination.key(),
+        reclaimed_lamports,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct StreamClosed {
+    pub stream: Pubkey,
+    pub rent_destination: Pubkey,
+    pub reclaimed_lamports: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    #[test]
+    fn test_fully_withdrawn_stream_is_closable() {
+        let stream = StreamBuilder::new().amount(1000).withdrawn(1000).build();
+        assert!(stream.is_closable());
+    }
+
+    #[test]
+    fn test_cancelled_stream_is_closable() {
+        let stream = StreamBuilder::new().withdrawn(400).status(StreamStatus::Cancelled).build();
+        assert!(stream.is_closable());
+    }
+
+    #[test]
+    fn test_active_stream_with_balance_is_not_closable() {
+        for status in [StreamStatus::Scheduled, StreamStatus::Streaming, StreamStatus::Paused] {
+            let stream = StreamBuilder::new().withdrawn(400).status(status).build();
+            assert!(!stream.is_closable());
+        }
+    }
+}
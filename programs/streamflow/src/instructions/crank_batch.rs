@@ -0,0 +1,351 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+use crate::state::*;
+use crate::state::utils::check_batch_size;
+use crate::errors::{StreamError, StreamFlowError};
+use super::crank_withdrawal::crank_plan;
+use super::withdraw::{
+    apply_withdrawal, load_pegged_accrual, prepare_and_journal_withdrawal, resolve_fee_accounts,
+    transfer_withdrawal, PeggedAccrual, WithdrawEvent, WithdrawalPlan,
+};
+
+/// Accounts per stream in `remaining_accounts`: stream, escrow, destination,
+/// rate cap, price feed, platform fee account and partner fee account
+pub const ACCOUNTS_PER_CRANKED_STREAM: usize = 7;
+
+/// Permissionless payout of many `automatic_withdrawal` streams at once.
+///
+/// For stream `i`, `remaining_accounts[7 * i..7 * i + 7]` holds the stream,
+/// its escrow, its automatic withdrawal destination, the sender's rate cap
+/// for the recipient, the stream's price feed and its platform and partner
+/// fee accounts, with the program id in place of any the stream does not
+/// have. Every stream in a batch streams `mint`. Streams that are not due, or whose accounts do
+/// not match, are skipped and reported in `CrankBatchResult` instead of
+/// aborting the batch.
+#[derive(Accounts)]
+pub struct CrankBatch<'info> {
+    /// CHECK: Mint of every cranked stream, read for the decimals of USD-pegged streams
+    pub mint: UncheckedAccount<'info>,
+
+    /// Program-wide totals for the mint
+    #[account(mut, seeds = [b"mint_stats", mint.key().as_ref()], bump = mint_stats.bump)]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(seeds = [b"program_config"], bump = program_config.bump)]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    /// Anyone may crank; they only pay the transaction fee
+    pub cranker: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, CrankBatch<'info>>,
+    streams: Vec<Pubkey>,
+) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    check_batch_size(BatchOp::Withdraw, streams.len())?;
+    require!(
+        ctx.remaining_accounts.len() == streams.len() * ACCOUNTS_PER_CRANKED_STREAM,
+        StreamFlowError::InvalidBatchOperation
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let mint = &ctx.accounts.mint;
+    let mut paid: Vec<Pubkey> = Vec::with_capacity(streams.len());
+    let mut skipped: Vec<u8> = Vec::new();
+    let mut total_amount: u64 = 0;
+
+    for (index, (stream_key, accounts)) in streams
+        .iter()
+        .zip(ctx.remaining_accounts.chunks(ACCOUNTS_PER_CRANKED_STREAM))
+        .enumerate()
+    {
+        let due = load_cranked_stream(stream_key, accounts, &mint.key(), ctx.program_id)
+            .and_then(|mut stream| {
+                let rate_cap = load_rate_cap(&stream, &accounts[3], ctx.program_id)?;
+                let pegged =
+                    load_cranked_accrual(&stream, &accounts[4], mint, ctx.program_id, current_time)?;
+                let plan = due_plan(&mut stream, *stream_key, current_time, pegged.as_ref())?;
+                let fee_accounts = load_cranked_fee_accounts(&stream, &accounts[5..], ctx.program_id)?;
+                Ok((stream, rate_cap, plan, fee_accounts))
+            });
+        let (mut stream, mut rate_cap, plan, (platform_fee_account, partner_fee_account)) = match due {
+            Ok(due) => due,
+            Err(err) => {
+                msg!("Skipping stream {}: {:?}", index, err);
+                skipped.push(index as u8);
+                continue;
+            }
+        };
+
+        // Persist the guard so a re-entrant call through a CPI observes it
+        stream.begin_processing()?;
+        let completed = apply_withdrawal(
+            &mut stream,
+            *stream_key,
+            &plan,
+            current_time,
+            &mut ctx.accounts.mint_stats,
+            rate_cap.as_deref_mut(),
+        )?;
+        stream.exit(ctx.program_id)?;
+        // Streams later in the batch may share this cap, so persist it now
+        if let Some(rate_cap) = rate_cap.as_ref() {
+            rate_cap.exit(ctx.program_id)?;
+        }
+
+        transfer_withdrawal(
+            ctx.accounts.token_program.to_account_info(),
+            accounts[1].clone(),
+            &stream,
+            accounts[2].clone(),
+            &plan,
+            platform_fee_account,
+            partner_fee_account,
+        )?;
+
+        stream.end_processing();
+        stream.exit(ctx.program_id)?;
+
+        emit!(WithdrawEvent::new(
+            *stream_key,
+            &stream,
+            plan.gross_amount,
+            plan.platform_fee,
+            plan.partner_fee,
+            current_time,
+            [0; 32],
+        ));
+        if let Some(completed) = completed {
+            emit!(completed);
+        }
+
+        total_amount = total_amount.checked_add(plan.gross_amount).ok_or(StreamError::MathOverflow)?;
+        paid.push(*stream_key);
+    }
+
+    emit!(CrankBatchResult {
+        cranker: ctx.accounts.cranker.key(),
+        paid,
+        skipped,
+        total_amount,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Load a stream to crank, checking its mint, escrow and destination accounts
+pub fn load_cranked_stream<'info>(
+    stream_key: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Account<'info, Stream>> {
+    let (stream_info, escrow_info, destination_info) = (&accounts[0], &accounts[1], &accounts[2]);
+    require_keys_eq!(stream_info.key(), *stream_key, StreamFlowError::InvalidBatchOperation);
+
+    let stream = Account::<Stream>::try_from(stream_info)?;
+    require_keys_eq!(stream.mint, *mint, StreamFlowError::InvalidTokenMint);
+    require_keys_eq!(
+        escrow_info.key(),
+        stream.escrow_address(stream_key, program_id)?,
+        StreamFlowError::InvalidBatchOperation
+    );
+    require_keys_eq!(
+        destination_info.key(),
+        stream.auto_withdraw_token_account(),
+        StreamFlowError::InvalidTokenAccountOwner
+    );
+    let destination = Account::<TokenAccount>::try_from(destination_info)?;
+    require_keys_eq!(destination.mint, stream.mint, StreamFlowError::InvalidTokenMint);
+    Ok(stream)
+}
+
+/// Load the sender's rate cap for a cranked stream, where the program id
+/// stands in for a stream without one
+pub fn load_rate_cap<'info>(
+    stream: &Stream,
+    rate_cap_info: &'info AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> Result<Option<Account<'info, RecipientRateCap>>> {
+    if rate_cap_info.key() == *program_id {
+        return Ok(None);
+    }
+    let rate_cap = Account::<RecipientRateCap>::try_from(rate_cap_info)?;
+    let expected = Pubkey::create_program_address(
+        &[
+            b"rate_cap",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &[rate_cap.bump],
+        ],
+        program_id,
+    )
+    .map_err(|_| StreamFlowError::InvalidBatchOperation)?;
+    require_keys_eq!(rate_cap_info.key(), expected, StreamFlowError::InvalidBatchOperation);
+    Ok(Some(rate_cap))
+}
+
+/// Price the accrual of a cranked USD-pegged stream, where the program id
+/// stands in for the price feed of a stream without a peg
+pub fn load_cranked_accrual(
+    stream: &Stream,
+    price_feed_info: &AccountInfo,
+    mint: &AccountInfo,
+    program_id: &Pubkey,
+    current_time: i64,
+) -> Result<Option<PeggedAccrual>> {
+    let price_feed = if price_feed_info.key() == *program_id {
+        None
+    } else {
+        require_keys_eq!(
+            price_feed_info.key(),
+            stream.price_feed.unwrap_or_default(),
+            StreamFlowError::OraclePriceFeedNotFound
+        );
+        Some(price_feed_info)
+    };
+    load_pegged_accrual(stream, price_feed, mint, current_time)
+}
+
+/// Fee accounts of a cranked stream, where the program id stands in for a
+/// fee account the stream does not need
+pub fn load_cranked_fee_accounts<'info>(
+    stream: &Stream,
+    fee_infos: &[AccountInfo<'info>],
+    program_id: &Pubkey,
+) -> Result<(Option<AccountInfo<'info>>, Option<AccountInfo<'info>>)> {
+    let given: Vec<AccountInfo<'info>> = fee_infos
+        .iter()
+        .filter(|info| info.key() != *program_id)
+        .cloned()
+        .collect();
+    let (platform, partner) = resolve_fee_accounts(stream, &given, false)?;
+    Ok((platform.cloned(), partner.cloned()))
+}
+
+/// What a batch crank at `current_time` pays `stream`, applying the same
+/// checks as a single crank. Fails when the stream is not due.
+pub fn due_plan(
+    stream: &mut Stream,
+    stream_key: Pubkey,
+    current_time: i64,
+    pegged: Option<&PeggedAccrual>,
+) -> Result<WithdrawalPlan> {
+    require!(
+        stream.is_active() || stream.status == StreamStatus::Scheduled,
+        StreamError::StreamNotActive
+    );
+    stream.check_withdrawal_receipt(&[0; 32])?;
+    stream.ensure_accepted()?;
+    prepare_and_journal_withdrawal(stream, stream_key, current_time)?;
+    crank_plan(stream, current_time, pegged)
+}
+
+#[event]
+pub struct CrankBatchResult {
+    pub cranker: Pubkey,
+    /// Streams paid out, in request order
+    pub paid: Vec<Pubkey>,
+    /// Indices of the streams that were skipped
+    pub skipped: Vec<u8>,
+    /// Tokens paid across all streams
+    pub total_amount: u64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn auto_stream(withdrawal_frequency: u64) -> Stream {
+        let mut stream = StreamBuilder::new()
+            .amount(1_000)
+            .status(StreamStatus::Scheduled)
+            .start_time(1_000)
+            .duration(1_000)
+            .build();
+        stream.automatic_withdrawal = true;
+        stream.withdrawal_frequency = withdrawal_frequency;
+        stream
+    }
+
+    /// Amounts paid per stream, as the handler decides them; `None` when skipped
+    fn crank_all(streams: &mut [Stream], current_time: i64) -> Vec<Option<u64>> {
+        streams
+            .iter_mut()
+            .map(|stream| due_plan(stream, Pubkey::default(), current_time, None).ok().map(|plan| plan.gross_amount))
+            .collect()
+    }
+
+    #[test]
+    fn test_only_due_streams_are_paid() {
+        let mut manual = auto_stream(0);
+        manual.automatic_withdrawal = false;
+        let mut cancelled = auto_stream(0);
+        cancelled.status = StreamStatus::Cancelled;
+        let mut awaiting_acceptance = auto_stream(0);
+        awaiting_acceptance.require_acceptance = true;
+
+        let mut streams = vec![
+            auto_stream(100),
+            auto_stream(500),
+            manual,
+            auto_stream(200),
+            cancelled,
+            awaiting_acceptance,
+        ];
+        assert_eq!(
+            crank_all(&mut streams, 1_200),
+            vec![Some(200), None, None, Some(200), None, None]
+        );
+        // Due streams started streaming; the rest are untouched
+        assert_eq!(streams[0].status, StreamStatus::Streaming);
+        assert_eq!(streams[4].status, StreamStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_streams_not_started_are_skipped() {
+        let mut streams = vec![auto_stream(0), auto_stream(0)];
+        streams[1].start_time = 900;
+        assert_eq!(crank_all(&mut streams, 950), vec![None, None]);
+        assert_eq!(
+            due_plan(&mut auto_stream(0), Pubkey::default(), 950, None).unwrap_err(),
+            StreamError::StreamNotStarted.into()
+        );
+    }
+
+    #[test]
+    fn test_batch_size_is_capped() {
+        assert!(check_batch_size(BatchOp::Withdraw, 20).is_ok());
+        assert_eq!(
+            check_batch_size(BatchOp::Withdraw, 21).unwrap_err(),
+            StreamFlowError::BatchOperationLimitExceeded.into()
+        );
+    }
+
+    #[test]
+    fn test_batch_charges_fees() {
+        let mut stream = StreamBuilder::new()
+            .amount(20_000)
+            .start_time(1_000)
+            .duration(1_000)
+            .fees(50, 100)
+            .build();
+        stream.automatic_withdrawal = true;
+        stream.partner_fee_active = true;
+        stream.pending_fee = Some((100, 1_200));
+
+        // A due fee change applies before the payout, as on a single crank
+        let plan = due_plan(&mut stream, Pubkey::default(), 1_500, None).unwrap();
+        assert_eq!(stream.pending_fee, None);
+        assert_eq!(stream.amendment_count, 1);
+        assert_eq!(plan.gross_amount, 10_000);
+        assert_eq!((plan.platform_fee, plan.partner_fee, plan.net_amount), (100, 100, 9_800));
+    }
+}
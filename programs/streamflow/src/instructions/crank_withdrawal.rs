@@ -0,0 +1,242 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+use crate::state::*;
+use crate::errors::{StreamError, StreamFlowError};
+use super::withdraw::{
+    apply_withdrawal, load_pegged_accrual, plan_withdrawal, prepare_and_journal_withdrawal,
+    resolve_fee_accounts, transfer_withdrawal, PeggedAccrual, WithdrawEvent, WithdrawalPlan,
+};
+
+/// Permissionless payout for `automatic_withdrawal` streams, letting keepers
+/// pay recipients out every `withdrawal_frequency` seconds. Fees are taken as
+/// for `withdraw`, with the fee accounts in `remaining_accounts`.
+#[derive(Accounts)]
+pub struct CrankWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &stream.nonce.to_le_bytes(),
+        ],
+        bump = stream.bump,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump = stream.escrow_bump,
+        token::mint = stream.mint,
+        token::authority = stream,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// The recipient's automatic withdrawal destination
+    #[account(
+        mut,
+        token::mint = stream.mint,
+        constraint = destination_token_account.key() == stream.auto_withdraw_token_account()
+            @ StreamFlowError::InvalidTokenAccountOwner,
+    )]
+    pub destination_token_account: Account<'info, TokenAccount>,
+
+    /// Sender's aggregate rate cap for this recipient, if one is configured
+    #[account(
+        mut,
+        seeds = [
+            b"rate_cap",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+        ],
+        bump = rate_cap.bump,
+    )]
+    pub rate_cap: Option<Account<'info, RecipientRateCap>>,
+
+    /// CHECK: Only read for the decimals of a USD-pegged stream
+    #[account(address = stream.mint @ StreamError::InvalidMint)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Pyth price account of a USD-pegged stream, decoded by `oracle::Price`
+    #[account(
+        address = stream.price_feed.unwrap_or_default() @ StreamFlowError::OraclePriceFeedNotFound,
+    )]
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        mut,
+        seeds = [b"mint_stats", stream.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Anyone may crank; they only pay the transaction fee
+    pub cranker: Signer<'info>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, CrankWithdrawal<'info>>,
+) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    // Cranks carry no invoice, so receipt-mode streams must be claimed manually
+    ctx.accounts.stream.check_withdrawal_receipt(&[0; 32])?;
+    ctx.accounts.stream.ensure_accepted()?;
+
+    // Persist the guard so a re-entrant call through a CPI observes it
+    ctx.accounts.stream.begin_processing()?;
+    ctx.accounts.stream.exit(ctx.program_id)?;
+
+    let stream_key = ctx.accounts.stream.key();
+    let current_time = Clock::get()?.unix_timestamp;
+    prepare_and_journal_withdrawal(&mut ctx.accounts.stream, stream_key, current_time)?;
+    let pegged = load_pegged_accrual(
+        &ctx.accounts.stream,
+        ctx.accounts.price_feed.as_deref(),
+        &ctx.accounts.mint,
+        current_time,
+    )?;
+    let stream = &mut ctx.accounts.stream;
+
+    let plan = crank_plan(stream, current_time, pegged.as_ref())?;
+    let (platform_fee_account, partner_fee_account) =
+        resolve_fee_accounts(stream, ctx.remaining_accounts, false)?;
+
+    let completed = apply_withdrawal(
+        stream,
+        stream_key,
+        &plan,
+        current_time,
+        &mut ctx.accounts.mint_stats,
+        ctx.accounts.rate_cap.as_deref_mut(),
+    )?;
+    transfer_withdrawal(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.escrow_token_account.to_account_info(),
+        stream,
+        ctx.accounts.destination_token_account.to_account_info(),
+        &plan,
+        platform_fee_account.cloned(),
+        partner_fee_account.cloned(),
+    )?;
+
+    stream.end_processing();
+
+    emit!(WithdrawEvent::new(
+        stream_key,
+        stream,
+        plan.gross_amount,
+        plan.platform_fee,
+        plan.partner_fee,
+        current_time,
+        [0; 32],
+    ));
+    if let Some(completed) = completed {
+        emit!(completed);
+    }
+
+    Ok(())
+}
+
+/// What a crank at `current_time` pays out: everything withdrawable, fees
+/// included, once `withdrawal_frequency` has passed since the last withdrawal
+pub fn crank_plan(
+    stream: &Stream,
+    current_time: i64,
+    pegged: Option<&PeggedAccrual>,
+) -> Result<WithdrawalPlan> {
+    require!(stream.automatic_withdrawal, StreamFlowError::FeatureNotEnabled);
+    require!(current_time >= stream.start_time, StreamError::StreamNotStarted);
+
+    let since_last = current_time.saturating_sub(stream.last_withdrawn_at);
+    require!(
+        since_last >= 0 && since_last as u64 >= stream.withdrawal_frequency,
+        StreamError::WithdrawalFrequencyNotElapsed
+    );
+    require!(stream.is_active(), StreamError::StreamNotActive);
+
+    plan_withdrawal(stream, current_time, None, pegged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn auto_stream() -> Stream {
+        let mut stream = StreamBuilder::new().amount(1_000).start_time(1_000).duration(1_000).build();
+        stream.automatic_withdrawal = true;
+        stream.withdrawal_frequency = 100;
+        stream
+    }
+
+    #[test]
+    fn test_crank_respects_frequency() {
+        let mut stream = auto_stream();
+        assert_eq!(
+            crank_plan(&stream, 1_099, None).unwrap_err(),
+            StreamError::WithdrawalFrequencyNotElapsed.into()
+        );
+        assert_eq!(crank_plan(&stream, 1_100, None).unwrap().gross_amount, 100);
+
+        // The gate restarts from the last withdrawal
+        stream.withdrawn_amount = 100;
+        stream.last_withdrawn_at = 1_100;
+        assert_eq!(
+            crank_plan(&stream, 1_150, None).unwrap_err(),
+            StreamError::WithdrawalFrequencyNotElapsed.into()
+        );
+    }
+
+    #[test]
+    fn test_crank_withdraws_accrued_amount() {
+        let mut stream = auto_stream();
+        stream.withdrawn_amount = 100;
+        stream.last_withdrawn_at = 1_100;
+
+        // A late crank pays everything accrued since the last withdrawal
+        assert_eq!(crank_plan(&stream, 1_450, None).unwrap().gross_amount, 350);
+        assert_eq!(crank_plan(&stream, 9_000, None).unwrap().gross_amount, 900);
+    }
+
+    #[test]
+    fn test_crank_requires_automatic_withdrawal() {
+        let mut stream = auto_stream();
+        stream.automatic_withdrawal = false;
+        assert_eq!(
+            crank_plan(&stream, 1_500, None).unwrap_err(),
+            StreamFlowError::FeatureNotEnabled.into()
+        );
+    }
+
+    #[test]
+    fn test_crank_charges_fees() {
+        let mut stream = StreamBuilder::new()
+            .amount(20_000)
+            .start_time(1_000)
+            .duration(1_000)
+            .fees(50, 100)
+            .build();
+        stream.automatic_withdrawal = true;
+        stream.partner_fee_active = true;
+
+        // The same fees a recipient's own withdrawal pays
+        let plan = crank_plan(&stream, 1_500, None).unwrap();
+        assert_eq!(plan, plan_withdrawal(&stream, 1_500, None, None).unwrap());
+        assert_eq!(plan.gross_amount, 10_000);
+        assert_eq!((plan.platform_fee, plan.partner_fee, plan.net_amount), (50, 100, 9_850));
+    }
+}
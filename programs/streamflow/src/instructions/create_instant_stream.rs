@@ -0,0 +1,232 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::StreamFlowError;
+use super::create_stream::{received_amount, validate_start_time};
+
+/// Airdrop-style stream whose whole deposit is claimable at `start_time`
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey, amount: u64)]
+pub struct CreateInstantStream<'info> {
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = StreamCounter::LEN,
+        seeds = [
+            b"stream_counter",
+            sender.key().as_ref(),
+            recipient.as_ref(),
+        ],
+        bump
+    )]
+    pub stream_counter: Account<'info, StreamCounter>,
+
+    #[account(
+        init,
+        payer = sender,
+        space = Stream::LEN,
+        seeds = [
+            b"stream",
+            sender.key().as_ref(),
+            recipient.as_ref(),
+            &stream_counter.count.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        init,
+        payer = sender,
+        token::mint = mint,
+        token::authority = stream,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = sender_token_account.mint == mint.key(),
+        constraint = sender_token_account.owner == sender.key(),
+        constraint = sender_token_account.amount >= amount @ StreamFlowError::InsufficientFunds
+    )]
+    pub sender_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = MintStats::LEN,
+        seeds = [b"mint_stats", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<CreateInstantStream>,
+    recipient: Pubkey,
+    amount: u64,
+    start_time: i64,
+    cancelable_by_sender: bool,
+) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(amount > 0, StreamFlowError::InvalidAmount);
+    validate_start_time(start_time, current_time, false, false)?;
+    require!(
+        recipient != ctx.accounts.sender.key(),
+        StreamFlowError::InvalidRecipient
+    );
+
+    let nonce = ctx.accounts.stream_counter.claim_nonce(
+        ctx.accounts.sender.key(),
+        recipient,
+        ctx.bumps.stream_counter,
+    )?;
+
+    let balance_before = ctx.accounts.escrow_token_account.amount;
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.sender_token_account.to_account_info(),
+                to: ctx.accounts.escrow_token_account.to_account_info(),
+                authority: ctx.accounts.sender.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+    ctx.accounts.escrow_token_account.reload()?;
+    let deposited_amount = received_amount(balance_before, ctx.accounts.escrow_token_account.amount)?;
+
+    let stream = &mut ctx.accounts.stream;
+    init_instant_stream(
+        stream,
+        ctx.accounts.sender.key(),
+        recipient,
+        ctx.accounts.mint.key(),
+        ctx.accounts.escrow_token_account.key(),
+        deposited_amount,
+        start_time,
+        current_time,
+    );
+    stream.cancelable_by_sender = cancelable_by_sender;
+    stream.nonce = nonce;
+    stream.bump = ctx.bumps.stream;
+    stream.escrow_bump = ctx.bumps.escrow_token_account;
+    stream.validate()?;
+
+    let mint_stats = &mut ctx.accounts.mint_stats;
+    mint_stats.initialize_if_needed(ctx.accounts.mint.key(), ctx.bumps.mint_stats);
+    mint_stats.record_deposit(deposited_amount)?;
+
+    emit!(InstantStreamCreated {
+        stream: stream.key(),
+        sender: stream.sender,
+        recipient,
+        mint: stream.mint,
+        amount: deposited_amount,
+        claimable_at: start_time,
+    });
+
+    Ok(())
+}
+
+/// Populate a freshly initialized stream as an instant stream
+#[allow(clippy::too_many_arguments)]
+pub fn init_instant_stream(
+    stream: &mut Stream,
+    sender: Pubkey,
+    recipient: Pubkey,
+    mint: Pubkey,
+    escrow: Pubkey,
+    deposited_amount: u64,
+    start_time: i64,
+    current_time: i64,
+) {
+    stream.sender = sender;
+    stream.recipient = recipient;
+    stream.mint = mint;
+    stream.escrow_tokens = escrow;
+    stream.deposited_amount = deposited_amount;
+    stream.withdrawn_amount = 0;
+    stream.start_time = start_time;
+    stream.end_time = start_time;
+    stream.last_withdrawn_at = start_time;
+    stream.stream_type = StreamType::Instant;
+    stream.status = StreamStatus::Streaming;
+    stream.allow_partial_withdraw = true;
+    stream.set_created_at(current_time);
+}
+
+#[event]
+pub struct InstantStreamCreated {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub claimable_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn instant_stream(amount: u64, start_time: i64) -> Stream {
+        let mut stream = StreamBuilder::new().build();
+        init_instant_stream(
+            &mut stream,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            amount,
+            start_time,
+            start_time - 10,
+        );
+        stream
+    }
+
+    #[test]
+    fn test_full_amount_claimable_at_start() {
+        let stream = instant_stream(5_000, 1_000);
+        assert_eq!(stream.stream_type, StreamType::Instant);
+        assert_eq!(stream.withdrawable_amount(999).unwrap(), 0);
+        assert_eq!(stream.withdrawable_amount(1_000).unwrap(), 5_000);
+        assert_eq!(stream.withdrawable_amount(50_000).unwrap(), 5_000);
+    }
+
+    #[test]
+    fn test_instant_stream_completes_on_first_claim() {
+        let mut stream = instant_stream(5_000, 1_000);
+        let claimable = stream.withdrawable_amount(1_000).unwrap();
+
+        stream.withdrawn_amount += claimable;
+        assert!(stream.complete_if_fully_withdrawn(Pubkey::new_unique(), 1_000, None).unwrap().is_some());
+        assert_eq!(stream.status, StreamStatus::Completed);
+        assert_eq!(stream.withdrawable_amount(2_000).unwrap(), 0);
+    }
+}
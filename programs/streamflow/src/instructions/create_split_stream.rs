@@ -0,0 +1,159 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::StreamFlowError;
+use super::create_stream::{received_amount, validate_start_time};
+
+/// Linear stream whose vested amount is shared by weight between recipients
+#[derive(Accounts)]
+#[instruction(split_id: u64, recipients: Vec<(Pubkey, u16)>, deposit_amount: u64)]
+pub struct CreateSplitStream<'info> {
+    #[account(
+        init,
+        payer = sender,
+        space = SplitStream::space(recipients.len()),
+        seeds = [
+            b"split_stream",
+            sender.key().as_ref(),
+            &split_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub split_stream: Account<'info, SplitStream>,
+
+    #[account(
+        init,
+        payer = sender,
+        token::mint = mint,
+        token::authority = split_stream,
+        seeds = [
+            b"escrow",
+            split_stream.key().as_ref(),
+        ],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = sender_token_account.mint == mint.key(),
+        constraint = sender_token_account.owner == sender.key(),
+        constraint = sender_token_account.amount >= deposit_amount @ StreamFlowError::InsufficientFunds
+    )]
+    pub sender_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = MintStats::LEN,
+        seeds = [b"mint_stats", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<CreateSplitStream>,
+    split_id: u64,
+    recipients: Vec<(Pubkey, u16)>,
+    deposit_amount: u64,
+    start_time: i64,
+    end_time: i64,
+    fee_config: FeeConfig,
+    require_acceptance: bool,
+    min_withdrawal_interval: u64,
+) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(deposit_amount > 0, StreamFlowError::InvalidAmount);
+    validate_start_time(start_time, current_time, false, false)?;
+    require!(end_time > start_time, StreamFlowError::InvalidEndTime);
+    SplitStream::validate_recipients(&recipients)?;
+    require!(
+        recipients.iter().all(|(recipient, _)| *recipient != ctx.accounts.sender.key()),
+        StreamFlowError::InvalidRecipient
+    );
+
+    let balance_before = ctx.accounts.escrow_token_account.amount;
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.sender_token_account.to_account_info(),
+                to: ctx.accounts.escrow_token_account.to_account_info(),
+                authority: ctx.accounts.sender.to_account_info(),
+            },
+        ),
+        deposit_amount,
+    )?;
+    ctx.accounts.escrow_token_account.reload()?;
+    let deposited_amount = received_amount(balance_before, ctx.accounts.escrow_token_account.amount)?;
+
+    let split_stream = &mut ctx.accounts.split_stream;
+    split_stream.sender = ctx.accounts.sender.key();
+    split_stream.mint = ctx.accounts.mint.key();
+    split_stream.escrow_tokens = ctx.accounts.escrow_token_account.key();
+    split_stream.split_id = split_id;
+    split_stream.deposited_amount = deposited_amount;
+    split_stream.start_time = start_time;
+    split_stream.end_time = end_time;
+    split_stream.recipients = recipients
+        .iter()
+        .map(|(recipient, weight_bps)| SplitShare {
+            recipient: *recipient,
+            weight_bps: *weight_bps,
+            withdrawn_amount: 0,
+            accepted: false,
+            last_withdrawn_at: start_time,
+        })
+        .collect();
+    split_stream.apply_fee_config(&fee_config)?;
+    split_stream.require_acceptance = require_acceptance;
+    split_stream.min_withdrawal_interval = min_withdrawal_interval;
+    split_stream.bump = ctx.bumps.split_stream;
+    split_stream.escrow_bump = ctx.bumps.escrow_token_account;
+
+    let mint_stats = &mut ctx.accounts.mint_stats;
+    mint_stats.initialize_if_needed(ctx.accounts.mint.key(), ctx.bumps.mint_stats);
+    mint_stats.record_deposit(deposited_amount)?;
+
+    emit!(SplitStreamCreated {
+        split_stream: split_stream.key(),
+        sender: split_stream.sender,
+        mint: split_stream.mint,
+        deposited_amount,
+        recipient_count: recipients.len() as u16,
+        start_time,
+        end_time,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct SplitStreamCreated {
+    pub split_stream: Pubkey,
+    pub sender: Pubkey,
+    pub mint: Pubkey,
+    pub deposited_amount: u64,
+    pub recipient_count: u16,
+    pub start_time: i64,
+    pub end_time: i64,
+}
@@ -1,5 +1,6 @@
 ```rust
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 use crate::state::*;
 use crate::errors::StreamFlowError;
@@ -19,6 +20,19 @@ use crate::errors::StreamFlowError;
     stream_name: String,
 )]
 pub struct CreateStream<'info> {
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = StreamCounter::LEN,
+        seeds = [
+            b"stream_counter",
+            sender.key().as_ref(),
+            recipient.as_ref(),
+        ],
+        bump
+    )]
+    pub stream_counter: Account<'info, StreamCounter>,
+
     #[account(
         init,
         payer = sender,
@@ -27,7 +41,7 @@ pub struct CreateStream<'info> {
             b"stream",
             sender.key().as_ref(),
             recipient.as_ref(),
-            stream_name.as_bytes(),
+            &stream_counter.count.to_le_bytes(),
         ],
         bump
     )]
@@ -62,6 +76,45 @@ pub struct CreateStream<'info> {
 
     pub mint: Account<'info, anchor_spl::token::Mint>,
 
+    /// Sender's aggregate rate cap for this recipient, if one is configured
+    #[account(
+        mut,
+        seeds = [
+            b"rate_cap",
+            sender.key().as_ref(),
+            recipient.as_ref(),
+        ],
+        bump = rate_cap.bump,
+    )]
+    pub rate_cap: Option<Account<'info, RecipientRateCap>>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = MintStats::LEN,
+        seeds = [b"mint_stats", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Index of the sender's open streams
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = StreamDirectory::LEN,
+        seeds = [b"stream_directory", sender.key().as_ref()],
+        bump
+    )]
+    pub stream_directory: Account<'info, StreamDirectory>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
@@ -80,44 +133,51 @@ pub fn create_stream(
     transferable_by_sender: bool,
     transferable_by_recipient: bool,
     stream_name: String,
+    rent_treasury: Option<Pubkey>,
+    interest_rate_bps: u16,
+    allow_distant_start: bool,
+    can_topup: bool,
+    fee_config: FeeConfig,
+    receipt_required: bool,
+    can_update_rate: bool,
+    lock_rate_after_start: bool,
+    allow_backdated: bool,
+    allow_partial_withdraw: bool,
+    automatic_withdrawal: bool,
+    withdrawal_frequency: u64,
+    require_acceptance: bool,
+    cancel_approvers: Vec<Pubkey>,
+    cancel_threshold: u8,
+    clawback_authority: Option<Pubkey>,
+    min_withdrawal_interval: u64,
+    usd_rate_per_second: Option<u64>,
+    price_feed: Option<Pubkey>,
+    max_price_age: u64,
+    max_pause_duration: u64,
+    start_on_acceptance: bool,
 ) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+
     let clock = Clock::get()?;
     let current_time = clock.unix_timestamp;
 
     // Validation checks
-    require!(
-        deposit_amount > 0,
-        StreamFlowError::InvalidDepositAmount
-    );
-
-    require!(
-        start_time >= current_time,
-        StreamFlowError::InvalidStartTime
-    );
+    validate_start_time(start_time, current_time, allow_distant_start, allow_backdated)?;
 
     require!(
         end_time > start_time,
         StreamFlowError::InvalidEndTime
     );
 
-    require!(
-        cliff_time >= start_time && cliff_time <= end_time,
-        StreamFlowError::InvalidCliffTime
-    );
+    validate_stream_bounds(start_time, end_time, deposit_amount)?;
 
-    require!(
-        cliff_amount <= deposit_amount,
-        StreamFlowError::InvalidCliffAmount
-    );
+    ctx.accounts.program_config.check_interest_rate(interest_rate_bps)?;
 
-    require!(
-        stream_name.len() <= 64,
-        StreamFlowError::StreamNameTooLong
-    );
+    validate_cliff(start_time, end_time, cliff_time, cliff_amount, deposit_amount)?;
 
     require!(
         recipient != ctx.accounts.sender.key(),
-        StreamFlowError::SenderCannotBeRecipient
+        StreamFlowError::InvalidRecipient
     );
 
     // Calculate stream rate (tokens per second)
@@ -128,31 +188,65 @@ pub fn create_stream(
         0
     };
 
+    // Claim the next nonce for this (sender, recipient) pair
+    let nonce = ctx.accounts.stream_counter.claim_nonce(
+        ctx.accounts.sender.key(),
+        recipient,
+        ctx.bumps.stream_counter,
+    )?;
+
     // Initialize stream account
     let stream = &mut ctx.accounts.stream;
     stream.sender = ctx.accounts.sender.key();
     stream.recipient = recipient;
     stream.mint = ctx.accounts.mint.key();
-    stream.escrow_token_account = ctx.accounts.escrow_token_account.key();
-    stream.deposit_amount = deposit_amount;
+    stream.escrow_tokens = ctx.accounts.escrow_token_account.key();
+    stream.deposited_amount = deposit_amount;
     stream.withdrawn_amount = 0;
     stream.start_time = start_time;
     stream.end_time = end_time;
+    stream.last_withdrawn_at = start_time;
     stream.cliff_time = cliff_time;
     stream.cliff_amount = cliff_amount;
-    stream.rate = stream_rate;
+    stream.stream_type = if cliff_amount > 0 { StreamType::Cliff } else { StreamType::Linear };
+    stream.status = StreamStatus::Scheduled;
+    stream.activate_if_started(current_time);
     stream.cancelable_by_sender = cancelable_by_sender;
     stream.cancelable_by_recipient = cancelable_by_recipient;
     stream.transferable_by_sender = transferable_by_sender;
     stream.transferable_by_recipient = transferable_by_recipient;
-    stream.stream_name = stream_name.clone();
-    stream.created_at = current_time;
-    stream.canceled_at = None;
-    stream.canceled_by = None;
-    stream.paused = false;
+    stream.can_topup = can_topup;
+    stream.apply_fee_config(&fee_config)?;
+    stream.receipt_required = receipt_required;
+    if let Some(rate_cap) = ctx.accounts.rate_cap.as_mut() {
+        let rate = stream_rate_per_second(deposit_amount, duration);
+        rate_cap.reserve(rate)?;
+        stream.capped_rate = rate;
+    }
+    stream.can_update_rate = can_update_rate;
+    stream.lock_rate_after_start = lock_rate_after_start;
+    stream.allow_partial_withdraw = allow_partial_withdraw;
+    stream.automatic_withdrawal = automatic_withdrawal;
+    stream.withdrawal_frequency = withdrawal_frequency;
+    stream.min_withdrawal_interval = min_withdrawal_interval;
+    // A schedule anchored at acceptance cannot vest before it
+    stream.require_acceptance = require_acceptance || start_on_acceptance;
+    stream.accepted = false;
+    stream.start_on_acceptance = start_on_acceptance;
+    stream.set_cancel_multisig(cancel_approvers, cancel_threshold)?;
+    stream.clawback_authority = clawback_authority;
+    stream.set_usd_peg(usd_rate_per_second, price_feed, max_price_age)?;
+    stream.set_name(&stream_name)?;
+    stream.rent_treasury = rent_treasury;
+    stream.interest_rate_bps = interest_rate_bps;
+    stream.set_created_at(current_time);
+    stream.cancelled_at = None;
     stream.paused_at = None;
+    stream.max_pause_duration = max_pause_duration;
+    stream.nonce = nonce;
     stream.bump = ctx.bumps.stream;
     stream.escrow_bump = ctx.bumps.escrow_token_account;
+    stream.validate()?;
 
     // Transfer tokens from sender to escrow
     let transfer_ctx = CpiContext::new(
@@ -164,8 +258,26 @@ pub fn create_stream(
         },
     );
 
+    let balance_before = ctx.accounts.escrow_token_account.amount;
     token::transfer(transfer_ctx, deposit_amount)?;
 
+    // Fee-on-transfer mints deliver less than requested; only promise what arrived
+    ctx.accounts.escrow_token_account.reload()?;
+    let deposit_amount = received_amount(balance_before, ctx.accounts.escrow_token_account.amount)?;
+    stream.deposited_amount = deposit_amount;
+
+    let mint_stats = &mut ctx.accounts.mint_stats;
+    mint_stats.initialize_if_needed(ctx.accounts.mint.key(), ctx.bumps.mint_stats);
+    mint_stats.record_deposit(deposit_amount)?;
+
+    let stream_directory = &mut ctx.accounts.stream_directory;
+    stream_directory.initialize_if_needed(ctx.accounts.sender.key(), ctx.bumps.stream_directory);
+    stream_directory.add(stream.key())?;
+    require!(
+        cliff_amount <= deposit_amount,
+        StreamFlowError::InvalidCliffPeriod
+    );
+
     // Emit event
     emit!(StreamCreated {
         stream: stream.key(),
@@ -182,6 +294,27 @@ pub fn create_stream(
         created_at: current_time,
     });
 
+    // Back-pay: part of the deposit is claimable the moment the stream exists
+    if let Some(vested_at_creation) = backdated_vested_amount(stream, current_time)? {
+        emit!(BackdatedStream {
+            stream: stream.key(),
+            start_time,
+            created_at: current_time,
+            vested_at_creation,
+        });
+    }
+
+    emit!(EscrowFunded::new(
+        stream.key(),
+        ctx.accounts.escrow_token_account.key(),
+        ctx.accounts.mint.key(),
+        deposit_amount,
+        start_time,
+        end_time,
+        cliff_time,
+        cliff_amount,
+    ));
+
     msg!(
         "Stream created: {} tokens from {} to {} over {} seconds",
         deposit_amount,
@@ -193,6 +326,95 @@ pub fn create_stream(
     Ok(())
 }
 
+/// Amount that actually landed in escrow, as opposed to the amount requested
+pub fn received_amount(balance_before: u64, balance_after: u64) -> Result<u64> {
+    let received = balance_after
+        .checked_sub(balance_before)
+        .ok_or(StreamFlowError::ArithmeticUnderflow)?;
+    require!(received > 0, StreamFlowError::InvalidAmount);
+    Ok(received)
+}
+
+/// Amount already vested when a stream starting in the past is created, or
+/// `None` for streams that start now or later
+pub fn backdated_vested_amount(stream: &Stream, current_time: i64) -> Result<Option<u64>> {
+    if stream.start_time >= current_time {
+        return Ok(None);
+    }
+    Ok(Some(stream.calculate_streamed_amount(current_time)?))
+}
+
+/// Start times must not be negative, and unless explicitly allowed, neither
+/// in the past (back-pay) nor further out than `MAX_FUTURE_START`
+pub fn validate_start_time(
+    start_time: i64,
+    current_time: i64,
+    allow_distant_start: bool,
+    allow_backdated: bool,
+) -> Result<()> {
+    require!(start_time >= 0, StreamFlowError::InvalidStartTime);
+    require!(
+        allow_backdated || start_time >= current_time,
+        StreamFlowError::InvalidStartTime
+    );
+
+    if !allow_distant_start {
+        let latest_start = current_time.saturating_add(constants::MAX_FUTURE_START as i64);
+        require!(
+            start_time <= latest_start,
+            StreamFlowError::InvalidStartTime
+        );
+    }
+
+    Ok(())
+}
+
+/// Reject dust deposits and durations outside
+/// `[MIN_STREAM_DURATION, MAX_STREAM_DURATION]`
+pub fn validate_stream_bounds(start_time: i64, end_time: i64, deposit_amount: u64) -> Result<()> {
+    require!(
+        deposit_amount >= constants::MIN_STREAM_AMOUNT,
+        StreamFlowError::InvalidAmount
+    );
+
+    let duration = end_time
+        .checked_sub(start_time)
+        .ok_or(StreamFlowError::ArithmeticOverflow)?;
+    require!(
+        duration >= constants::MIN_STREAM_DURATION as i64
+            && duration <= constants::MAX_STREAM_DURATION as i64,
+        StateError::InvalidStreamDuration
+    );
+
+    Ok(())
+}
+
+/// A cliff is either absent (both fields zero) or fully configured, with
+/// `cliff_time` inside the stream and a nonzero `cliff_amount`
+pub fn validate_cliff(
+    start_time: i64,
+    end_time: i64,
+    cliff_time: i64,
+    cliff_amount: u64,
+    deposit_amount: u64,
+) -> Result<()> {
+    if cliff_time == 0 && cliff_amount == 0 {
+        return Ok(());
+    }
+
+    require!(
+        cliff_time >= start_time && cliff_time <= end_time,
+        StreamFlowError::InvalidCliffPeriod
+    );
+
+    require!(
+        cliff_amount > 0 && cliff_amount <= deposit_amount,
+        StreamFlowError::InvalidCliffPeriod
+    );
+
+    Ok(())
+}
+
 #[event]
 pub struct StreamCreated {
     pub stream: Pubkey,
@@ -208,4 +430,194 @@ pub struct StreamCreated {
     pub stream_name: String,
     pub created_at: i64,
 }
-```
\ No newline at end of file
+
+/// A stream was created with a start time in the past
+#[event]
+pub struct BackdatedStream {
+    pub stream: Pubkey,
+    pub start_time: i64,
+    pub created_at: i64,
+    /// Amount claimable by the recipient immediately after creation
+    pub vested_at_creation: u64,
+}
+
+/// Escrow-specific accounting record for reconciling back-office ledgers
+#[event]
+pub struct EscrowFunded {
+    pub stream: Pubkey,
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    pub expected_balance: u64,
+    /// SHA-256 over the stream parameters that determine the escrow obligation
+    pub params_checksum: [u8; 32],
+}
+
+impl EscrowFunded {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        stream: Pubkey,
+        escrow: Pubkey,
+        mint: Pubkey,
+        deposit_amount: u64,
+        start_time: i64,
+        end_time: i64,
+        cliff_time: i64,
+        cliff_amount: u64,
+    ) -> Self {
+        let params_checksum = hashv(&[
+            stream.as_ref(),
+            escrow.as_ref(),
+            mint.as_ref(),
+            &deposit_amount.to_le_bytes(),
+            &start_time.to_le_bytes(),
+            &end_time.to_le_bytes(),
+            &cliff_time.to_le_bytes(),
+            &cliff_amount.to_le_bytes(),
+        ])
+        .to_bytes();
+
+        Self {
+            stream,
+            escrow,
+            mint,
+            expected_balance: deposit_amount,
+            params_checksum,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    #[test]
+    fn test_escrow_funded_expected_balance_matches_deposit() {
+        let (stream, escrow, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let event = EscrowFunded::new(stream, escrow, mint, 1_000_000, 100, 200, 150, 0);
+
+        assert_eq!(event.expected_balance, 1_000_000);
+        assert_eq!(event.escrow, escrow);
+
+        // The checksum is deterministic and sensitive to every parameter
+        let same = EscrowFunded::new(stream, escrow, mint, 1_000_000, 100, 200, 150, 0);
+        let different = EscrowFunded::new(stream, escrow, mint, 1_000_000, 100, 201, 150, 0);
+        assert_eq!(event.params_checksum, same.params_checksum);
+        assert_ne!(event.params_checksum, different.params_checksum);
+    }
+
+    #[test]
+    fn test_deposit_reflects_net_received_for_transfer_fee_mint() {
+        // Mock mint withholding a 1% transfer fee
+        let transfer_with_fee = |amount: u64| amount - amount / 100;
+
+        let requested = 1_000_000;
+        let balance_after = transfer_with_fee(requested);
+        let deposited = received_amount(0, balance_after).unwrap();
+
+        assert_eq!(deposited, 990_000);
+        assert!(deposited < requested);
+
+        // Without a fee the full amount is recorded
+        assert_eq!(received_amount(0, requested).unwrap(), requested);
+
+        // Nothing received is rejected
+        assert!(received_amount(500, 500).is_err());
+    }
+
+    #[test]
+    fn test_start_time_future_bound() {
+        let now = 1_700_000_000;
+        let max = constants::MAX_FUTURE_START as i64;
+
+        assert!(validate_start_time(now, now, false, false).is_ok());
+        assert!(validate_start_time(now + max, now, false, false).is_ok());
+        assert!(validate_start_time(now + max + 1, now, false, false).is_err());
+        assert!(validate_start_time(now - 1, now, false, false).is_err());
+
+        // Integrators scheduling far out can opt in
+        assert!(validate_start_time(now + max + 1, now, true, false).is_ok());
+        assert!(validate_start_time(now - 1, now, true, false).is_err());
+    }
+
+    #[test]
+    fn test_negative_start_time_is_rejected() {
+        assert!(validate_start_time(-1, -10, false, false).is_err());
+        assert!(validate_start_time(-1, -10, true, false).is_err());
+        assert!(validate_start_time(-1, -10, false, true).is_err());
+        assert!(validate_start_time(0, -10, false, false).is_ok());
+    }
+
+    #[test]
+    fn test_backdated_start_requires_opt_in() {
+        let now = 1_700_000_000;
+        assert_eq!(
+            validate_start_time(now - 86_400, now, false, false).unwrap_err(),
+            StreamFlowError::InvalidStartTime.into()
+        );
+        assert!(validate_start_time(now - 86_400, now, false, true).is_ok());
+
+        // Opting in to back-pay does not lift the future bound
+        let max = constants::MAX_FUTURE_START as i64;
+        assert!(validate_start_time(now + max + 1, now, false, true).is_err());
+    }
+
+    #[test]
+    fn test_backdated_stream_is_partially_vested_at_creation() {
+        let stream = StreamBuilder::new().amount(1_000).start_time(1_000).duration(1_000).build();
+
+        // Created 400s after its start: 40% is claimable straight away
+        assert_eq!(backdated_vested_amount(&stream, 1_400).unwrap(), Some(400));
+        assert_eq!(stream.withdrawable_amount(1_400).unwrap(), 400);
+
+        // Created past its end: everything is claimable
+        assert_eq!(backdated_vested_amount(&stream, 5_000).unwrap(), Some(1_000));
+
+        // Not backdated
+        assert_eq!(backdated_vested_amount(&stream, 1_000).unwrap(), None);
+        assert_eq!(backdated_vested_amount(&stream, 900).unwrap(), None);
+    }
+
+    #[test]
+    fn test_cliff_time_and_amount_coupling() {
+        // No cliff, and a fully configured cliff
+        assert!(validate_cliff(100, 200, 0, 0, 1000).is_ok());
+        assert!(validate_cliff(100, 200, 150, 250, 1000).is_ok());
+        assert!(validate_cliff(100, 200, 100, 1000, 1000).is_ok());
+
+        // Half-configured cliffs
+        assert!(validate_cliff(100, 200, 150, 0, 1000).is_err());
+        assert!(validate_cliff(100, 200, 0, 250, 1000).is_err());
+
+        // Out of range values
+        assert!(validate_cliff(100, 200, 201, 250, 1000).is_err());
+        assert!(validate_cliff(100, 200, 150, 1001, 1000).is_err());
+    }
+
+    #[test]
+    fn test_duration_bounds() {
+        let min = constants::MIN_STREAM_DURATION as i64;
+        let max = constants::MAX_STREAM_DURATION as i64;
+
+        assert_eq!(
+            validate_stream_bounds(1_000, 1_000 + min - 1, 100).unwrap_err(),
+            StateError::InvalidStreamDuration.into()
+        );
+        assert!(validate_stream_bounds(1_000, 1_000 + min, 100).is_ok());
+        assert!(validate_stream_bounds(1_000, 1_000 + max, 100).is_ok());
+        assert_eq!(
+            validate_stream_bounds(1_000, 1_000 + max + 1, 100).unwrap_err(),
+            StateError::InvalidStreamDuration.into()
+        );
+    }
+
+    #[test]
+    fn test_minimum_deposit() {
+        assert_eq!(
+            validate_stream_bounds(1_000, 2_000, constants::MIN_STREAM_AMOUNT - 1).unwrap_err(),
+            StreamFlowError::InvalidAmount.into()
+        );
+        assert!(validate_stream_bounds(1_000, 2_000, constants::MIN_STREAM_AMOUNT).is_ok());
+    }
+}
+```
@@ -0,0 +1,241 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::StreamFlowError;
+use super::create_stream::{received_amount, validate_cliff, validate_stream_bounds};
+
+/// Open a stream to `recipient` starting now, using a template's defaults
+/// with the given deposit
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct CreateStreamFromTemplate<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"template",
+            authority.key().as_ref(),
+            &template.template_id.to_le_bytes(),
+        ],
+        bump = template.bump,
+        has_one = authority,
+        has_one = mint,
+    )]
+    pub template: Account<'info, StreamTemplate>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = StreamCounter::LEN,
+        seeds = [
+            b"stream_counter",
+            authority.key().as_ref(),
+            recipient.as_ref(),
+        ],
+        bump
+    )]
+    pub stream_counter: Account<'info, StreamCounter>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Stream::LEN,
+        seeds = [
+            b"stream",
+            authority.key().as_ref(),
+            recipient.as_ref(),
+            &stream_counter.count.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = stream,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = authority,
+    )]
+    pub funding_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = MintStats::LEN,
+        seeds = [b"mint_stats", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn handler(
+    ctx: Context<CreateStreamFromTemplate>,
+    recipient: Pubkey,
+    deposit_amount: u64,
+) -> Result<()> {
+    require!(
+        recipient != ctx.accounts.authority.key(),
+        StreamFlowError::InvalidRecipient
+    );
+    require!(
+        ctx.accounts.funding_token_account.amount >= deposit_amount,
+        StreamFlowError::InsufficientFunds
+    );
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let nonce = ctx.accounts.stream_counter.claim_nonce(
+        ctx.accounts.authority.key(),
+        recipient,
+        ctx.bumps.stream_counter,
+    )?;
+
+    let balance_before = ctx.accounts.escrow_token_account.amount;
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.funding_token_account.to_account_info(),
+                to: ctx.accounts.escrow_token_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        deposit_amount,
+    )?;
+    ctx.accounts.escrow_token_account.reload()?;
+    let deposited_amount = received_amount(balance_before, ctx.accounts.escrow_token_account.amount)?;
+
+    let template_key = ctx.accounts.template.key();
+    let stream = &mut ctx.accounts.stream;
+    stream.sender = ctx.accounts.authority.key();
+    stream.recipient = recipient;
+    stream.mint = ctx.accounts.mint.key();
+    stream.escrow_tokens = ctx.accounts.escrow_token_account.key();
+    stream.deposited_amount = deposited_amount;
+    stream.withdrawn_amount = 0;
+    instantiate_template(&ctx.accounts.template, stream, current_time)?;
+    stream.template = Some(template_key);
+    stream.set_created_at(current_time);
+    stream.nonce = nonce;
+    stream.bump = ctx.bumps.stream;
+    stream.escrow_bump = ctx.bumps.escrow_token_account;
+    stream.validate()?;
+    stream.activate_if_started(current_time);
+
+    let mint_stats = &mut ctx.accounts.mint_stats;
+    mint_stats.initialize_if_needed(ctx.accounts.mint.key(), ctx.bumps.mint_stats);
+    mint_stats.record_deposit(deposited_amount)?;
+
+    ctx.accounts.template.record_stream()?;
+
+    emit!(StreamCreatedFromTemplate {
+        template: template_key,
+        stream: ctx.accounts.stream.key(),
+        recipient,
+        deposited_amount,
+        start_time: current_time,
+    });
+
+    Ok(())
+}
+
+/// Apply the template to `stream`, whose deposit is already set, and check the
+/// result against the same bounds `create_stream` enforces
+pub fn instantiate_template(
+    template: &StreamTemplate,
+    stream: &mut Stream,
+    start_time: i64,
+) -> Result<()> {
+    template.apply_to(stream, start_time)?;
+    validate_stream_bounds(stream.start_time, stream.end_time, stream.deposited_amount)?;
+    validate_cliff(
+        stream.start_time,
+        stream.end_time,
+        stream.cliff_time,
+        stream.cliff_amount,
+        stream.deposited_amount,
+    )
+}
+
+#[event]
+pub struct StreamCreatedFromTemplate {
+    pub template: Pubkey,
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub deposited_amount: u64,
+    pub start_time: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn template() -> StreamTemplate {
+        StreamTemplate {
+            authority: Pubkey::new_unique(),
+            template_id: 7,
+            mint: Pubkey::new_unique(),
+            deposit_amount: 10_000,
+            duration: 86_400,
+            stream_type: StreamType::Cliff,
+            cliff_offset: 3_600,
+            cliff_amount: 1_000,
+            rate_amount: 0,
+            rate_interval_in_seconds: 0,
+            cancelable_by_sender: true,
+            cancelable_by_recipient: false,
+            fee_percentage: 0,
+            fee_recipient: None,
+            partner_fee_percentage: 0,
+            partner_fee_recipient: None,
+            onboarding_lamports: 0,
+            active_stream_count: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_stream_from_template_uses_its_defaults() {
+        let template = template();
+        let mut stream = StreamBuilder::new().amount(20_000).build();
+        instantiate_template(&template, &mut stream, 1_000).unwrap();
+
+        assert_eq!(stream.deposited_amount, 20_000);
+        assert_eq!(stream.start_time, 1_000);
+        assert_eq!(stream.end_time, 87_400);
+        assert_eq!(stream.cliff_time, 4_600);
+        assert_eq!(stream.cliff_amount, 1_000);
+        assert!(stream.cancelable_by_sender);
+        assert!(!stream.cancelable_by_recipient);
+    }
+
+    #[test]
+    fn test_deposit_below_template_cliff_is_rejected() {
+        let template = template();
+        let mut stream = StreamBuilder::new().amount(500).build();
+        assert_eq!(
+            instantiate_template(&template, &mut stream, 1_000).unwrap_err(),
+            StreamFlowError::InvalidCliffPeriod.into()
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use crate::state::*;
+use crate::state::utils::check_batch_size;
+use crate::errors::StreamFlowError;
+use super::batch_create_from_template::{
+    check_stream_accounts, open_batch_stream, BatchFunding, ACCOUNTS_PER_BATCH_STREAM,
+};
+use super::create_stream::{validate_cliff, validate_start_time, validate_stream_bounds};
+
+/// Create several independent streams in one transaction.
+///
+/// For item `i`, `remaining_accounts[3 * i..3 * i + 3]` holds the stream
+/// counter, stream and escrow PDAs. Items that fail validation are skipped and
+/// reported in `BatchResult` instead of aborting the batch.
+#[derive(Accounts)]
+pub struct CreateStreamsBatch<'info> {
+    /// Token account every deposit in the batch is drawn from
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = sender,
+    )]
+    pub sender_token_account: Account<'info, TokenAccount>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = MintStats::LEN,
+        seeds = [b"mint_stats", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// One stream to create in a batch
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CreateStreamParams {
+    pub recipient: Pubkey,
+    pub deposit_amount: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub cliff_time: i64,
+    pub cliff_amount: u64,
+    pub cancelable_by_sender: bool,
+    pub cancelable_by_recipient: bool,
+}
+
+pub fn handler<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, CreateStreamsBatch<'info>>,
+    params: Vec<CreateStreamParams>,
+) -> Result<()> {
+    check_batch_size(BatchOp::Create, params.len())?;
+    require!(
+        ctx.remaining_accounts.len() == params.len() * ACCOUNTS_PER_BATCH_STREAM,
+        StreamFlowError::InvalidBatchOperation
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let sender = ctx.accounts.sender.key();
+    let mut available = ctx.accounts.sender_token_account.amount;
+    let mut succeeded: Vec<Pubkey> = Vec::with_capacity(params.len());
+    let mut failed: Vec<u8> = Vec::new();
+
+    let funding = BatchFunding {
+        authority: &ctx.accounts.sender.to_account_info(),
+        funding_token_account: &ctx.accounts.sender_token_account.to_account_info(),
+        mint: &ctx.accounts.mint.to_account_info(),
+        token_program: &ctx.accounts.token_program.to_account_info(),
+        system_program: &ctx.accounts.system_program.to_account_info(),
+    };
+
+    ctx.accounts
+        .mint_stats
+        .initialize_if_needed(ctx.accounts.mint.key(), ctx.bumps.mint_stats);
+
+    for (index, (item, accounts)) in params
+        .iter()
+        .zip(ctx.remaining_accounts.chunks(ACCOUNTS_PER_BATCH_STREAM))
+        .enumerate()
+    {
+        let checked = check_stream_params(&sender, item, current_time, available)
+            .and_then(|_| check_stream_accounts(&sender, &item.recipient, accounts, ctx.program_id));
+        if let Err(err) = checked {
+            msg!("Skipping batch item {}: {:?}", index, err);
+            failed.push(index as u8);
+            continue;
+        }
+
+        let mut stream = open_batch_stream(&funding, &item.recipient, item.deposit_amount, accounts, ctx.program_id)?;
+        apply_stream_params(&mut stream, item, current_time);
+        stream.validate()?;
+        stream.exit(ctx.program_id)?;
+
+        available -= item.deposit_amount;
+        ctx.accounts.mint_stats.record_deposit(stream.deposited_amount)?;
+        succeeded.push(stream.key());
+    }
+
+    emit!(BatchResult {
+        sender,
+        succeeded,
+        failed,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+/// Checks on an item's arguments, made before any account is touched so a bad
+/// item can be skipped. `available` is what is left in the sender's account.
+pub fn check_stream_params(
+    sender: &Pubkey,
+    item: &CreateStreamParams,
+    current_time: i64,
+    available: u64,
+) -> Result<()> {
+    require!(item.deposit_amount <= available, StreamFlowError::InsufficientFunds);
+    require!(item.recipient != *sender, StreamFlowError::InvalidRecipient);
+    validate_start_time(item.start_time, current_time, false, false)?;
+    require!(item.end_time > item.start_time, StreamFlowError::InvalidEndTime);
+    validate_stream_bounds(item.start_time, item.end_time, item.deposit_amount)?;
+    validate_cliff(
+        item.start_time,
+        item.end_time,
+        item.cliff_time,
+        item.cliff_amount,
+        item.deposit_amount,
+    )
+}
+
+/// Set the schedule and permissions of a freshly opened batch stream
+pub fn apply_stream_params(stream: &mut Stream, item: &CreateStreamParams, current_time: i64) {
+    stream.withdrawn_amount = 0;
+    stream.start_time = item.start_time;
+    stream.end_time = item.end_time;
+    stream.last_withdrawn_at = item.start_time;
+    stream.cliff_time = item.cliff_time;
+    stream.cliff_amount = item.cliff_amount;
+    stream.stream_type = if item.cliff_amount > 0 { StreamType::Cliff } else { StreamType::Linear };
+    stream.cancelable_by_sender = item.cancelable_by_sender;
+    stream.cancelable_by_recipient = item.cancelable_by_recipient;
+    stream.allow_partial_withdraw = true;
+    stream.set_created_at(current_time);
+    stream.status = StreamStatus::Scheduled;
+    stream.activate_if_started(current_time);
+}
+
+#[event]
+pub struct BatchResult {
+    pub sender: Pubkey,
+    /// Streams created, in item order
+    pub succeeded: Vec<Pubkey>,
+    /// Indices of the items that were skipped
+    pub failed: Vec<u8>,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn item(start_time: i64, end_time: i64) -> CreateStreamParams {
+        CreateStreamParams {
+            recipient: Pubkey::new_unique(),
+            deposit_amount: 1_000,
+            start_time,
+            end_time,
+            cliff_time: 0,
+            cliff_amount: 0,
+            cancelable_by_sender: true,
+            cancelable_by_recipient: false,
+        }
+    }
+
+    /// Indices that pass validation and that are skipped, as the handler sees them
+    fn partition(sender: &Pubkey, items: &[CreateStreamParams], mut available: u64) -> (Vec<usize>, Vec<u8>) {
+        let mut succeeded = Vec::new();
+        let mut failed = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            match check_stream_params(sender, item, 1_000, available) {
+                Ok(()) => {
+                    available -= item.deposit_amount;
+                    succeeded.push(index);
+                }
+                Err(_) => failed.push(index as u8),
+            }
+        }
+        (succeeded, failed)
+    }
+
+    #[test]
+    fn test_all_success_batch() {
+        let sender = Pubkey::new_unique();
+        let items = [item(2_000, 5_600), item(1_000, 2_000), item(3_000, 90_000)];
+        assert_eq!(partition(&sender, &items, 3_000), (vec![0, 1, 2], vec![]));
+
+        let mut stream = StreamBuilder::new().amount(1_000).build();
+        apply_stream_params(&mut stream, &items[1], 1_000);
+        assert_eq!(stream.status, StreamStatus::Streaming);
+        assert_eq!(stream.end_time, 2_000);
+        apply_stream_params(&mut stream, &items[0], 1_000);
+        assert_eq!(stream.status, StreamStatus::Scheduled);
+        assert_eq!(stream.last_withdrawn_at, 2_000);
+    }
+
+    #[test]
+    fn test_mixed_batch_skips_invalid_time_range() {
+        let sender = Pubkey::new_unique();
+        let items = [item(2_000, 5_600), item(5_000, 4_000), item(3_000, 90_000)];
+        assert_eq!(partition(&sender, &items, 3_000), (vec![0, 2], vec![1]));
+        assert_eq!(
+            check_stream_params(&sender, &items[1], 1_000, 3_000).unwrap_err(),
+            StreamFlowError::InvalidEndTime.into()
+        );
+    }
+
+    #[test]
+    fn test_batch_skips_items_the_balance_cannot_cover() {
+        let sender = Pubkey::new_unique();
+        let items = [item(2_000, 5_600), item(2_000, 5_600), item(2_000, 5_600)];
+        assert_eq!(partition(&sender, &items, 2_500), (vec![0, 1], vec![2]));
+    }
+}
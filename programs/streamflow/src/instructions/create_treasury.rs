@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use crate::state::*;
+
+/// Open a treasury for `mint` together with its escrow
+#[derive(Accounts)]
+#[instruction(treasury_id: u64)]
+pub struct CreateTreasury<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = Treasury::LEN,
+        seeds = [
+            b"treasury",
+            owner.key().as_ref(),
+            &treasury_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            b"treasury_escrow",
+            treasury.key().as_ref(),
+        ],
+        bump,
+        token::mint = mint,
+        token::authority = treasury,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn handler(ctx: Context<CreateTreasury>, treasury_id: u64) -> Result<()> {
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.owner = ctx.accounts.owner.key();
+    treasury.treasury_id = treasury_id;
+    treasury.mint = ctx.accounts.mint.key();
+    treasury.escrow = ctx.accounts.escrow_token_account.key();
+    treasury.members = Vec::new();
+    treasury.bump = ctx.bumps.treasury;
+    treasury.escrow_bump = ctx.bumps.escrow_token_account;
+
+    emit!(TreasuryCreated {
+        treasury: treasury.key(),
+        owner: treasury.owner,
+        mint: treasury.mint,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct TreasuryCreated {
+    pub treasury: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+}
@@ -0,0 +1,129 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+
+/// Grant `beneficiary` a vesting schedule funded from the grantor's tokens
+#[derive(Accounts)]
+#[instruction(beneficiary: Pubkey)]
+pub struct CreateVesting<'info> {
+    /// Count of schedules granted to the beneficiary, created on first use
+    #[account(
+        init_if_needed,
+        payer = grantor,
+        space = VestingRegistry::LEN,
+        seeds = [b"vesting_registry", beneficiary.as_ref()],
+        bump
+    )]
+    pub vesting_registry: Account<'info, VestingRegistry>,
+
+    #[account(
+        init,
+        payer = grantor,
+        space = VestingSchedule::LEN,
+        seeds = [
+            b"vesting",
+            beneficiary.as_ref(),
+            &vesting_registry.schedule_count.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+
+    #[account(
+        init,
+        payer = grantor,
+        seeds = [
+            b"vesting_escrow",
+            vesting_schedule.key().as_ref(),
+        ],
+        bump,
+        token::mint = mint,
+        token::authority = vesting_schedule,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = grantor,
+    )]
+    pub grantor_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub grantor: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn handler(
+    ctx: Context<CreateVesting>,
+    beneficiary: Pubkey,
+    total_amount: u64,
+    vesting_type: VestingType,
+    start_time: i64,
+    cliff_time: Option<i64>,
+    end_time: i64,
+) -> Result<()> {
+    VestingSchedule::validate_params(total_amount, vesting_type, start_time, cliff_time, end_time)?;
+
+    let index = ctx
+        .accounts
+        .vesting_registry
+        .claim_index(beneficiary, ctx.bumps.vesting_registry)?;
+
+    let schedule = &mut ctx.accounts.vesting_schedule;
+    schedule.grantor = ctx.accounts.grantor.key();
+    schedule.beneficiary = beneficiary;
+    schedule.mint = ctx.accounts.mint.key();
+    schedule.escrow = ctx.accounts.escrow_token_account.key();
+    schedule.index = index;
+    schedule.total_amount = total_amount;
+    schedule.claimed_amount = 0;
+    schedule.vesting_type = vesting_type;
+    schedule.start_time = start_time;
+    schedule.cliff_time = cliff_time;
+    schedule.end_time = end_time;
+    schedule.bump = ctx.bumps.vesting_schedule;
+    schedule.escrow_bump = ctx.bumps.escrow_token_account;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.grantor_token_account.to_account_info(),
+                to: ctx.accounts.escrow_token_account.to_account_info(),
+                authority: ctx.accounts.grantor.to_account_info(),
+            },
+        ),
+        total_amount,
+    )?;
+
+    emit!(VestingCreated {
+        vesting_schedule: schedule.key(),
+        grantor: schedule.grantor,
+        beneficiary,
+        total_amount,
+        vesting_type,
+        start_time,
+        cliff_time,
+        end_time,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct VestingCreated {
+    pub vesting_schedule: Pubkey,
+    pub grantor: Pubkey,
+    pub beneficiary: Pubkey,
+    pub total_amount: u64,
+    pub vesting_type: VestingType,
+    pub start_time: i64,
+    pub cliff_time: Option<i64>,
+    pub end_time: i64,
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::StreamFlowError;
+
+#[derive(Accounts)]
+pub struct DiffSchedule<'info> {
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        seeds = [
+            b"schedule",
+            stream.key().as_ref(),
+        ],
+        bump = schedule.bump,
+        has_one = stream,
+    )]
+    pub schedule: Account<'info, CustomSchedule>,
+}
+
+/// Read-only: emits the differences between the stored and a proposed schedule
+pub fn handler(ctx: Context<DiffSchedule>, proposed: Vec<UnlockPoint>) -> Result<()> {
+    require!(
+        proposed.len() <= MAX_UNLOCK_POINTS,
+        StreamFlowError::InvalidVestingSchedule
+    );
+
+    let changes = ctx.accounts.schedule.diff(&proposed);
+
+    emit!(ScheduleDiffed {
+        stream: ctx.accounts.stream.key(),
+        changes,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct ScheduleDiffed {
+    pub stream: Pubkey,
+    pub changes: Vec<ScheduleChange>,
+}
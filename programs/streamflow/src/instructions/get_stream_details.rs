@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+/// Read-only view of a stream with its vesting math evaluated on-chain, so
+/// SDKs do not have to reimplement it
+#[derive(Accounts)]
+pub struct GetStreamDetails<'info> {
+    pub stream: Account<'info, Stream>,
+}
+
+/// Computed fields of a stream at one point in time
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct StreamView {
+    /// What a withdrawal right now would release, before fees
+    pub withdrawable_now: u64,
+    /// Everything the schedule has released so far, withdrawn or not
+    pub vested_total: u64,
+    /// Deposit not yet withdrawn
+    pub remaining_balance: u64,
+    /// Elapsed share of the schedule (basis points)
+    pub progress_bps: u16,
+    pub effective_status: StreamStatus,
+}
+
+pub fn handler(ctx: Context<GetStreamDetails>) -> Result<StreamView> {
+    let current_time = Clock::get()?.unix_timestamp;
+    stream_view(&ctx.accounts.stream, current_time)
+}
+
+/// Evaluate `stream` at `current_time`
+pub fn stream_view(stream: &Stream, current_time: i64) -> Result<StreamView> {
+    // A withdrawal first moves a started stream out of `Scheduled`
+    let mut started = stream.clone();
+    started.activate_if_started(current_time);
+
+    Ok(StreamView {
+        withdrawable_now: started.withdrawable_amount(current_time)?,
+        vested_total: stream.calculate_streamed_amount(current_time)?,
+        remaining_balance: stream.remaining_balance(),
+        progress_bps: stream.get_progress(current_time)?,
+        effective_status: stream.derive_status(current_time)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn assert_view_matches_helpers(stream: &Stream, current_time: i64) {
+        let view = stream_view(stream, current_time).unwrap();
+        assert_eq!(view.vested_total, stream.calculate_streamed_amount(current_time).unwrap());
+        assert_eq!(view.remaining_balance, stream.remaining_balance());
+        assert_eq!(view.progress_bps, stream.get_progress(current_time).unwrap());
+        assert_eq!(view.effective_status, stream.derive_status(current_time).unwrap());
+    }
+
+    #[test]
+    fn test_view_matches_helpers_over_time() {
+        let stream = StreamBuilder::new().amount(1_000).withdrawn(200).build();
+        for current_time in [50, 100, 125, 150, 199, 200, 300] {
+            assert_view_matches_helpers(&stream, current_time);
+            assert_eq!(
+                stream_view(&stream, current_time).unwrap().withdrawable_now,
+                stream.withdrawable_amount(current_time).unwrap()
+            );
+        }
+
+        let view = stream_view(&stream, 150).unwrap();
+        assert_eq!(view.withdrawable_now, 300);
+        assert_eq!(view.vested_total, 500);
+        assert_eq!(view.remaining_balance, 800);
+        assert_eq!(view.progress_bps, 5_000);
+        assert_eq!(view.effective_status, StreamStatus::Streaming);
+    }
+
+    #[test]
+    fn test_scheduled_stream_reports_what_withdraw_would_release() {
+        let stream = StreamBuilder::new().status(StreamStatus::Scheduled).build();
+        assert_eq!(stream_view(&stream, 50).unwrap().withdrawable_now, 0);
+        assert_eq!(stream_view(&stream, 150).unwrap().withdrawable_now, 500);
+        for current_time in [50, 150, 250] {
+            assert_view_matches_helpers(&stream, current_time);
+        }
+    }
+
+    #[test]
+    fn test_cancelled_stream_has_nothing_withdrawable() {
+        let stream = StreamBuilder::new().status(StreamStatus::Cancelled).build();
+        let view = stream_view(&stream, 150).unwrap();
+        assert_eq!(view.withdrawable_now, 0);
+        assert_eq!(view.effective_status, StreamStatus::Cancelled);
+    }
+}
@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+/// Read-only view of a stream's effective status
+#[derive(Accounts)]
+pub struct GetStreamStatus<'info> {
+    pub stream: Account<'info, Stream>,
+}
+
+pub fn handler(ctx: Context<GetStreamStatus>) -> Result<StreamStatus> {
+    let current_time = Clock::get()?.unix_timestamp;
+    ctx.accounts.stream.derive_status(current_time)
+}
@@ -0,0 +1,214 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use crate::state::*;
+use crate::errors::StreamFlowError;
+
+/// Open a proposal to change the program's settings; the proposer must hold
+/// governance tokens
+#[derive(Accounts)]
+#[instruction(proposal_id: u64)]
+pub struct CreateProposal<'info> {
+    #[account(
+        init,
+        payer = proposer,
+        space = Proposal::LEN,
+        seeds = [
+            b"proposal",
+            proposer.key().as_ref(),
+            &proposal_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        token::mint = program_config.governance_mint,
+        token::authority = proposer,
+    )]
+    pub proposer_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Vote on a proposal with the voter's governance token balance
+#[derive(Accounts)]
+pub struct Vote<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"proposal",
+            proposal.proposer.as_ref(),
+            &proposal.proposal_id.to_le_bytes(),
+        ],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// One per (proposal, voter); a second vote finds it already filled in
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = VoteRecord::LEN,
+        seeds = [
+            b"vote",
+            proposal.key().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        token::mint = program_config.governance_mint,
+        token::authority = voter,
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Apply a proposal that passed; anyone may execute it
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"proposal",
+            proposal.proposer.as_ref(),
+            &proposal.proposal_id.to_le_bytes(),
+        ],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub executor: Signer<'info>,
+}
+
+pub fn create_proposal_handler(
+    ctx: Context<CreateProposal>,
+    proposal_id: u64,
+    proposal_type: ProposalType,
+    voting_period: i64,
+) -> Result<()> {
+    let (_, quorum) = ctx.accounts.program_config.governance()?;
+    require!(
+        ctx.accounts.proposer_token_account.amount > 0,
+        StreamFlowError::InsufficientVotingPower
+    );
+    require!(voting_period > 0, StreamFlowError::InvalidGovernanceConfiguration);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.proposer = ctx.accounts.proposer.key();
+    proposal.proposal_id = proposal_id;
+    proposal.proposal_type = proposal_type;
+    proposal.votes_for = 0;
+    proposal.votes_against = 0;
+    proposal.quorum = quorum;
+    proposal.voting_deadline = current_time
+        .checked_add(voting_period)
+        .ok_or(StreamFlowError::ArithmeticOverflow)?;
+    proposal.executed = false;
+    proposal.bump = ctx.bumps.proposal;
+
+    emit!(ProposalCreated {
+        proposal: proposal.key(),
+        proposer: proposal.proposer,
+        proposal_type,
+        voting_deadline: proposal.voting_deadline,
+    });
+
+    Ok(())
+}
+
+pub fn vote_handler(ctx: Context<Vote>, support: bool) -> Result<()> {
+    ctx.accounts.program_config.governance()?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let proposal_key = ctx.accounts.proposal.key();
+    let voter = ctx.accounts.voter.key();
+    let weight = ctx.accounts.voter_token_account.amount;
+
+    ctx.accounts
+        .vote_record
+        .cast(proposal_key, voter, support, weight, ctx.bumps.vote_record)?;
+    ctx.accounts.proposal.record_vote(support, weight, current_time)?;
+
+    emit!(VoteCast {
+        proposal: proposal_key,
+        voter,
+        support,
+        weight,
+    });
+
+    Ok(())
+}
+
+pub fn execute_proposal_handler(ctx: Context<ExecuteProposal>) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.check_executable(current_time)?;
+
+    proposal.proposal_type.apply(&mut ctx.accounts.program_config);
+    proposal.executed = true;
+
+    emit!(ProposalExecuted {
+        proposal: proposal.key(),
+        proposal_type: proposal.proposal_type,
+        votes_for: proposal.votes_for,
+        votes_against: proposal.votes_against,
+        executed_at: current_time,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub proposal: Pubkey,
+    pub proposer: Pubkey,
+    pub proposal_type: ProposalType,
+    pub voting_deadline: i64,
+}
+
+#[event]
+pub struct VoteCast {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub weight: u64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub proposal: Pubkey,
+    pub proposal_type: ProposalType,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub executed_at: i64,
+}
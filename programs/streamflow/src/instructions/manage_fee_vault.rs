@@ -0,0 +1,159 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::StreamFlowError;
+
+/// Open a fee vault for the signing fee recipient and one mint. Withdrawals
+/// that pass the vault pay their platform fee into it instead of the
+/// recipient's own token account.
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(
+        init,
+        payer = fee_recipient,
+        space = FeeVault::LEN,
+        seeds = [b"fee_vault", fee_recipient.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(
+        init,
+        payer = fee_recipient,
+        seeds = [b"fee_vault_tokens", fee_vault.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = fee_vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub fee_recipient: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"fee_vault", fee_recipient.key().as_ref(), fee_vault.mint.as_ref()],
+        bump = fee_vault.bump,
+        has_one = fee_recipient @ StreamFlowError::InvalidFeeConfiguration,
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(
+        mut,
+        address = fee_vault.token_account @ StreamFlowError::InvalidFeeConfiguration,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == fee_vault.mint @ StreamFlowError::InvalidTokenMint,
+    )]
+    pub destination: Account<'info, TokenAccount>,
+
+    pub fee_recipient: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn initialize_handler(ctx: Context<InitializeFeeVault>) -> Result<()> {
+    let fee_vault = &mut ctx.accounts.fee_vault;
+    fee_vault.fee_recipient = ctx.accounts.fee_recipient.key();
+    fee_vault.mint = ctx.accounts.mint.key();
+    fee_vault.token_account = ctx.accounts.vault_token_account.key();
+    fee_vault.bump = ctx.bumps.fee_vault;
+    Ok(())
+}
+
+pub fn claim_handler(ctx: Context<ClaimFees>, amount: u64) -> Result<()> {
+    let fee_vault = &mut ctx.accounts.fee_vault;
+    fee_vault.claim(amount)?;
+
+    let seeds = &[
+        b"fee_vault",
+        fee_vault.fee_recipient.as_ref(),
+        fee_vault.mint.as_ref(),
+        &[fee_vault.bump],
+    ];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.vault_token_account.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: fee_vault.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+    )?;
+
+    emit!(FeesClaimed {
+        fee_vault: fee_vault.key(),
+        fee_recipient: fee_vault.fee_recipient,
+        amount,
+        total_fees_claimed: fee_vault.total_fees_claimed,
+        remaining: fee_vault.unclaimed(),
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct FeesClaimed {
+    pub fee_vault: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub amount: u64,
+    pub total_fees_claimed: u64,
+    /// Fees still unclaimed after this claim
+    pub remaining: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::StreamError;
+    use crate::instructions::withdraw::plan_withdrawal;
+    use crate::state::stream::StreamBuilder;
+
+    #[test]
+    fn test_fees_from_two_withdrawals_can_be_claimed() {
+        // 0.5% platform fee
+        let mut stream = StreamBuilder::new().amount(20_000).fees(50, 0).build();
+        let mut fee_vault = FeeVault::default();
+
+        for current_time in [150, 200] {
+            let plan = plan_withdrawal(&stream, current_time, None, None).unwrap();
+            stream.withdrawn_amount += plan.gross_amount;
+            fee_vault.record_fee(plan.platform_fee).unwrap();
+        }
+        assert_eq!(fee_vault.total_fees_collected, 100);
+
+        fee_vault.claim(60).unwrap();
+        assert_eq!(fee_vault.total_fees_claimed, 60);
+        assert_eq!(fee_vault.unclaimed(), 40);
+
+        fee_vault.claim(40).unwrap();
+        assert_eq!(fee_vault.unclaimed(), 0);
+    }
+
+    #[test]
+    fn test_over_claiming_is_rejected() {
+        let mut fee_vault = FeeVault::default();
+        fee_vault.record_fee(50).unwrap();
+        fee_vault.record_fee(30).unwrap();
+
+        assert_eq!(fee_vault.claim(81).unwrap_err(), StreamError::InsufficientFunds.into());
+        fee_vault.claim(80).unwrap();
+        assert_eq!(fee_vault.claim(1).unwrap_err(), StreamError::InsufficientFunds.into());
+        assert_eq!(fee_vault.total_fees_claimed, 80);
+    }
+}
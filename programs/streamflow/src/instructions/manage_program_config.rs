@@ -0,0 +1,170 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::StreamFlowError;
+
+/// Create the program's config; the signer becomes its admin
+#[derive(Accounts)]
+pub struct InitializeProgramConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = ProgramConfig::LEN,
+        seeds = [b"program_config"],
+        bump
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetEmergencyPause<'info> {
+    #[account(
+        mut,
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureGovernance<'info> {
+    #[account(
+        mut,
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxInterestRate<'info> {
+    #[account(
+        mut,
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+/// Create the mint's yield reserve if needed and top it up from the admin.
+/// The reserve is owned by the program config, which signs interest payouts.
+#[derive(Accounts)]
+pub struct FundYieldReserve<'info> {
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = admin @ StreamFlowError::UnauthorizedAccess,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = mint,
+        token::authority = program_config,
+        seeds = [b"yield_reserve", mint.key().as_ref()],
+        bump
+    )]
+    pub yield_reserve: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = admin,
+    )]
+    pub admin_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn initialize_handler(ctx: Context<InitializeProgramConfig>) -> Result<()> {
+    let config = &mut ctx.accounts.program_config;
+    config.admin = ctx.accounts.admin.key();
+    config.emergency_paused = false;
+    config.bump = ctx.bumps.program_config;
+    Ok(())
+}
+
+pub fn pause_handler(ctx: Context<SetEmergencyPause>, paused: bool) -> Result<()> {
+    let admin = ctx.accounts.admin.key();
+    ctx.accounts.program_config.set_emergency_pause(&admin, paused)?;
+
+    emit!(EmergencyPauseSet {
+        admin,
+        paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn governance_handler(ctx: Context<ConfigureGovernance>, governance_mint: Pubkey, quorum: u64) -> Result<()> {
+    let admin = ctx.accounts.admin.key();
+    ctx.accounts
+        .program_config
+        .configure_governance(&admin, governance_mint, quorum)?;
+    Ok(())
+}
+
+pub fn interest_rate_handler(ctx: Context<SetMaxInterestRate>, max_interest_rate_bps: u16) -> Result<()> {
+    let admin = ctx.accounts.admin.key();
+    ctx.accounts
+        .program_config
+        .set_max_interest_rate(&admin, max_interest_rate_bps)?;
+    Ok(())
+}
+
+pub fn fund_yield_reserve_handler(ctx: Context<FundYieldReserve>, amount: u64) -> Result<()> {
+    require!(amount > 0, StreamFlowError::InvalidAmount);
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.admin_token_account.to_account_info(),
+                to: ctx.accounts.yield_reserve.to_account_info(),
+                authority: ctx.accounts.admin.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    emit!(YieldReserveFunded {
+        mint: ctx.accounts.mint.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct YieldReserveFunded {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyPauseSet {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use crate::state::*;
+use crate::errors::StreamFlowError;
+use super::create_stream::{validate_cliff, validate_stream_bounds};
+
+/// Create a template of stream defaults for the authority
+#[derive(Accounts)]
+#[instruction(template_id: u64)]
+pub struct CreateTemplate<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = StreamTemplate::LEN,
+        seeds = [
+            b"template",
+            authority.key().as_ref(),
+            &template_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub template: Account<'info, StreamTemplate>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Delete a template none of whose streams remain, returning its rent
+#[derive(Accounts)]
+pub struct DeleteTemplate<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            b"template",
+            authority.key().as_ref(),
+            &template.template_id.to_le_bytes(),
+        ],
+        bump = template.bump,
+        has_one = authority,
+    )]
+    pub template: Account<'info, StreamTemplate>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+pub fn create_handler(
+    ctx: Context<CreateTemplate>,
+    template_id: u64,
+    params: TemplateParams,
+) -> Result<()> {
+    let template = &mut ctx.accounts.template;
+    require!(
+        template.authority == Pubkey::default(),
+        StreamFlowError::TemplateAlreadyExists
+    );
+    validate_template_params(&params)?;
+
+    template.authority = ctx.accounts.authority.key();
+    template.template_id = template_id;
+    template.mint = ctx.accounts.mint.key();
+    template.configure(&params);
+    template.active_stream_count = 0;
+    template.bump = ctx.bumps.template;
+
+    emit!(TemplateCreated {
+        template: template.key(),
+        authority: template.authority,
+        template_id,
+        mint: template.mint,
+    });
+
+    Ok(())
+}
+
+pub fn delete_handler(ctx: Context<DeleteTemplate>) -> Result<()> {
+    ctx.accounts.template.ensure_deletable()?;
+
+    emit!(TemplateDeleted {
+        template: ctx.accounts.template.key(),
+        authority: ctx.accounts.authority.key(),
+    });
+
+    Ok(())
+}
+
+/// Template defaults must describe a valid stream starting at time zero
+pub fn validate_template_params(params: &TemplateParams) -> Result<()> {
+    validate_stream_bounds(0, params.duration, params.deposit_amount)?;
+    FeeConfig {
+        fee_percentage: params.fee_percentage,
+        fee_recipient: params.fee_recipient,
+        partner_fee_percentage: params.partner_fee_percentage,
+        partner_fee_recipient: params.partner_fee_recipient,
+        allow_self_routing: false,
+    }
+    .validate_cap()?;
+    if params.cliff_amount > 0 {
+        validate_cliff(
+            0,
+            params.duration,
+            params.cliff_offset,
+            params.cliff_amount,
+            params.deposit_amount,
+        )?;
+    }
+    Ok(())
+}
+
+#[event]
+pub struct TemplateCreated {
+    pub template: Pubkey,
+    pub authority: Pubkey,
+    pub template_id: u64,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct TemplateDeleted {
+    pub template: Pubkey,
+    pub authority: Pubkey,
+}
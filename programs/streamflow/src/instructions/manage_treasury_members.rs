@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+/// Add or remove a treasury member; only an `Owner` may change membership
+#[derive(Accounts)]
+pub struct ManageTreasuryMembers<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"treasury",
+            treasury.owner.as_ref(),
+            &treasury.treasury_id.to_le_bytes(),
+        ],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn add_member_handler(
+    ctx: Context<ManageTreasuryMembers>,
+    member: Pubkey,
+    role: TreasuryRole,
+) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.add_member(&authority, member, role)?;
+
+    emit!(TreasuryMemberAdded {
+        treasury: treasury.key(),
+        member,
+        role,
+    });
+
+    Ok(())
+}
+
+pub fn remove_member_handler(ctx: Context<ManageTreasuryMembers>, member: Pubkey) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.remove_member(&authority, &member)?;
+
+    emit!(TreasuryMemberRemoved {
+        treasury: treasury.key(),
+        member,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct TreasuryMemberAdded {
+    pub treasury: Pubkey,
+    pub member: Pubkey,
+    pub role: TreasuryRole,
+}
+
+#[event]
+pub struct TreasuryMemberRemoved {
+    pub treasury: Pubkey,
+    pub member: Pubkey,
+}
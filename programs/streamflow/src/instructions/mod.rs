@@ -0,0 +1,91 @@
+pub mod accept_split_share;
+pub mod accept_stream;
+pub mod approve_cancel;
+pub mod batch_create_from_template;
+pub mod cancel_stream;
+pub mod claim_parked;
+pub mod claim_vested;
+pub mod clawback;
+pub mod close_stream;
+pub mod crank_batch;
+pub mod crank_withdrawal;
+pub mod create_instant_stream;
+pub mod create_split_stream;
+pub mod create_stream;
+pub mod create_stream_from_template;
+pub mod create_streams_batch;
+pub mod create_treasury;
+pub mod create_vesting;
+pub mod diff_schedule;
+pub mod get_stream_details;
+pub mod get_stream_status;
+pub mod governance;
+pub mod manage_fee_vault;
+pub mod manage_program_config;
+pub mod manage_template;
+pub mod manage_treasury_members;
+pub mod onboard_recipient;
+pub mod preview_vesting;
+pub mod reduce_stream;
+pub mod register_recipient_token_account;
+pub mod schedule_fee_change;
+pub mod set_auto_withdraw_destination;
+pub mod simulate_withdraw;
+pub mod set_recipient_rate_cap;
+pub mod set_withdraw_destination;
+pub mod sweep_surplus;
+pub mod treasury_deposit;
+pub mod treasury_withdraw;
+pub mod update_metadata;
+pub mod update_rate;
+pub mod withdraw;
+pub mod withdraw_and_swap;
+pub mod withdraw_on_behalf;
+pub mod withdraw_split;
+pub mod withdraw_with_authorization;
+
+pub use accept_split_share::*;
+pub use accept_stream::*;
+pub use approve_cancel::*;
+pub use batch_create_from_template::*;
+pub use cancel_stream::*;
+pub use claim_parked::*;
+pub use claim_vested::*;
+pub use clawback::*;
+pub use close_stream::*;
+pub use crank_batch::*;
+pub use crank_withdrawal::*;
+pub use create_instant_stream::*;
+pub use create_split_stream::*;
+pub use create_stream::*;
+pub use create_stream_from_template::*;
+pub use create_streams_batch::*;
+pub use create_treasury::*;
+pub use create_vesting::*;
+pub use diff_schedule::*;
+pub use get_stream_details::*;
+pub use get_stream_status::*;
+pub use governance::*;
+pub use manage_fee_vault::*;
+pub use manage_program_config::*;
+pub use manage_template::*;
+pub use manage_treasury_members::*;
+pub use onboard_recipient::*;
+pub use preview_vesting::*;
+pub use reduce_stream::*;
+pub use register_recipient_token_account::*;
+pub use schedule_fee_change::*;
+pub use set_auto_withdraw_destination::*;
+pub use simulate_withdraw::*;
+pub use set_recipient_rate_cap::*;
+pub use set_withdraw_destination::*;
+pub use sweep_surplus::*;
+pub use treasury_deposit::*;
+pub use treasury_withdraw::*;
+pub use update_metadata::*;
+pub use update_rate::*;
+pub use withdraw::*;
+pub use withdraw_and_swap::*;
+pub use withdraw_on_behalf::*;
+pub use withdraw_split::*;
+pub use withdraw_with_authorization::*;
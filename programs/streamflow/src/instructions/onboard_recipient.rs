@@ -0,0 +1,203 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::StreamFlowError;
+use super::create_stream::{received_amount, validate_start_time};
+
+/// Onboard a recipient in one call: create their token account if needed,
+/// optionally drip SOL for fees, and open a stream from a template
+#[derive(Accounts)]
+pub struct OnboardRecipient<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"template",
+            authority.key().as_ref(),
+            &template.template_id.to_le_bytes(),
+        ],
+        bump = template.bump,
+        has_one = authority,
+        has_one = mint,
+    )]
+    pub template: Account<'info, StreamTemplate>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = StreamCounter::LEN,
+        seeds = [
+            b"stream_counter",
+            authority.key().as_ref(),
+            recipient.key().as_ref(),
+        ],
+        bump
+    )]
+    pub stream_counter: Account<'info, StreamCounter>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Stream::LEN,
+        seeds = [
+            b"stream",
+            authority.key().as_ref(),
+            recipient.key().as_ref(),
+            &stream_counter.count.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = stream,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// Token account the onboarding deposit is drawn from
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = authority,
+        constraint = funding_token_account.amount >= template.deposit_amount @ StreamFlowError::InsufficientFunds,
+    )]
+    pub funding_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = recipient.key() != authority.key() @ StreamFlowError::InvalidRecipient,
+    )]
+    pub recipient: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = MintStats::LEN,
+        seeds = [b"mint_stats", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<OnboardRecipient>, start_time: i64, drip_sol: bool) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    let current_time = Clock::get()?.unix_timestamp;
+    validate_start_time(start_time, current_time, false, false)?;
+
+    let template = &ctx.accounts.template;
+    let recipient = ctx.accounts.recipient.key();
+
+    let nonce = ctx.accounts.stream_counter.claim_nonce(
+        ctx.accounts.authority.key(),
+        recipient,
+        ctx.bumps.stream_counter,
+    )?;
+
+    let balance_before = ctx.accounts.escrow_token_account.amount;
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.funding_token_account.to_account_info(),
+                to: ctx.accounts.escrow_token_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        template.deposit_amount,
+    )?;
+    ctx.accounts.escrow_token_account.reload()?;
+    let deposited_amount = received_amount(balance_before, ctx.accounts.escrow_token_account.amount)?;
+
+    let stream = &mut ctx.accounts.stream;
+    stream.sender = ctx.accounts.authority.key();
+    stream.recipient = recipient;
+    stream.mint = ctx.accounts.mint.key();
+    stream.escrow_tokens = ctx.accounts.escrow_token_account.key();
+    stream.deposited_amount = deposited_amount;
+    stream.withdrawn_amount = 0;
+    template.apply_to(stream, start_time)?;
+    stream.template = Some(template.key());
+    stream.set_created_at(current_time);
+    stream.nonce = nonce;
+    stream.bump = ctx.bumps.stream;
+    stream.escrow_bump = ctx.bumps.escrow_token_account;
+    stream.validate()?;
+
+    let mint_stats = &mut ctx.accounts.mint_stats;
+    mint_stats.initialize_if_needed(ctx.accounts.mint.key(), ctx.bumps.mint_stats);
+    mint_stats.record_deposit(deposited_amount)?;
+
+    let dripped_lamports = if drip_sol { template.onboarding_lamports } else { 0 };
+    if dripped_lamports > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.recipient.to_account_info(),
+                },
+            ),
+            dripped_lamports,
+        )?;
+    }
+
+    let template = &mut ctx.accounts.template;
+    template.record_stream()?;
+
+    emit!(RecipientOnboarded {
+        template: template.key(),
+        stream: ctx.accounts.stream.key(),
+        recipient,
+        recipient_token_account: ctx.accounts.recipient_token_account.key(),
+        deposited_amount,
+        dripped_lamports,
+        start_time,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct RecipientOnboarded {
+    pub template: Pubkey,
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub recipient_token_account: Pubkey,
+    pub deposited_amount: u64,
+    pub dripped_lamports: u64,
+    pub start_time: i64,
+}
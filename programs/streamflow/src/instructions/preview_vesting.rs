@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::state::constants::MAX_VESTING_PREVIEW_POINTS;
+use crate::errors::StreamFlowError;
+
+/// Read-only view of a stream's vesting curve
+#[derive(Accounts)]
+pub struct PreviewVesting<'info> {
+    pub stream: Account<'info, Stream>,
+}
+
+pub fn handler(ctx: Context<PreviewVesting>, sample_points: u8) -> Result<Vec<(i64, u64)>> {
+    vesting_timeline(&ctx.accounts.stream, sample_points)
+}
+
+/// Cumulative streamed amount at `sample_points` evenly spaced timestamps,
+/// from `start_time` to `end_time` inclusive. Sampling is capped at
+/// `MAX_VESTING_PREVIEW_POINTS` to bound compute.
+pub fn vesting_timeline(stream: &Stream, sample_points: u8) -> Result<Vec<(i64, u64)>> {
+    require!(sample_points >= 2, StreamFlowError::InvalidAmount);
+    require!(
+        usize::from(sample_points) <= MAX_VESTING_PREVIEW_POINTS,
+        StreamFlowError::CapacityLimitReached
+    );
+
+    let duration = i128::from(stream.end_time) - i128::from(stream.start_time);
+    let intervals = i128::from(sample_points - 1);
+    (0..i128::from(sample_points))
+        .map(|i| {
+            let timestamp = stream.start_time + (duration * i / intervals) as i64;
+            Ok((timestamp, stream.calculate_streamed_amount(timestamp)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn assert_non_decreasing(timeline: &[(i64, u64)]) {
+        for pair in timeline.windows(2) {
+            assert!(pair[0].0 < pair[1].0);
+            assert!(pair[0].1 <= pair[1].1);
+        }
+    }
+
+    #[test]
+    fn test_linear_timeline() {
+        let stream = StreamBuilder::new().amount(1_000).build();
+        let timeline = vesting_timeline(&stream, 5).unwrap();
+        assert_eq!(
+            timeline,
+            vec![(100, 0), (125, 250), (150, 500), (175, 750), (200, 1_000)]
+        );
+    }
+
+    #[test]
+    fn test_cliff_timeline_is_monotonic_with_exact_endpoints() {
+        let stream = StreamBuilder::new()
+            .amount(1_000)
+            .duration(997)
+            .stream_type(StreamType::Cliff)
+            .cliff(400, 300)
+            .build();
+
+        let timeline = vesting_timeline(&stream, 64).unwrap();
+        assert_eq!(timeline.len(), 64);
+        assert_non_decreasing(&timeline);
+        assert_eq!(timeline[0], (100, 0));
+        assert_eq!(timeline[63], (1_097, 1_000));
+    }
+
+    #[test]
+    fn test_sample_points_are_bounded() {
+        let stream = StreamBuilder::new().build();
+        for sample_points in [0, 1] {
+            assert_eq!(
+                vesting_timeline(&stream, sample_points).unwrap_err(),
+                StreamFlowError::InvalidAmount.into()
+            );
+        }
+        assert_eq!(
+            vesting_timeline(&stream, 65).unwrap_err(),
+            StreamFlowError::CapacityLimitReached.into()
+        );
+        assert_eq!(vesting_timeline(&stream, 2).unwrap(), vec![(100, 0), (200, 1_000)]);
+    }
+}
@@ -0,0 +1,251 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::{StreamError, StreamFlowError};
+
+/// Partially cancel a stream: lower its deposit and refund the difference to
+/// the sender while the stream keeps running
+#[derive(Accounts)]
+pub struct ReduceStream<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &stream.nonce.to_le_bytes(),
+        ],
+        bump = stream.bump,
+        has_one = sender @ StreamError::UnauthorizedSender,
+        constraint = stream.cancelable_by_sender @ StreamError::UnauthorizedCancel,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump = stream.escrow_bump,
+        token::mint = stream.mint,
+        token::authority = stream,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = stream.mint,
+        token::authority = sender,
+    )]
+    pub sender_token_account: Account<'info, TokenAccount>,
+
+    /// Sender's aggregate rate cap for this recipient, if one is configured
+    #[account(
+        mut,
+        seeds = [
+            b"rate_cap",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+        ],
+        bump = rate_cap.bump,
+    )]
+    pub rate_cap: Option<Account<'info, RecipientRateCap>>,
+
+    pub sender: Signer<'info>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        mut,
+        seeds = [b"mint_stats", stream.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<ReduceStream>, new_deposit_amount: u64) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let stream_key = ctx.accounts.stream.key();
+    let stream = &mut ctx.accounts.stream;
+    let old_deposit_amount = stream.deposited_amount;
+    let refund = reduce_deposit(stream, new_deposit_amount, current_time)?;
+    stream.touch(current_time);
+    ctx.accounts.mint_stats.record_withdrawal(refund)?;
+    // Reducing the deposit to what was already withdrawn ends the stream
+    let completed = stream.complete_if_fully_withdrawn(
+        stream_key,
+        current_time,
+        ctx.accounts.rate_cap.as_deref_mut(),
+    )?;
+
+    let seeds = &[
+        b"stream",
+        stream.sender.as_ref(),
+        stream.recipient.as_ref(),
+        &stream.nonce.to_le_bytes(),
+        &[stream.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: ctx.accounts.sender_token_account.to_account_info(),
+                authority: stream.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        refund,
+    )?;
+
+    emit!(StreamReduced {
+        stream: stream_key,
+        old_deposit_amount,
+        new_deposit_amount,
+        refunded_amount: refund,
+        end_time: stream.end_time,
+        timestamp: current_time,
+    });
+    if let Some(completed) = completed {
+        emit!(completed);
+    }
+
+    Ok(())
+}
+
+/// Lower the stream's deposit to `new_deposit_amount`, returning the amount to
+/// refund. The new deposit must cover what has already vested.
+///
+/// Linear streams keep their rate and end earlier; the other curves are capped
+/// by the deposit already. Either way the recipient's vested amount at
+/// `current_time` is unchanged.
+pub fn reduce_deposit(stream: &mut Stream, new_deposit_amount: u64, current_time: i64) -> Result<u64> {
+    require!(
+        !matches!(stream.status, StreamStatus::Cancelled | StreamStatus::Completed),
+        StreamFlowError::StreamModificationNotAllowed
+    );
+    require!(new_deposit_amount > 0, StreamError::InvalidAmount);
+    require!(
+        new_deposit_amount < stream.deposited_amount,
+        StreamError::InvalidAmount
+    );
+
+    let vested = stream.calculate_streamed_amount(current_time)?;
+    require!(new_deposit_amount >= vested, StreamError::InsufficientFunds);
+
+    if stream.stream_type == StreamType::Linear {
+        // Rounding the duration down never lowers the rate, so nothing vested is lost
+        let duration = (stream.end_time - stream.start_time) as u128;
+        let new_duration = duration * new_deposit_amount as u128 / stream.deposited_amount as u128;
+        stream.end_time = stream
+            .start_time
+            .checked_add(new_duration as i64)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+    }
+
+    let refund = stream.deposited_amount - new_deposit_amount;
+    stream.deposited_amount = new_deposit_amount;
+
+    require!(
+        stream.calculate_streamed_amount(current_time)? >= vested,
+        StreamFlowError::StreamModificationNotAllowed
+    );
+    stream.check_withdrawn_within_vested(current_time)?;
+
+    Ok(refund)
+}
+
+#[event]
+pub struct StreamReduced {
+    pub stream: Pubkey,
+    pub old_deposit_amount: u64,
+    pub new_deposit_amount: u64,
+    pub refunded_amount: u64,
+    /// End time after the reduction; linear streams end earlier
+    pub end_time: i64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn linear_stream() -> Stream {
+        StreamBuilder::new().amount(1_000).start_time(1_000).duration(1_000).build()
+    }
+
+    #[test]
+    fn test_reduce_to_exactly_vested_amount() {
+        let mut stream = linear_stream();
+        stream.withdrawn_amount = 400;
+        let refund = reduce_deposit(&mut stream, 400, 1_400).unwrap();
+
+        assert_eq!(refund, 600);
+        assert_eq!(stream.deposited_amount, 400);
+        // Nothing more vests and the stream is fully vested now
+        assert_eq!(stream.end_time, 1_400);
+        assert_eq!(stream.calculate_streamed_amount(1_400).unwrap(), 400);
+        assert_eq!(stream.calculate_streamed_amount(5_000).unwrap(), 400);
+    }
+
+    #[test]
+    fn test_reduce_below_vested_amount_rejected() {
+        let mut stream = linear_stream();
+        assert_eq!(
+            reduce_deposit(&mut stream, 399, 1_400).unwrap_err(),
+            StreamError::InsufficientFunds.into()
+        );
+        assert_eq!(stream.deposited_amount, 1_000);
+        assert_eq!(stream.end_time, 2_000);
+    }
+
+    #[test]
+    fn test_reduce_keeps_linear_rate() {
+        let mut stream = linear_stream();
+        let refund = reduce_deposit(&mut stream, 700, 1_400).unwrap();
+
+        assert_eq!(refund, 300);
+        assert_eq!(stream.end_time, 1_700);
+        assert_eq!(stream.calculate_streamed_amount(1_400).unwrap(), 400);
+        assert_eq!(stream.calculate_streamed_amount(1_600).unwrap(), 600);
+        assert_eq!(stream.calculate_streamed_amount(1_700).unwrap(), 700);
+    }
+
+    #[test]
+    fn test_reduce_before_start() {
+        let mut stream = linear_stream();
+        let refund = reduce_deposit(&mut stream, 1, 500).unwrap();
+        assert_eq!(refund, 999);
+        assert_eq!(stream.calculate_streamed_amount(500).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_reduce_must_lower_deposit() {
+        let mut stream = linear_stream();
+        assert!(reduce_deposit(&mut stream, 1_000, 1_400).is_err());
+        assert!(reduce_deposit(&mut stream, 0, 900).is_err());
+    }
+
+    #[test]
+    fn test_reduce_terminal_stream_rejected() {
+        let mut stream = StreamBuilder::new().status(StreamStatus::Cancelled).build();
+        assert_eq!(
+            reduce_deposit(&mut stream, 1, 0).unwrap_err(),
+            StreamFlowError::StreamModificationNotAllowed.into()
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use crate::state::*;
+use crate::errors::StreamFlowError;
+
+#[derive(Accounts)]
+pub struct RegisterRecipientTokenAccount<'info> {
+    #[account(
+        mut,
+        has_one = recipient,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    pub recipient: Signer<'info>,
+
+    #[account(
+        constraint = new_token_account.mint == stream.mint @ StreamFlowError::InvalidTokenMint,
+        constraint = new_token_account.owner == recipient.key() @ StreamFlowError::InvalidTokenAccountOwner,
+    )]
+    pub new_token_account: Account<'info, TokenAccount>,
+}
+
+pub fn handler(ctx: Context<RegisterRecipientTokenAccount>) -> Result<()> {
+    let stream = &mut ctx.accounts.stream;
+
+    require!(
+        stream.status != StreamStatus::Cancelled && stream.status != StreamStatus::Completed,
+        StreamFlowError::StreamNotActive
+    );
+
+    let previous = stream.destination_token_account();
+    let new_token_account = ctx.accounts.new_token_account.key();
+    stream.recipient_token_account = Some(new_token_account);
+    stream.touch(Clock::get()?.unix_timestamp);
+
+    emit!(RecipientTokenAccountRegistered {
+        stream: stream.key(),
+        recipient: stream.recipient,
+        previous,
+        new_token_account,
+    });
+
+    msg!(
+        "Recipient token account for stream {} changed to {}",
+        stream.key(),
+        new_token_account
+    );
+
+    Ok(())
+}
+
+#[event]
+pub struct RecipientTokenAccountRegistered {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub previous: Pubkey,
+    pub new_token_account: Pubkey,
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::StreamError;
+
+#[derive(Accounts)]
+pub struct ScheduleFeeChange<'info> {
+    #[account(
+        mut,
+        constraint = stream.fee_recipient == Some(fee_authority.key()) @ StreamError::Unauthorized,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    /// The platform fee recipient announcing the change
+    pub fee_authority: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<ScheduleFeeChange>,
+    new_fee_percentage: u16,
+    effective_at: i64,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let stream = &mut ctx.accounts.stream;
+    let current_fee_percentage = stream.fee_percentage;
+
+    stream.schedule_fee_change(new_fee_percentage, effective_at, current_time)?;
+    stream.touch(current_time);
+
+    emit!(FeeChangeScheduled {
+        stream: stream.key(),
+        current_fee_percentage,
+        new_fee_percentage,
+        effective_at,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct FeeChangeScheduled {
+    pub stream: Pubkey,
+    pub current_fee_percentage: u16,
+    pub new_fee_percentage: u16,
+    pub effective_at: i64,
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use crate::state::*;
+use crate::errors::StreamFlowError;
+
+#[derive(Accounts)]
+pub struct SetAutoWithdrawDestination<'info> {
+    #[account(
+        mut,
+        has_one = recipient,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    pub recipient: Signer<'info>,
+
+    /// Omit to route automatic withdrawals back to the manual destination
+    #[account(
+        constraint = destination.mint == stream.mint @ StreamFlowError::InvalidTokenMint,
+    )]
+    pub destination: Option<Account<'info, TokenAccount>>,
+}
+
+pub fn handler(ctx: Context<SetAutoWithdrawDestination>) -> Result<()> {
+    let stream = &mut ctx.accounts.stream;
+    stream.auto_withdraw_destination = ctx.accounts.destination.as_ref().map(|d| d.key());
+    stream.touch(Clock::get()?.unix_timestamp);
+
+    emit!(AutoWithdrawDestinationSet {
+        stream: stream.key(),
+        recipient: stream.recipient,
+        destination: stream.auto_withdraw_token_account(),
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct AutoWithdrawDestinationSet {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub destination: Pubkey,
+}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct SetRecipientRateCap<'info> {
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = RecipientRateCap::LEN,
+        seeds = [
+            b"rate_cap",
+            sender.key().as_ref(),
+            recipient.as_ref(),
+        ],
+        bump
+    )]
+    pub rate_cap: Account<'info, RecipientRateCap>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<SetRecipientRateCap>, recipient: Pubkey, max_rate: u64) -> Result<()> {
+    let rate_cap = &mut ctx.accounts.rate_cap;
+    rate_cap.authority = ctx.accounts.sender.key();
+    rate_cap.recipient = recipient;
+    rate_cap.max_rate = max_rate;
+    rate_cap.bump = ctx.bumps.rate_cap;
+
+    emit!(RecipientRateCapSet {
+        sender: rate_cap.authority,
+        recipient,
+        max_rate,
+        current_rate: rate_cap.current_rate,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct RecipientRateCapSet {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub max_rate: u64,
+    pub current_rate: u64,
+}
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use crate::state::*;
+use crate::errors::{StreamError, StreamFlowError};
+
+#[derive(Accounts)]
+pub struct SetWithdrawDestination<'info> {
+    #[account(
+        mut,
+        has_one = recipient,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    pub recipient: Signer<'info>,
+
+    /// The destination token account; required unless clearing the destination
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+}
+
+pub fn handler(ctx: Context<SetWithdrawDestination>, destination: Option<Pubkey>) -> Result<()> {
+    let stream = &mut ctx.accounts.stream;
+
+    require!(
+        stream.status != StreamStatus::Cancelled && stream.status != StreamStatus::Completed,
+        StreamFlowError::StreamNotActive
+    );
+
+    if let Some(destination) = destination {
+        let token_account = ctx.accounts.destination_token_account
+            .as_ref()
+            .ok_or(StreamFlowError::TokenAccountNotFound)?;
+        require_keys_eq!(token_account.key(), destination, StreamError::InvalidTokenAccount);
+        check_withdraw_destination(stream, token_account)?;
+    }
+
+    let previous = stream.destination_token_account();
+    stream.withdraw_destination = destination;
+    stream.touch(Clock::get()?.unix_timestamp);
+
+    emit!(WithdrawDestinationSet {
+        stream: stream.key(),
+        recipient: stream.recipient,
+        previous,
+        destination: stream.destination_token_account(),
+    });
+
+    Ok(())
+}
+
+/// A recipient-chosen destination may belong to any wallet, but must hold
+/// the stream's mint
+pub fn check_withdraw_destination(stream: &Stream, token_account: &TokenAccount) -> Result<()> {
+    require_keys_eq!(token_account.mint, stream.mint, StreamError::InvalidMint);
+    Ok(())
+}
+
+#[event]
+pub struct WithdrawDestinationSet {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub previous: Pubkey,
+    /// Where withdrawals are now paid; the recipient's ATA once cleared
+    pub destination: Pubkey,
+}
@@ -0,0 +1,192 @@
+use anchor_lang::prelude::*;
+use crate::errors::StreamError;
+use crate::state::*;
+use super::withdraw::{plan_manual_withdrawal, prepare_withdrawal, WithdrawalPlan};
+
+/// Read-only preview of a withdrawal, computed by the same code path as
+/// `withdraw` so clients never have to re-implement fees and limits
+#[derive(Accounts)]
+pub struct SimulateWithdraw<'info> {
+    pub stream: Account<'info, Stream>,
+}
+
+/// Outcome of a simulated withdrawal
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct WithdrawSimulation {
+    pub plan: WithdrawalPlan,
+    /// Why `withdraw` would fail, or `None` if it would succeed
+    pub reason: Option<String>,
+}
+
+pub fn handler(
+    ctx: Context<SimulateWithdraw>,
+    amount: Option<u64>,
+    receipt_hash: [u8; 32],
+) -> Result<WithdrawSimulation> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let simulation = simulate_withdrawal(&ctx.accounts.stream, current_time, amount, receipt_hash);
+
+    emit!(WithdrawSimulated {
+        stream: ctx.accounts.stream.key(),
+        gross_amount: simulation.plan.gross_amount,
+        platform_fee: simulation.plan.platform_fee,
+        partner_fee: simulation.plan.partner_fee,
+        net_amount: simulation.plan.net_amount,
+        reason: simulation.reason.clone(),
+        timestamp: current_time,
+    });
+
+    Ok(simulation)
+}
+
+/// Run the withdraw decision logic on a copy of `stream`. Interest from the
+/// yield reserve is paid separately and is not included.
+pub fn simulate_withdrawal(
+    stream: &Stream,
+    current_time: i64,
+    amount: Option<u64>,
+    receipt_hash: [u8; 32],
+) -> WithdrawSimulation {
+    let mut stream = stream.clone();
+    // Same checks, in the same order, as the `Withdraw` constraints and handler
+    let outcome = (|| {
+        require!(stream.status != StreamStatus::Cancelled, StreamError::StreamCanceled);
+        require!(
+            stream.is_active() || stream.status == StreamStatus::Scheduled,
+            StreamError::StreamNotActive
+        );
+        stream.ensure_not_cancelled()?;
+        stream.ensure_accepted()?;
+        stream.check_withdrawal_receipt(&receipt_hash)?;
+        stream.begin_processing()?;
+        prepare_withdrawal(&mut stream, current_time);
+        plan_manual_withdrawal(&stream, current_time, amount, None)
+    })();
+
+    match outcome {
+        Ok(plan) => WithdrawSimulation { plan, reason: None },
+        Err(err) => WithdrawSimulation { plan: WithdrawalPlan::default(), reason: Some(error_reason(&err)) },
+    }
+}
+
+/// Name of the error a real withdrawal would fail with
+fn error_reason(err: &Error) -> String {
+    match err {
+        Error::AnchorError(anchor_error) => anchor_error.error_name.clone(),
+        Error::ProgramError(program_error) => program_error.program_error.to_string(),
+    }
+}
+
+#[event]
+pub struct WithdrawSimulated {
+    pub stream: Pubkey,
+    pub gross_amount: u64,
+    pub platform_fee: u64,
+    pub partner_fee: u64,
+    pub net_amount: u64,
+    pub reason: Option<String>,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    /// What `withdraw` would transfer, following the handler's steps
+    fn actual_withdraw(stream: &Stream, current_time: i64, amount: Option<u64>) -> Result<WithdrawalPlan> {
+        let mut stream = stream.clone();
+        prepare_withdrawal(&mut stream, current_time);
+        plan_manual_withdrawal(&stream, current_time, amount, None)
+    }
+
+    fn assert_matches_withdraw(stream: &Stream, current_time: i64, amount: Option<u64>) {
+        let simulation = simulate_withdrawal(stream, current_time, amount, [0; 32]);
+        match actual_withdraw(stream, current_time, amount) {
+            Ok(plan) => {
+                assert_eq!(simulation.plan, plan);
+                assert_eq!(simulation.reason, None);
+            }
+            Err(err) => {
+                assert_eq!(simulation.plan, WithdrawalPlan::default());
+                assert_eq!(simulation.reason, Some(error_reason(&err)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_simulation_matches_withdraw_with_fees() {
+        let mut stream = StreamBuilder::new().amount(20_000).fees(50, 100).build();
+        stream.partner_fee_active = true;
+
+        let simulation = simulate_withdrawal(&stream, 150, None, [0; 32]);
+        assert_eq!(
+            simulation.plan,
+            WithdrawalPlan {
+                gross_amount: 10_000,
+                platform_fee: 50,
+                partner_fee: 100,
+                net_amount: 9_850,
+                paid_through: 150,
+            }
+        );
+        for current_time in [99, 100, 150, 200, 500] {
+            assert_matches_withdraw(&stream, current_time, None);
+            assert_matches_withdraw(&stream, current_time, Some(1_000));
+        }
+    }
+
+    #[test]
+    fn test_simulation_applies_due_fee_change() {
+        let mut stream = StreamBuilder::new().amount(20_000).fees(50, 0).build();
+        stream.pending_fee = Some((100, 140));
+
+        assert_eq!(simulate_withdrawal(&stream, 130, None, [0; 32]).plan.platform_fee, 30);
+        assert_eq!(simulate_withdrawal(&stream, 150, None, [0; 32]).plan.platform_fee, 100);
+        assert_matches_withdraw(&stream, 150, None);
+        // The stream itself is untouched
+        assert_eq!(stream.fee_percentage, 50);
+    }
+
+    #[test]
+    fn test_simulation_reports_reason_when_nothing_to_receive() {
+        let stream = StreamBuilder::new().build();
+        assert_eq!(
+            simulate_withdrawal(&stream, 99, None, [0; 32]).reason.as_deref(),
+            Some("StreamNotStarted")
+        );
+        assert_eq!(
+            simulate_withdrawal(&stream, 100, None, [0; 32]).reason.as_deref(),
+            Some("NoTokensToWithdraw")
+        );
+
+        let mut all_or_nothing = StreamBuilder::new().build();
+        all_or_nothing.allow_partial_withdraw = false;
+        assert_matches_withdraw(&all_or_nothing, 150, Some(100));
+        assert_eq!(
+            simulate_withdrawal(&all_or_nothing, 150, Some(100), [0; 32]).reason.as_deref(),
+            Some("StreamModificationNotAllowed")
+        );
+
+        let mut receipt_mode = StreamBuilder::new().build();
+        receipt_mode.receipt_required = true;
+        assert_eq!(
+            simulate_withdrawal(&receipt_mode, 150, None, [0; 32]).reason.as_deref(),
+            Some("MissingWithdrawalReceipt")
+        );
+        assert_eq!(simulate_withdrawal(&receipt_mode, 150, None, [1; 32]).plan.gross_amount, 500);
+    }
+
+    #[test]
+    fn test_simulation_of_scheduled_and_cancelled_streams() {
+        let scheduled = StreamBuilder::new().status(StreamStatus::Scheduled).build();
+        assert_matches_withdraw(&scheduled, 150, None);
+        assert_eq!(simulate_withdrawal(&scheduled, 150, None, [0; 32]).plan.gross_amount, 500);
+
+        let cancelled = StreamBuilder::new().status(StreamStatus::Cancelled).build();
+        assert_eq!(
+            simulate_withdrawal(&cancelled, 150, None, [0; 32]).reason.as_deref(),
+            Some("StreamCanceled")
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::StreamError;
+
+/// Return escrow tokens above the stream's obligation to the sender
+#[derive(Accounts)]
+pub struct SweepSurplus<'info> {
+    #[account(
+        seeds = [
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &stream.nonce.to_le_bytes(),
+        ],
+        bump = stream.bump,
+        has_one = sender,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump = stream.escrow_bump,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = stream.mint,
+        token::authority = sender,
+    )]
+    pub sender_token_account: Account<'info, TokenAccount>,
+
+    pub sender: Signer<'info>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        mut,
+        seeds = [b"mint_stats", stream.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<SweepSurplus>) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    let stream = &ctx.accounts.stream;
+    let surplus = stream.escrow_surplus(ctx.accounts.escrow_token_account.amount);
+    require!(surplus > 0, StreamError::InvalidAmount);
+
+    // The surplus never arrived through a deposit, so count it in and out
+    // and leave the mint's locked total unchanged
+    let mint_stats = &mut ctx.accounts.mint_stats;
+    mint_stats.record_deposit(surplus)?;
+    mint_stats.record_withdrawal(surplus)?;
+
+    let seeds = &[
+        b"stream",
+        stream.sender.as_ref(),
+        stream.recipient.as_ref(),
+        &stream.nonce.to_le_bytes(),
+        &[stream.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: ctx.accounts.sender_token_account.to_account_info(),
+                authority: stream.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        surplus,
+    )?;
+
+    // Never leave the escrow short of what the recipient is owed
+    ctx.accounts.escrow_token_account.reload()?;
+    require!(
+        ctx.accounts.escrow_token_account.amount >= ctx.accounts.stream.escrow_obligation(),
+        StreamError::InsufficientBalance
+    );
+
+    emit!(SurplusSwept {
+        stream: ctx.accounts.stream.key(),
+        sender: ctx.accounts.sender.key(),
+        amount: surplus,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct SurplusSwept {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub amount: u64,
+}
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::StreamFlowError;
+
+/// Move tokens into a treasury's escrow. Anyone may fund a treasury.
+#[derive(Accounts)]
+pub struct TreasuryDeposit<'info> {
+    #[account(
+        seeds = [
+            b"treasury",
+            treasury.owner.as_ref(),
+            &treasury.treasury_id.to_le_bytes(),
+        ],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        address = treasury.escrow,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = treasury.mint,
+        token::authority = depositor,
+    )]
+    pub depositor_token_account: Account<'info, TokenAccount>,
+
+    pub depositor: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<TreasuryDeposit>, amount: u64) -> Result<()> {
+    require!(amount > 0, StreamFlowError::InvalidAmount);
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.depositor_token_account.to_account_info(),
+                to: ctx.accounts.escrow_token_account.to_account_info(),
+                authority: ctx.accounts.depositor.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    emit!(TreasuryDeposited {
+        treasury: ctx.accounts.treasury.key(),
+        depositor: ctx.accounts.depositor.key(),
+        amount,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct TreasuryDeposited {
+    pub treasury: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+}
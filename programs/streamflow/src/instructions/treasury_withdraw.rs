@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::StreamFlowError;
+
+/// Move tokens out of a treasury's escrow; requires the `Admin` role
+#[derive(Accounts)]
+pub struct TreasuryWithdraw<'info> {
+    #[account(
+        seeds = [
+            b"treasury",
+            treasury.owner.as_ref(),
+            &treasury.treasury_id.to_le_bytes(),
+        ],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"treasury_escrow",
+            treasury.key().as_ref(),
+        ],
+        bump = treasury.escrow_bump,
+        token::mint = treasury.mint,
+        token::authority = treasury,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = treasury.mint,
+    )]
+    pub destination_token_account: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<TreasuryWithdraw>, amount: u64) -> Result<()> {
+    require!(amount > 0, StreamFlowError::InvalidAmount);
+
+    let treasury = &ctx.accounts.treasury;
+    treasury.authorize_withdrawal(
+        &ctx.accounts.authority.key(),
+        amount,
+        ctx.accounts.escrow_token_account.amount,
+    )?;
+
+    let seeds = &[
+        b"treasury".as_ref(),
+        treasury.owner.as_ref(),
+        &treasury.treasury_id.to_le_bytes(),
+        &[treasury.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: ctx.accounts.destination_token_account.to_account_info(),
+                authority: treasury.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    emit!(TreasuryWithdrawn {
+        treasury: treasury.key(),
+        authority: ctx.accounts.authority.key(),
+        destination: ctx.accounts.destination_token_account.key(),
+        amount,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub treasury: Pubkey,
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
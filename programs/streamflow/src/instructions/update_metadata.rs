@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::StreamFlowError;
+
+#[derive(Accounts)]
+pub struct UpdateMetadata<'info> {
+    #[account(
+        mut,
+        has_one = sender,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    pub sender: Signer<'info>,
+
+    /// Required as a co-signer once the metadata is locked
+    #[account(
+        constraint = recipient.key() == stream.recipient @ StreamFlowError::InvalidRecipient
+    )]
+    pub recipient: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct LockMetadata<'info> {
+    #[account(mut)]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        constraint = authority.key() == stream.sender
+            || authority.key() == stream.recipient @ StreamFlowError::UnauthorizedAccess
+    )]
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<UpdateMetadata>,
+    category: Option<String>,
+    external_id: Option<String>,
+) -> Result<()> {
+    let stream = &mut ctx.accounts.stream;
+    let recipient_signed = ctx.accounts.recipient.is_some();
+
+    require!(
+        stream.can_update_metadata(true, recipient_signed),
+        StreamFlowError::StreamModificationNotAllowed
+    );
+
+    if let Some(category) = category {
+        stream.metadata.category = to_fixed_bytes(&category)?;
+    }
+    if let Some(external_id) = external_id {
+        stream.metadata.external_id = to_fixed_bytes(&external_id)?;
+    }
+
+    let current_time = Clock::get()?.unix_timestamp;
+    stream.touch(current_time);
+
+    emit!(MetadataUpdated {
+        stream: stream.key(),
+        category: stream.metadata.category,
+        external_id: stream.metadata.external_id,
+        cosigned: recipient_signed,
+        updated_at: current_time,
+    });
+
+    Ok(())
+}
+
+/// Rename a stream; the same signers as `handler` are required
+pub fn name_handler(ctx: Context<UpdateMetadata>, name: String) -> Result<()> {
+    let stream = &mut ctx.accounts.stream;
+    let recipient_signed = ctx.accounts.recipient.is_some();
+    let current_time = Clock::get()?.unix_timestamp;
+
+    rename_stream(stream, &name, recipient_signed, current_time)?;
+
+    emit!(StreamRenamed {
+        stream: stream.key(),
+        name: stream.name,
+        cosigned: recipient_signed,
+        updated_at: current_time,
+    });
+
+    Ok(())
+}
+
+/// Replace the stream's name, rejecting names longer than the 64-byte field
+pub fn rename_stream(stream: &mut Stream, name: &str, recipient_signed: bool, current_time: i64) -> Result<()> {
+    require!(
+        stream.can_update_metadata(true, recipient_signed),
+        StreamFlowError::StreamModificationNotAllowed
+    );
+    stream.set_name(name)?;
+    stream.touch(current_time);
+    Ok(())
+}
+
+pub fn lock_handler(ctx: Context<LockMetadata>) -> Result<()> {
+    let stream = &mut ctx.accounts.stream;
+
+    require!(!stream.metadata_locked, StreamFlowError::StreamModificationNotAllowed);
+    stream.metadata_locked = true;
+    stream.touch(Clock::get()?.unix_timestamp);
+
+    emit!(MetadataLocked {
+        stream: stream.key(),
+        locked_by: ctx.accounts.authority.key(),
+    });
+
+    Ok(())
+}
+
+fn to_fixed_bytes<const N: usize>(value: &str) -> Result<[u8; N]> {
+    let bytes = value.as_bytes();
+    require!(bytes.len() <= N, StreamFlowError::StreamMetadataTooLarge);
+
+    let mut out = [0u8; N];
+    out[..bytes.len()].copy_from_slice(bytes);
+    Ok(out)
+}
+
+#[event]
+pub struct MetadataUpdated {
+    pub stream: Pubkey,
+    pub category: [u8; 32],
+    pub external_id: [u8; 32],
+    pub cosigned: bool,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct StreamRenamed {
+    pub stream: Pubkey,
+    pub name: [u8; 64],
+    pub cosigned: bool,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct MetadataLocked {
+    pub stream: Pubkey,
+    pub locked_by: Pubkey,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    #[test]
+    fn test_rename_stream() {
+        let mut stream = StreamBuilder::new().build();
+        stream.set_name("payroll").unwrap();
+
+        rename_stream(&mut stream, "payroll 2025", false, 500).unwrap();
+        assert_eq!(&stream.name[..12], b"payroll 2025");
+        assert!(stream.name[12..].iter().all(|b| *b == 0));
+        assert_eq!(stream.metadata.updated_at, 500);
+    }
+
+    #[test]
+    fn test_rename_rejects_too_long_name() {
+        let mut stream = StreamBuilder::new().build();
+        stream.set_name("payroll").unwrap();
+
+        assert_eq!(
+            rename_stream(&mut stream, &"x".repeat(65), false, 500).unwrap_err(),
+            StreamFlowError::StreamMetadataTooLarge.into()
+        );
+        assert_eq!(&stream.name[..7], b"payroll");
+        rename_stream(&mut stream, &"x".repeat(64), false, 500).unwrap();
+    }
+
+    #[test]
+    fn test_rename_of_locked_stream_needs_recipient() {
+        let mut stream = StreamBuilder::new().build();
+        stream.metadata_locked = true;
+        assert_eq!(
+            rename_stream(&mut stream, "payroll", false, 500).unwrap_err(),
+            StreamFlowError::StreamModificationNotAllowed.into()
+        );
+        rename_stream(&mut stream, "payroll", true, 500).unwrap();
+    }
+}
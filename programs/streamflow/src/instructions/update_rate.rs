@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::StreamError;
+
+#[derive(Accounts)]
+pub struct UpdateRate<'info> {
+    #[account(
+        mut,
+        has_one = sender @ StreamError::UnauthorizedSender,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    pub sender: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<UpdateRate>,
+    new_rate_amount: u64,
+    new_rate_interval: u64,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let stream = &mut ctx.accounts.stream;
+    let (old_rate_amount, old_rate_interval, old_end_time) = (
+        stream.rate_amount,
+        stream.rate_interval_in_seconds,
+        stream.end_time,
+    );
+
+    stream.update_rate(new_rate_amount, new_rate_interval, current_time)?;
+
+    emit!(RateUpdated {
+        stream: stream.key(),
+        old_rate_amount,
+        old_rate_interval,
+        new_rate_amount,
+        new_rate_interval,
+        old_end_time,
+        new_end_time: stream.end_time,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct RateUpdated {
+    pub stream: Pubkey,
+    pub old_rate_amount: u64,
+    pub old_rate_interval: u64,
+    pub new_rate_amount: u64,
+    pub new_rate_interval: u64,
+    pub old_end_time: i64,
+    pub new_end_time: i64,
+    pub timestamp: i64,
+}
@@ -1,8 +1,11 @@
 ```rust
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 use crate::state::*;
-use crate::errors::StreamError;
+use crate::bps::MAX_BPS;
+use crate::errors::{StreamError, StreamFlowError};
+use crate::oracle::Price;
+use super::set_withdraw_destination::check_withdraw_destination;
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
@@ -12,29 +15,65 @@ pub struct Withdraw<'info> {
             b"stream",
             stream.sender.as_ref(),
             stream.recipient.as_ref(),
-            &stream.start_time.to_le_bytes(),
+            &stream.nonce.to_le_bytes(),
         ],
         bump = stream.bump,
         has_one = sender,
         has_one = recipient,
         has_one = mint,
-        constraint = stream.is_active @ StreamError::StreamNotActive,
+        constraint = stream.status != StreamStatus::Cancelled @ StreamError::StreamCanceled,
+        constraint = (stream.is_active() || stream.status == StreamStatus::Scheduled)
+            @ StreamError::StreamNotActive,
     )]
     pub stream: Account<'info, Stream>,
 
     #[account(
         mut,
-        associated_token::mint = mint,
-        associated_token::authority = stream,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump = stream.escrow_bump,
+        token::mint = mint,
+        token::authority = stream,
     )]
     pub stream_token_account: Account<'info, TokenAccount>,
 
+    /// CHECK: Validated in `validate_destination` so that a closed account
+    /// surfaces as `TokenAccountNotFound` rather than a deserialization error
+    #[account(mut)]
+    pub recipient_token_account: UncheckedAccount<'info>,
+
+    /// Reserve funded by the admin through `fund_yield_reserve`, paying
+    /// interest on delayed claims
+    #[account(
+        mut,
+        seeds = [b"yield_reserve", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = program_config,
+    )]
+    pub yield_reserve: Option<Account<'info, TokenAccount>>,
+
+    /// Sender's aggregate rate cap for this recipient, if one is configured
+    #[account(
+        mut,
+        seeds = [
+            b"rate_cap",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+        ],
+        bump = rate_cap.bump,
+    )]
+    pub rate_cap: Option<Account<'info, RecipientRateCap>>,
+
+    /// Program-wide totals for the mint
     #[account(
         mut,
-        associated_token::mint = mint,
-        associated_token::authority = recipient,
+        seeds = [b"mint_stats", stream.mint.as_ref()],
+        bump = mint_stats.bump,
     )]
-    pub recipient_token_account: Account<'info, TokenAccount>,
+    pub mint_stats: Account<'info, MintStats>,
 
     /// CHECK: This is the mint of the token being streamed
     pub mint: AccountInfo<'info>,
@@ -45,83 +84,248 @@ pub struct Withdraw<'info> {
     /// CHECK: This is the sender of the stream
     pub sender: AccountInfo<'info>,
 
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    /// CHECK: Pyth price account of a USD-pegged stream, decoded by `oracle::Price`
+    #[account(
+        address = stream.price_feed.unwrap_or_default() @ StreamFlowError::OraclePriceFeedNotFound,
+    )]
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    /// Fee recipient's vault for this mint; when given, the platform fee is
+    /// paid into `fee_vault_token_account` rather than a remaining account
+    #[account(
+        mut,
+        seeds = [b"fee_vault", stream.fee_recipient.unwrap_or_default().as_ref(), mint.key().as_ref()],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Option<Account<'info, FeeVault>>,
+
+    #[account(mut)]
+    pub fee_vault_token_account: Option<Account<'info, TokenAccount>>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Withdraw<'info> {
-    pub fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        CpiContext::new(
-            self.token_program.to_account_info(),
-            Transfer {
-                from: self.stream_token_account.to_account_info(),
-                to: self.recipient_token_account.to_account_info(),
-                authority: self.stream.to_account_info(),
-            },
-        )
+    /// Price the accrual of a USD-pegged stream; `None` for other streams
+    pub fn pegged_accrual(&self, current_time: i64) -> Result<Option<PeggedAccrual>> {
+        load_pegged_accrual(&self.stream, self.price_feed.as_deref(), &self.mint, current_time)
+    }
+
+    pub fn validate_destination(&self) -> Result<()> {
+        let destination = self.recipient_token_account.to_account_info();
+
+        // A recipient may close their token account mid-stream
+        require!(
+            !destination.data_is_empty() && *destination.owner == token::ID,
+            StreamFlowError::TokenAccountNotFound
+        );
+
+        let token_account = TokenAccount::try_deserialize(&mut &destination.data.borrow()[..])?;
+        check_destination(&self.stream, &destination.key(), &token_account)
+    }
+
+    /// The vault token account the platform fee is paid into, if a fee vault was given
+    pub fn fee_vault_destination(&self) -> Result<Option<AccountInfo<'info>>> {
+        match (&self.fee_vault, &self.fee_vault_token_account) {
+            (Some(fee_vault), Some(token_account)) => {
+                require_keys_eq!(
+                    token_account.key(),
+                    fee_vault.token_account,
+                    StreamFlowError::InvalidFeeConfiguration
+                );
+                Ok(Some(token_account.to_account_info()))
+            }
+            (None, None) => Ok(None),
+            _ => err!(StreamFlowError::InvalidFeeConfiguration),
+        }
+    }
+
+}
+
+/// Ensure a withdrawal pays the stream's current destination: its registered
+/// account or the recipient's ATA, owned by the current recipient, so a
+/// transferred stream can no longer pay the previous recipient. A
+/// `withdraw_destination` chosen by the recipient only has to match the mint.
+pub fn check_destination(stream: &Stream, destination: &Pubkey, token_account: &TokenAccount) -> Result<()> {
+    require_keys_eq!(
+        *destination,
+        stream.destination_token_account(),
+        StreamFlowError::InvalidTokenAccountOwner
+    );
+    if stream.withdraw_destination.is_some() {
+        return check_withdraw_destination(stream, token_account);
     }
+    require_keys_eq!(
+        token_account.owner,
+        stream.recipient,
+        StreamFlowError::InvalidTokenAccountOwner
+    );
+    require_keys_eq!(
+        token_account.mint,
+        stream.mint,
+        StreamFlowError::InvalidTokenMint
+    );
+    Ok(())
+}
+
+/// Token accounts receiving the platform and partner fees
+///
+/// `remaining_accounts` holds the platform fee account first when the stream
+/// has a `fee_recipient`, followed by the partner fee account when it has a
+/// `partner_fee_recipient`. Each must be a token account of the stream's mint
+/// owned by the stored recipient. When `platform_fee_to_vault` is set, the
+/// platform fee goes to the recipient's `FeeVault` and no account is taken
+/// for it.
+pub fn resolve_fee_accounts<'a, 'info>(
+    stream: &Stream,
+    remaining_accounts: &'a [AccountInfo<'info>],
+    platform_fee_to_vault: bool,
+) -> Result<(Option<&'a AccountInfo<'info>>, Option<&'a AccountInfo<'info>>)> {
+    let mut accounts = remaining_accounts.iter();
+    let mut next_fee_account = |recipient: Option<Pubkey>| -> Result<Option<&'a AccountInfo<'info>>> {
+        let recipient = match recipient {
+            Some(recipient) => recipient,
+            None => return Ok(None),
+        };
+        let account = accounts.next().ok_or(StreamFlowError::InvalidFeeConfiguration)?;
+        validate_fee_account(account, &recipient, &stream.mint)?;
+        Ok(Some(account))
+    };
+
+    let platform = if platform_fee_to_vault {
+        None
+    } else {
+        next_fee_account(stream.fee_recipient)?
+    };
+    let partner = next_fee_account(stream.partner_fee_recipient)?;
+    Ok((platform, partner))
+}
+
+/// A fee account must be a token account of `mint` owned by `recipient`
+pub fn validate_fee_account(account: &AccountInfo, recipient: &Pubkey, mint: &Pubkey) -> Result<()> {
+    require!(
+        !account.data_is_empty() && *account.owner == token::ID,
+        StreamFlowError::TokenAccountNotFound
+    );
+    let token_account = TokenAccount::try_deserialize(&mut &account.data.borrow()[..])?;
+    require_keys_eq!(token_account.owner, *recipient, StreamFlowError::InvalidFeeConfiguration);
+    require_keys_eq!(token_account.mint, *mint, StreamFlowError::InvalidTokenMint);
+    Ok(())
 }
 
-pub fn handler(ctx: Context<Withdraw>, amount: Option<u64>) -> Result<()> {
+pub fn handler<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, Withdraw<'info>>,
+    amount: Option<u64>,
+    receipt_hash: [u8; 32],
+) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    ctx.accounts.stream.ensure_not_cancelled()?;
+    ctx.accounts.stream.ensure_accepted()?;
+    ctx.accounts.validate_destination()?;
+    ctx.accounts.stream.check_withdrawal_receipt(&receipt_hash)?;
+
+    // Persist the guard so a re-entrant call through a CPI observes it
+    ctx.accounts.stream.begin_processing()?;
+    ctx.accounts.stream.exit(ctx.program_id)?;
+
+    let stream_key = ctx.accounts.stream.key();
     let stream = &mut ctx.accounts.stream;
     let clock = Clock::get()?;
     let current_time = clock.unix_timestamp;
 
-    // Calculate withdrawable amount
-    let withdrawable_amount = stream.calculate_withdrawable_amount(current_time)?;
-    
-    require!(withdrawable_amount > 0, StreamError::NoTokensToWithdraw);
+    prepare_and_journal_withdrawal(stream, stream_key, current_time)?;
 
-    // Determine actual withdrawal amount
-    let withdrawal_amount = match amount {
-        Some(requested_amount) => {
-            require!(
-                requested_amount <= withdrawable_amount,
-                StreamError::InsufficientWithdrawableBalance
-            );
-            requested_amount
-        }
-        None => withdrawable_amount,
-    };
+    let pegged = ctx.accounts.pegged_accrual(current_time)?;
+    let fee_vault_destination = ctx.accounts.fee_vault_destination()?;
+    let stream = &mut ctx.accounts.stream;
 
-    // Update stream state
-    stream.withdrawn_amount = stream.withdrawn_amount
-        .checked_add(withdrawal_amount)
-        .ok_or(StreamError::MathOverflow)?;
+    // Determine actual withdrawal amount and the fees taken from it
+    let plan = plan_manual_withdrawal(stream, current_time, amount, pegged.as_ref())?;
+    let WithdrawalPlan { gross_amount: withdrawal_amount, platform_fee, partner_fee, .. } = plan;
+    let (platform_fee_account, partner_fee_account) =
+        resolve_fee_accounts(stream, ctx.remaining_accounts, fee_vault_destination.is_some())?;
+    let platform_fee_account = fee_vault_destination.or_else(|| platform_fee_account.cloned());
 
-    stream.last_withdrawn_at = current_time;
+    // Interest on the balance left unclaimed, bounded by what the reserve holds
+    let interest_amount = match &ctx.accounts.yield_reserve {
+        Some(reserve) => std::cmp::min(stream.accrued_interest(current_time)?, reserve.amount),
+        None => 0,
+    };
 
-    // Check if stream is fully withdrawn
-    if stream.withdrawn_amount >= stream.deposited_amount {
-        stream.is_active = false;
-        stream.end_time = Some(current_time);
+    // Update stream state and check if stream is fully withdrawn
+    let completed = apply_withdrawal(
+        stream,
+        stream_key,
+        &plan,
+        current_time,
+        &mut ctx.accounts.mint_stats,
+        ctx.accounts.rate_cap.as_deref_mut(),
+    )?;
+
+    // Transfer tokens from stream account to recipient and fee recipients
+    transfer_withdrawal(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.stream_token_account.to_account_info(),
+        stream,
+        ctx.accounts.recipient_token_account.to_account_info(),
+        &plan,
+        platform_fee_account,
+        partner_fee_account.cloned(),
+    )?;
+    if let Some(fee_vault) = ctx.accounts.fee_vault.as_mut() {
+        fee_vault.record_fee(platform_fee)?;
     }
 
-    // Transfer tokens from stream account to recipient
-    let seeds = &[
-        b"stream",
-        stream.sender.as_ref(),
-        stream.recipient.as_ref(),
-        &stream.start_time.to_le_bytes(),
-        &[stream.bump],
-    ];
-    let signer_seeds = &[&seeds[..]];
+    if interest_amount > 0 {
+        if let Some(reserve) = &ctx.accounts.yield_reserve {
+            // The reserve is owned by the program config, which signs with its stored bump
+            let config_seeds = &[b"program_config".as_ref(), &[ctx.accounts.program_config.bump]];
 
-    token::transfer(
-        ctx.accounts.transfer_context().with_signer(signer_seeds),
-        withdrawal_amount,
-    )?;
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: reserve.to_account_info(),
+                        to: ctx.accounts.recipient_token_account.to_account_info(),
+                        authority: ctx.accounts.program_config.to_account_info(),
+                    },
+                    &[&config_seeds[..]],
+                ),
+                interest_amount,
+            )?;
+
+            emit!(InterestPaid {
+                stream: stream_key,
+                recipient: ctx.accounts.recipient.key(),
+                amount: interest_amount,
+                timestamp: current_time,
+            });
+        }
+    }
+
+    stream.end_processing();
 
     // Emit withdrawal event
-    emit!(WithdrawEvent {
-        stream: ctx.accounts.stream.key(),
-        recipient: ctx.accounts.recipient.key(),
-        amount: withdrawal_amount,
-        timestamp: current_time,
-        remaining_balance: stream.deposited_amount
-            .checked_sub(stream.withdrawn_amount)
-            .unwrap_or(0),
-    });
+    emit!(WithdrawEvent::new(
+        stream_key,
+        stream,
+        withdrawal_amount,
+        platform_fee,
+        partner_fee,
+        current_time,
+        receipt_hash,
+    ));
+    if let Some(completed) = completed {
+        emit!(completed);
+    }
 
     msg!(
         "Withdrawn {} tokens from stream. Remaining balance: {}",
@@ -132,95 +336,333 @@ pub fn handler(ctx: Context<Withdraw>, amount: Option<u64>) -> Result<()> {
     Ok(())
 }
 
+/// Withdraw `bps` basis points of what is currently withdrawable, through
+/// the same checks and transfers as `handler`
+pub fn percentage_handler<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, Withdraw<'info>>,
+    bps: u16,
+    receipt_hash: [u8; 32],
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let withdrawable = match ctx.accounts.pegged_accrual(current_time)? {
+        Some(accrual) => accrual.amount,
+        None => ctx.accounts.stream.calculate_withdrawable_amount(current_time)?,
+    };
+    let amount = percentage_of_withdrawable(withdrawable, bps)?;
+    require!(amount > 0, StreamError::NoTokensToWithdraw);
+    handler(ctx, Some(amount), receipt_hash)
+}
+
+/// `withdrawable * bps / MAX_BPS`, rounded down
+pub fn percentage_of_withdrawable(withdrawable: u64, bps: u16) -> Result<u64> {
+    require!(bps <= MAX_BPS, StreamError::InvalidAmount);
+    let amount = u128::from(withdrawable)
+        .checked_mul(u128::from(bps))
+        .ok_or(StreamError::MathOverflow)?
+        / u128::from(MAX_BPS);
+    Ok(u64::try_from(amount).map_err(|_| StreamError::MathOverflow)?)
+}
+
 #[event]
-pub struct WithdrawEvent {
+pub struct InterestPaid {
     pub stream: Pubkey,
     pub recipient: Pubkey,
     pub amount: u64,
     pub timestamp: i64,
-    pub remaining_balance: u64,
 }
 
-impl Stream {
-    pub fn calculate_withdrawable_amount(&self, current_time: i64) -> Result<u64> {
-        // If stream hasn't started yet
-        if current_time < self.start_time {
-            return Ok(0);
-        }
+/// State changes a withdrawal applies before deciding the amount: a scheduled
+/// stream starts streaming, and an announced fee change that has come due
+/// takes effect. Returns the applied `(old_fee, new_fee)`, if any.
+pub fn prepare_withdrawal(stream: &mut Stream, current_time: i64) -> Option<(u16, u16)> {
+    stream.activate_if_started(current_time);
+    stream.apply_pending_fee(current_time)
+}
 
-        // If stream has ended or is cancelled
-        if let Some(end_time) = self.end_time {
-            if current_time >= end_time {
-                return Ok(self.deposited_amount.checked_sub(self.withdrawn_amount).unwrap_or(0));
-            }
+/// `prepare_withdrawal`, journaling an applied fee change as an amendment by
+/// the fee recipient
+pub fn prepare_and_journal_withdrawal(
+    stream: &mut Stream,
+    stream_key: Pubkey,
+    current_time: i64,
+) -> Result<()> {
+    if let Some((old_fee, new_fee)) = prepare_withdrawal(stream, current_time) {
+        let authority = stream.fee_recipient.unwrap_or_default();
+        stream.record_amendment(
+            stream_key,
+            AmendmentKind::FeeChange,
+            &old_fee.to_le_bytes(),
+            &new_fee.to_le_bytes(),
+            authority,
+            current_time,
+        )?;
+    }
+    Ok(())
+}
+
+/// USD accrued by a pegged stream since its last withdrawal, priced in tokens
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PeggedAccrual {
+    /// Tokens the accrual buys, capped by what the schedule has vested
+    pub amount: u64,
+    /// Start of the accrual period
+    pub since: i64,
+    /// End of the accrual period
+    pub until: i64,
+}
+
+impl PeggedAccrual {
+    /// Time up to which the accrual is paid once `withdrawn` of it is taken,
+    /// so a partial withdrawal leaves the rest accruing
+    pub fn paid_through(&self, withdrawn: u64) -> i64 {
+        if withdrawn >= self.amount {
+            return self.until;
         }
+        let elapsed = (self.until - self.since) as u128;
+        self.since + (elapsed * u128::from(withdrawn) / u128::from(self.amount)) as i64
+    }
+}
 
-        // Calculate streamed amount based on time elapsed
-        let time_elapsed = current_time
-            .checked_sub(self.start_time)
-            .ok_or(StreamError::MathOverflow)?;
+/// Price the USD accrued by `stream` at `current_time`, capped by the
+/// tokens its schedule has vested
+pub fn pegged_accrual(stream: &Stream, price: &Price, decimals: u8, current_time: i64) -> Result<PeggedAccrual> {
+    let pegged = stream.usd_pegged_amount(price, decimals, current_time)?;
+    let vested = stream.calculate_withdrawable_amount(current_time)?;
+    Ok(PeggedAccrual {
+        amount: std::cmp::min(pegged, vested),
+        since: stream.last_withdrawn_at,
+        until: current_time,
+    })
+}
 
-        let total_duration = match self.stream_type {
-            StreamType::Linear => {
-                self.end_time.unwrap_or(current_time)
-                    .checked_sub(self.start_time)
-                    .ok_or(StreamError::MathOverflow)?
-            }
-            StreamType::Cliff => {
-                // For cliff vesting, check if cliff period has passed
-                if current_time < self.start_time + self.cliff_amount.unwrap_or(0) {
-                    return Ok(0);
-                }
-                self.end_time.unwrap_or(current_time)
-                    .checked_sub(self.start_time)
-                    .ok_or(StreamError::MathOverflow)?
-            }
-        };
+/// Read the oracle price of a USD-pegged stream from `price_feed` and price
+/// its accrual; `None` for other streams. Every instruction paying out of a
+/// stream passes the result to `plan_withdrawal` or `cancel_split`.
+pub fn load_pegged_accrual(
+    stream: &Stream,
+    price_feed: Option<&AccountInfo>,
+    mint: &AccountInfo,
+    current_time: i64,
+) -> Result<Option<PeggedAccrual>> {
+    if stream.usd_rate_per_second.is_none() {
+        return Ok(None);
+    }
+    let feed = price_feed.ok_or(StreamFlowError::OraclePriceFeedNotFound)?;
+    let price = Price::from_pyth_account(&feed.try_borrow_data()?)?;
+    price.ensure_fresh(current_time, stream.max_price_age)?;
+    let decimals = Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])?.decimals;
+    pegged_accrual(stream, &price, decimals, current_time).map(Some)
+}
 
-        // Prevent division by zero
-        if total_duration == 0 {
-            return Ok(self.deposited_amount.checked_sub(self.withdrawn_amount).unwrap_or(0));
+/// Amounts moved by one withdrawal
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct WithdrawalPlan {
+    /// Amount released from the stream, before fees
+    pub gross_amount: u64,
+    pub platform_fee: u64,
+    pub partner_fee: u64,
+    /// Amount received by the recipient
+    pub net_amount: u64,
+    /// The stream's `last_withdrawn_at` once the withdrawal is applied
+    pub paid_through: i64,
+}
+
+/// Decide what a withdrawal of `amount` (everything withdrawable if `None`)
+/// transfers at `current_time`, fees included. `pegged` is the accrual of a
+/// USD-pegged stream, from `load_pegged_accrual`.
+pub fn plan_withdrawal(
+    stream: &Stream,
+    current_time: i64,
+    amount: Option<u64>,
+    pegged: Option<&PeggedAccrual>,
+) -> Result<WithdrawalPlan> {
+    let gross_amount = resolve_withdrawal_amount(stream, current_time, amount, pegged)?;
+    // Partner fees depend on what was withdrawn before this withdrawal
+    let (net_amount, platform_fee, partner_fee) = stream.split_withdrawal(gross_amount)?;
+    // A partial pegged withdrawal leaves the unpaid seconds accruing
+    let paid_through = pegged.map_or(current_time, |accrual| accrual.paid_through(gross_amount));
+    Ok(WithdrawalPlan { gross_amount, platform_fee, partner_fee, net_amount, paid_through })
+}
+
+/// `plan_withdrawal` for a withdrawal someone asked for directly, which must
+/// respect the stream's `min_withdrawal_interval`. Cranked withdrawals run on
+/// the stream's own schedule and skip the cooldown.
+pub fn plan_manual_withdrawal(
+    stream: &Stream,
+    current_time: i64,
+    amount: Option<u64>,
+    pegged: Option<&PeggedAccrual>,
+) -> Result<WithdrawalPlan> {
+    stream.check_withdrawal_cooldown(current_time)?;
+    plan_withdrawal(stream, current_time, amount, pegged)
+}
+
+/// Resolve how many tokens a withdrawal request should transfer at `current_time`
+pub fn resolve_withdrawal_amount(
+    stream: &Stream,
+    current_time: i64,
+    amount: Option<u64>,
+    pegged: Option<&PeggedAccrual>,
+) -> Result<u64> {
+    // Distinguish "not started" from "nothing vested yet"
+    require!(current_time >= stream.start_time, StreamError::StreamNotStarted);
+
+    // A pegged stream releases what its USD accrual buys at the oracle price
+    let withdrawable_amount = match pegged {
+        Some(accrual) => accrual.amount,
+        None => {
+            require!(stream.usd_rate_per_second.is_none(), StreamFlowError::OraclePriceFeedNotFound);
+            stream.calculate_withdrawable_amount(current_time)?
         }
+    };
+    
+    require!(withdrawable_amount > 0, StreamError::NoTokensToWithdraw);
 
-        // Calculate proportional amount
-        let streamed_amount = (self.deposited_amount as u128)
-            .checked_mul(time_elapsed as u128)
-            .ok_or(StreamError::MathOverflow)?
-            .checked_div(total_duration as u128)
-            .ok_or(StreamError::MathOverflow)? as u64;
+    match amount {
+        Some(requested_amount) => {
+            require!(
+                requested_amount <= withdrawable_amount,
+                StreamError::InsufficientWithdrawableBalance
+            );
+            // All-or-nothing grants must claim everything withdrawable
+            require!(
+                stream.allow_partial_withdraw || requested_amount == withdrawable_amount,
+                StreamFlowError::StreamModificationNotAllowed
+            );
+            Ok(requested_amount)
+        }
+        None => Ok(withdrawable_amount),
+    }
+}
 
-        // Ensure we don't exceed deposited amount
-        let streamed_amount = std::cmp::min(streamed_amount, self.deposited_amount);
+/// Record a planned withdrawal on `stream` and in the mint's totals. Returns
+/// the event to emit when it completes the stream.
+pub fn apply_withdrawal(
+    stream: &mut Stream,
+    stream_key: Pubkey,
+    plan: &WithdrawalPlan,
+    current_time: i64,
+    mint_stats: &mut MintStats,
+    rate_cap: Option<&mut RecipientRateCap>,
+) -> Result<Option<StreamCompleted>> {
+    stream.withdrawn_amount = stream.withdrawn_amount
+        .checked_add(plan.gross_amount)
+        .ok_or(StreamError::MathOverflow)?;
+    stream.check_withdrawn_within_vested(current_time)?;
+    stream.last_withdrawn_at = plan.paid_through;
+    stream.touch(current_time);
+    stream.update_partner_fee_activation();
+    mint_stats.record_withdrawal(plan.gross_amount)?;
+    stream.complete_if_fully_withdrawn(stream_key, current_time, rate_cap)
+}
 
-        // Calculate withdrawable amount (streamed - already withdrawn)
-        let withdrawable = streamed_amount
-            .checked_sub(self.withdrawn_amount)
-            .unwrap_or(0);
+/// Pay a planned withdrawal out of the stream's escrow: the net amount to
+/// `destination`, then each fee to its account, signed with the stream seeds
+pub fn transfer_withdrawal<'info>(
+    token_program: AccountInfo<'info>,
+    escrow: AccountInfo<'info>,
+    stream: &Account<'info, Stream>,
+    destination: AccountInfo<'info>,
+    plan: &WithdrawalPlan,
+    platform_fee_account: Option<AccountInfo<'info>>,
+    partner_fee_account: Option<AccountInfo<'info>>,
+) -> Result<()> {
+    let seeds = &[
+        b"stream",
+        stream.sender.as_ref(),
+        stream.recipient.as_ref(),
+        &stream.nonce.to_le_bytes(),
+        &[stream.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
 
-        Ok(withdrawable)
+    for (to, amount) in [
+        (Some(destination), plan.net_amount),
+        (platform_fee_account, plan.platform_fee),
+        (partner_fee_account, plan.partner_fee),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        let to = to.ok_or(StreamFlowError::InvalidFeeConfiguration)?;
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                Transfer {
+                    from: escrow.clone(),
+                    to,
+                    authority: stream.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+    }
+    Ok(())
+}
+
+/// The single event emitted for every withdrawal
+#[event]
+pub struct WithdrawEvent {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    /// Amount released from the stream, before fees
+    pub gross_amount: u64,
+    pub platform_fee: u64,
+    pub partner_fee: u64,
+    /// Amount received by the recipient
+    pub net_amount: u64,
+    /// Cumulative amount withdrawn after this withdrawal
+    pub withdrawn_amount: u64,
+    pub remaining_balance: u64,
+    pub timestamp: i64,
+    /// Hash of the off-chain invoice this claim settles; zero when unused
+    pub receipt_hash: [u8; 32],
+}
+
+impl WithdrawEvent {
+    /// Build the event from the stream state after the withdrawal was applied
+    pub fn new(
+        stream_key: Pubkey,
+        stream: &Stream,
+        gross_amount: u64,
+        platform_fee: u64,
+        partner_fee: u64,
+        timestamp: i64,
+        receipt_hash: [u8; 32],
+    ) -> Self {
+        Self {
+            stream: stream_key,
+            recipient: stream.recipient,
+            gross_amount,
+            platform_fee,
+            partner_fee,
+            net_amount: gross_amount
+                .saturating_sub(platform_fee)
+                .saturating_sub(partner_fee),
+            withdrawn_amount: stream.withdrawn_amount,
+            remaining_balance: stream.deposited_amount.saturating_sub(stream.withdrawn_amount),
+            timestamp,
+            receipt_hash,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::instructions::cancel_stream::cancel_split;
+    use crate::state::constants::MAX_RECIPIENT_HISTORY;
+    use crate::state::stream::StreamBuilder;
 
     #[test]
     fn test_calculate_withdrawable_amount_linear() {
-        let mut stream = Stream {
-            sender: Pubkey::default(),
-            recipient: Pubkey::default(),
-            mint: Pubkey::default(),
-            deposited_amount: 1000,
-            withdrawn_amount: 0,
-            start_time: 100,
-            end_time: Some(200),
-            stream_type: StreamType::Linear,
-            is_active: true,
-            cliff_amount: None,
-            last_withdrawn_at: 100,
-            bump: 255,
-        };
+        let mut stream = StreamBuilder::new()
+            .amount(1000)
+            .start_time(100)
+            .duration(100)
+            .stream_type(StreamType::Linear)
+            .build();
 
         // Test before start
         assert_eq!(stream.calculate_withdrawable_amount(50).unwrap(), 0);
@@ -241,29 +683,583 @@ mod tests {
 
     #[test]
     fn test_calculate_withdrawable_amount_cliff() {
-        let stream = Stream {
-            sender: Pubkey::default(),
-            recipient: Pubkey::default(),
-            mint: Pubkey::default(),
-            deposited_amount: 1000,
-            withdrawn_amount: 0,
-            start_time: 100,
-            end_time: Some(200),
-            stream_type: StreamType::Cliff,
-            is_active: true,
-            cliff_amount: Some(50), // 50 second cliff
-            last_withdrawn_at: 100,
-            bump: 255,
-        };
+        let stream = StreamBuilder::new()
+            .amount(1000)
+            .start_time(100)
+            .duration(100)
+            .stream_type(StreamType::Cliff)
+            .cliff(150, 0) // 50 second cliff
+            .build();
 
         // Test before cliff
         assert_eq!(stream.calculate_withdrawable_amount(140).unwrap(), 0);
 
-        // Test after cliff at 50% completion
-        assert_eq!(stream.calculate_withdrawable_amount(150).unwrap(), 500);
+        // Nothing has accrued yet at the cliff itself
+        assert_eq!(stream.calculate_withdrawable_amount(150).unwrap(), 0);
+
+        // Halfway between the cliff and the end
+        assert_eq!(stream.calculate_withdrawable_amount(175).unwrap(), 500);
 
         // Test at completion
         assert_eq!(stream.calculate_withdrawable_amount(200).unwrap(), 1000);
     }
+
+    #[test]
+    fn test_withdraw_before_start_is_rejected() {
+        let stream = StreamBuilder::new().start_time(100).duration(100).build();
+
+        assert_eq!(
+            resolve_withdrawal_amount(&stream, 99, None, None).unwrap_err(),
+            StreamError::StreamNotStarted.into()
+        );
+        assert_eq!(
+            resolve_withdrawal_amount(&stream, 100, None, None).unwrap_err(),
+            StreamError::NoTokensToWithdraw.into()
+        );
+        assert_eq!(resolve_withdrawal_amount(&stream, 150, None, None).unwrap(), 500);
+    }
+
+    #[test]
+    fn test_partial_withdraw_allowed_by_default() {
+        let stream = StreamBuilder::new().start_time(100).duration(100).build();
+        assert_eq!(resolve_withdrawal_amount(&stream, 150, Some(200), None).unwrap(), 200);
+    }
+
+    #[test]
+    fn test_partial_withdraw_rejected_when_disabled() {
+        let mut stream = StreamBuilder::new().start_time(100).duration(100).build();
+        stream.allow_partial_withdraw = false;
+
+        assert_eq!(
+            resolve_withdrawal_amount(&stream, 150, Some(200), None).unwrap_err(),
+            StreamFlowError::StreamModificationNotAllowed.into()
+        );
+        // Claiming exactly everything withdrawable, explicitly or implicitly, succeeds
+        assert_eq!(resolve_withdrawal_amount(&stream, 150, Some(500), None).unwrap(), 500);
+        assert_eq!(resolve_withdrawal_amount(&stream, 150, None, None).unwrap(), 500);
+    }
+
+    #[test]
+    fn test_receipt_hash_required_in_receipt_mode() {
+        let mut stream = StreamBuilder::new().build();
+        assert!(stream.check_withdrawal_receipt(&[0; 32]).is_ok());
+
+        stream.receipt_required = true;
+        assert_eq!(
+            stream.check_withdrawal_receipt(&[0; 32]).unwrap_err(),
+            StreamError::MissingWithdrawalReceipt.into()
+        );
+        assert!(stream.check_withdrawal_receipt(&[7; 32]).is_ok());
+    }
+
+    #[test]
+    fn test_receipt_hash_threads_through_event() {
+        let mut stream = StreamBuilder::new().amount(1000).build();
+        stream.withdrawn_amount = 400;
+        let key = Pubkey::new_unique();
+        let receipt = [0xab; 32];
+
+        let event = WithdrawEvent::new(key, &stream, 400, 0, 0, 150, receipt);
+        assert_eq!(event.stream, key);
+        assert_eq!(event.recipient, stream.recipient);
+        assert_eq!(event.remaining_balance, 600);
+        assert_eq!(event.receipt_hash, receipt);
+    }
+
+    #[test]
+    fn test_withdraw_event_fields() {
+        let mut stream = StreamBuilder::new().amount(10_000).build();
+        stream.withdrawn_amount = 6_000;
+        let key = Pubkey::new_unique();
+
+        // 4000 gross with a 0.5% platform fee and 1% partner fee
+        let event = WithdrawEvent::new(key, &stream, 4_000, 20, 40, 300, [0; 32]);
+        assert_eq!(event.stream, key);
+        assert_eq!(event.recipient, stream.recipient);
+        assert_eq!(event.gross_amount, 4_000);
+        assert_eq!(event.platform_fee, 20);
+        assert_eq!(event.partner_fee, 40);
+        assert_eq!(event.net_amount, 3_940);
+        assert_eq!(event.withdrawn_amount, 6_000);
+        assert_eq!(event.remaining_balance, 4_000);
+        assert_eq!(event.timestamp, 300);
+    }
+
+    #[test]
+    fn test_crafted_over_withdraw_fails_integrity_check() {
+        let mut stream = StreamBuilder::new().amount(1000).start_time(100).duration(100).build();
+
+        // Bypass resolve_withdrawal_amount and claim the full deposit mid-stream
+        stream.withdrawn_amount += 1000;
+        assert!(matches!(
+            stream.check_withdrawn_within_vested(150),
+            Err(StreamFlowError::DataIntegrityCheckFailed)
+        ));
+    }
+
+    #[test]
+    fn test_withdraw_deducts_platform_and_partner_fees() {
+        // 0.5% platform fee and 1% partner fee, partner fee already active
+        let mut stream = StreamBuilder::new().amount(20_000).fees(50, 100).build();
+        stream.partner_fee_active = true;
+
+        let gross = resolve_withdrawal_amount(&stream, 150, None, None).unwrap();
+        assert_eq!(gross, 10_000);
+        let (net, platform_fee, partner_fee) = stream.split_withdrawal(gross).unwrap();
+        assert_eq!(platform_fee, 50);
+        assert_eq!(partner_fee, 100);
+        assert_eq!(net, 9_850);
+
+        stream.withdrawn_amount += gross;
+        let event = WithdrawEvent::new(Pubkey::new_unique(), &stream, gross, platform_fee, partner_fee, 150, [0; 32]);
+        assert_eq!(event.net_amount, 9_850);
+        assert_eq!(event.platform_fee, 50);
+        assert_eq!(event.partner_fee, 100);
+    }
+
+    #[test]
+    fn test_partner_fee_waits_for_activation() {
+        let mut stream = StreamBuilder::new().amount(20_000).fees(50, 100).build();
+        stream.partner_fee_activation_amount = 5_000;
+
+        // Below the activation threshold only the platform fee applies
+        assert_eq!(stream.split_withdrawal(4_000).unwrap(), (3_980, 20, 0));
+
+        stream.withdrawn_amount = 6_000;
+        assert_eq!(stream.split_withdrawal(4_000).unwrap(), (3_940, 20, 40));
+    }
+
+    #[test]
+    fn test_fee_accounts_required_for_configured_recipients() {
+        let mut stream = StreamBuilder::new().fees(50, 0).build();
+        stream.fee_recipient = Some(Pubkey::new_unique());
+        assert_eq!(
+            resolve_fee_accounts(&stream, &[], false).unwrap_err(),
+            StreamFlowError::InvalidFeeConfiguration.into()
+        );
+
+        // Without fee recipients no accounts are needed
+        let stream = StreamBuilder::new().build();
+        let (platform, partner) = resolve_fee_accounts(&stream, &[], false).unwrap();
+        assert!(platform.is_none() && partner.is_none());
+    }
+
+    #[test]
+    fn test_full_withdrawal_completes_stream() {
+        let stream_key = Pubkey::new_unique();
+        let mut stream = StreamBuilder::new().amount(1_000).build();
+
+        // A partial withdrawal leaves the stream streaming
+        let plan = plan_withdrawal(&stream, 150, None, None).unwrap();
+        stream.withdrawn_amount += plan.gross_amount;
+        assert!(stream.complete_if_fully_withdrawn(stream_key, 150, None).unwrap().is_none());
+        assert_eq!(stream.status, StreamStatus::Streaming);
+
+        let plan = plan_withdrawal(&stream, 200, None, None).unwrap();
+        stream.withdrawn_amount += plan.gross_amount;
+        let event = stream.complete_if_fully_withdrawn(stream_key, 200, None).unwrap().unwrap();
+        assert_eq!(stream.status, StreamStatus::Completed);
+        assert_eq!(event.stream, stream_key);
+        assert_eq!(event.recipient, stream.recipient);
+        assert_eq!(event.total_withdrawn, 1_000);
+        assert_eq!(event.completed_at, 200);
+
+        // Completion is reported only once
+        assert!(stream.complete_if_fully_withdrawn(stream_key, 201, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_final_withdrawal_drains_escrow() {
+        // 1,000 over 7 seconds never divides evenly, and a pause shifts the end to 110
+        let mut stream = StreamBuilder::new().amount(1_000).duration(7).build();
+        let mut escrow = stream.deposited_amount;
+        for current_time in 101..=110 {
+            match current_time {
+                103 => stream.pause(current_time).unwrap(),
+                106 => stream.resume(current_time).unwrap(),
+                _ => {}
+            }
+            if stream.status == StreamStatus::Paused {
+                continue;
+            }
+            let gross = plan_withdrawal(&stream, current_time, None, None).map_or(0, |plan| plan.gross_amount);
+            stream.withdrawn_amount += gross;
+            escrow -= gross;
+            if current_time < 110 {
+                assert!(escrow > 0);
+            }
+        }
+        assert_eq!(escrow, 0);
+        assert_eq!(stream.remaining_balance(), 0);
+        assert_eq!(stream.withdrawable_amount(200).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_completion_follows_status_transitions() {
+        // Paused streams cannot move straight to Completed
+        let mut stream = StreamBuilder::new().amount(1_000).status(StreamStatus::Paused).build();
+        stream.withdrawn_amount = 1_000;
+        assert!(stream.complete_if_fully_withdrawn(Pubkey::new_unique(), 200, None).unwrap().is_none());
+        assert_eq!(stream.status, StreamStatus::Paused);
+    }
+
+    #[test]
+    fn test_completion_releases_rate_cap() {
+        let mut cap = RecipientRateCap {
+            authority: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            max_rate: 100,
+            current_rate: 0,
+            bump: 255,
+        };
+        let mut stream = StreamBuilder::new().amount(1_000).build();
+        cap.reserve(10).unwrap();
+        stream.capped_rate = 10;
+
+        // A stream holding a reservation cannot complete without its cap
+        stream.withdrawn_amount = 1_000;
+        assert_eq!(
+            stream.complete_if_fully_withdrawn(Pubkey::new_unique(), 200, None).unwrap_err(),
+            StreamError::RateCapRequired.into()
+        );
+        assert_eq!(stream.status, StreamStatus::Streaming);
+
+        assert!(stream
+            .complete_if_fully_withdrawn(Pubkey::new_unique(), 200, Some(&mut cap))
+            .unwrap()
+            .is_some());
+        assert_eq!(cap.current_rate, 0);
+        assert_eq!(stream.capped_rate, 0);
+
+        // Releasing again is a no-op
+        stream.release_rate_cap(None).unwrap();
+    }
+
+    #[test]
+    fn test_withdraw_after_cancel_is_rejected() {
+        let mut stream = StreamBuilder::new().amount(1_000).build();
+
+        // Cancel at the midpoint, the way cancel_stream records it
+        let (to_recipient, _) = cancel_split(&stream, 150, 1_000, None).unwrap();
+        stream.withdrawn_amount += to_recipient;
+        stream.status = StreamStatus::Cancelled;
+        stream.cancelled_at = Some(150);
+        assert_eq!(stream.ensure_not_cancelled().unwrap_err(), StreamError::StreamCanceled.into());
+
+        // A cancel recorded only in `cancelled_at` is caught too
+        stream.status = StreamStatus::Streaming;
+        assert_eq!(stream.ensure_not_cancelled().unwrap_err(), StreamError::StreamCanceled.into());
+
+        stream.cancelled_at = None;
+        assert!(stream.ensure_not_cancelled().is_ok());
+    }
+
+    #[test]
+    fn test_reentrant_withdrawal_sees_persisted_guard() {
+        let mut stream = StreamBuilder::new().amount(1_000).build();
+
+        // A hook invoked by the transfer reloads the stream account and
+        // starts a nested withdrawal
+        let hook = |data: &[u8]| -> Result<()> {
+            let mut reloaded = Stream::try_deserialize(&mut &data[..])?;
+            reloaded.begin_processing()
+        };
+
+        // Without persisting, the nested call would not see the guard
+        let mut stale = Vec::new();
+        stream.try_serialize(&mut stale).unwrap();
+        stream.begin_processing().unwrap();
+        assert!(hook(&stale).is_ok());
+
+        // The handler writes the guard back before any CPI
+        let mut persisted = Vec::new();
+        stream.try_serialize(&mut persisted).unwrap();
+        assert_eq!(hook(&persisted).unwrap_err(), StreamError::ReentrancyDetected.into());
+
+        stream.end_processing();
+        assert!(!stream.processing);
+    }
+
+    #[test]
+    fn test_withdrawal_cooldown() {
+        let mut stream = StreamBuilder::new().amount(1_000).build();
+        stream.min_withdrawal_interval = 30;
+        stream.withdrawn_amount = 300;
+        stream.last_withdrawn_at = 130;
+
+        // Inside the window
+        assert_eq!(
+            plan_manual_withdrawal(&stream, 159, None, None).unwrap_err(),
+            StreamFlowError::RateLimitExceeded.into()
+        );
+        assert_eq!(
+            plan_manual_withdrawal(&stream, 140, Some(50), None).unwrap_err(),
+            StreamFlowError::RateLimitExceeded.into()
+        );
+
+        // Outside it
+        assert_eq!(plan_manual_withdrawal(&stream, 160, None, None).unwrap().gross_amount, 300);
+
+        // Cranked withdrawals are not rate limited
+        assert_eq!(plan_withdrawal(&stream, 140, None, None).unwrap().gross_amount, 100);
+
+        // No limit by default
+        stream.min_withdrawal_interval = 0;
+        assert_eq!(plan_manual_withdrawal(&stream, 140, None, None).unwrap().gross_amount, 100);
+    }
+
+    fn token_account(owner: Pubkey, mint: Pubkey) -> TokenAccount {
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState};
+
+        let mut data = vec![0u8; SplAccount::LEN];
+        SplAccount {
+            mint,
+            owner,
+            amount: 0,
+            delegate: None.into(),
+            state: AccountState::Initialized,
+            is_native: None.into(),
+            delegated_amount: 0,
+            close_authority: None.into(),
+        }
+        .pack_into_slice(&mut data);
+        TokenAccount::try_deserialize_unchecked(&mut data.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_transferred_stream_rejects_old_recipient_ata() {
+        let mut stream = StreamBuilder::new().build();
+        let old_recipient = stream.recipient;
+        let old_ata = stream.destination_token_account();
+        let old_account = token_account(old_recipient, stream.mint);
+        check_destination(&stream, &old_ata, &old_account).unwrap();
+
+        let new_recipient = Pubkey::new_unique();
+        assert_eq!(stream.reassign_recipient(new_recipient, 150).unwrap(), old_recipient);
+        assert_eq!(
+            check_destination(&stream, &old_ata, &old_account).unwrap_err(),
+            StreamFlowError::InvalidTokenAccountOwner.into()
+        );
+
+        let new_ata = stream.destination_token_account();
+        check_destination(&stream, &new_ata, &token_account(new_recipient, stream.mint)).unwrap();
+        // The right address is not enough if someone else owns the account
+        assert_eq!(
+            check_destination(&stream, &new_ata, &old_account).unwrap_err(),
+            StreamFlowError::InvalidTokenAccountOwner.into()
+        );
+    }
+
+    #[test]
+    fn test_withdraw_to_custom_destination() {
+        let mut stream = StreamBuilder::new().build();
+        let recipient_ata = stream.destination_token_account();
+        let cold_wallet = Pubkey::new_unique();
+        let cold_account = token_account(cold_wallet, stream.mint);
+        let cold_address = Pubkey::new_unique();
+
+        check_withdraw_destination(&stream, &cold_account).unwrap();
+        stream.withdraw_destination = Some(cold_address);
+        assert_eq!(stream.destination_token_account(), cold_address);
+
+        // The cold wallet owns the account, not the recipient
+        check_destination(&stream, &cold_address, &cold_account).unwrap();
+        assert_eq!(
+            check_destination(&stream, &recipient_ata, &token_account(stream.recipient, stream.mint))
+                .unwrap_err(),
+            StreamFlowError::InvalidTokenAccountOwner.into()
+        );
+
+        // Unset, withdrawals go back to the recipient's ATA
+        stream.withdraw_destination = None;
+        assert_eq!(stream.destination_token_account(), recipient_ata);
+    }
+
+    #[test]
+    fn test_custom_destination_with_wrong_mint_is_rejected() {
+        let mut stream = StreamBuilder::new().build();
+        let wrong_mint = token_account(Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(
+            check_withdraw_destination(&stream, &wrong_mint).unwrap_err(),
+            StreamError::InvalidMint.into()
+        );
+
+        let destination = Pubkey::new_unique();
+        stream.withdraw_destination = Some(destination);
+        assert_eq!(
+            check_destination(&stream, &destination, &wrong_mint).unwrap_err(),
+            StreamError::InvalidMint.into()
+        );
+    }
+
+    #[test]
+    fn test_transfer_clears_custom_destination() {
+        let mut stream = StreamBuilder::new().build();
+        stream.withdraw_destination = Some(Pubkey::new_unique());
+        let new_recipient = Pubkey::new_unique();
+        stream.reassign_recipient(new_recipient, 150).unwrap();
+        assert_eq!(stream.withdraw_destination, None);
+        assert_eq!(
+            stream.destination_token_account(),
+            anchor_spl::associated_token::get_associated_token_address(&new_recipient, &stream.mint)
+        );
+    }
+
+    #[test]
+    fn test_transfer_to_same_recipient_is_rejected() {
+        let mut stream = StreamBuilder::new().build();
+        let recipient = stream.recipient;
+        assert_eq!(
+            stream.reassign_recipient(recipient, 150).unwrap_err(),
+            StreamError::InvalidRecipient.into()
+        );
+        assert_eq!(stream.recipient, recipient);
+        assert!(stream.recipient_history.is_empty());
+    }
+
+    #[test]
+    fn test_transfer_records_recipient_history() {
+        let mut stream = StreamBuilder::new().amount(1_000).build();
+        let first = stream.recipient;
+        stream.withdrawn_amount = stream.withdrawable_amount(140).unwrap();
+
+        let second = Pubkey::new_unique();
+        stream.reassign_recipient(second, 150).unwrap();
+        let third = Pubkey::new_unique();
+        stream.reassign_recipient(third, 170).unwrap();
+        assert_eq!(stream.recipient_history, vec![(first, 150), (second, 170)]);
+
+        // What earlier recipients withdrew stays withdrawn
+        assert_eq!(stream.withdrawn_amount, 400);
+        assert_eq!(plan_withdrawal(&stream, 170, None, None).unwrap().gross_amount, 300);
+    }
+
+    #[test]
+    fn test_transfers_stop_at_history_cap() {
+        let mut stream = StreamBuilder::new().build();
+        for i in 0..MAX_RECIPIENT_HISTORY {
+            stream.reassign_recipient(Pubkey::new_unique(), 100 + i as i64).unwrap();
+        }
+        let recipient = stream.recipient;
+        assert_eq!(
+            stream.reassign_recipient(Pubkey::new_unique(), 200).unwrap_err(),
+            StreamFlowError::StreamModificationNotAllowed.into()
+        );
+        assert_eq!(stream.recipient, recipient);
+        assert_eq!(stream.recipient_history.len(), MAX_RECIPIENT_HISTORY);
+    }
+
+    /// Price of a pegged stream's feed, read from a mock Pyth account
+    fn mock_price(price: i64, exponent: i32, publish_time: i64) -> Price {
+        Price::from_pyth_account(&crate::oracle::tests::mock_price_account(price, exponent, publish_time)).unwrap()
+    }
+
+    fn pegged_stream() -> Stream {
+        // 10,000 tokens over [100, 200]; $1 per second
+        let mut stream = StreamBuilder::new().amount(10_000).build();
+        stream.set_usd_peg(Some(1_000_000), Some(Pubkey::new_unique()), 60).unwrap();
+        stream
+    }
+
+    #[test]
+    fn test_pegged_withdrawal_with_fresh_price() {
+        let stream = pegged_stream();
+        // $0.50 per token, 0 decimals: 50 seconds at $1/s buy 100 tokens
+        let price = mock_price(50, -2, 140);
+        price.ensure_fresh(150, stream.max_price_age).unwrap();
+        let accrual = pegged_accrual(&stream, &price, 0, 150).unwrap();
+        assert_eq!(accrual, PeggedAccrual { amount: 100, since: 100, until: 150 });
+        let plan = plan_withdrawal(&stream, 150, None, Some(&accrual)).unwrap();
+        assert_eq!((plan.gross_amount, plan.paid_through), (100, 150));
+
+        // A partial withdrawal leaves the unpaid seconds accruing
+        let plan = plan_withdrawal(&stream, 150, Some(40), Some(&accrual)).unwrap();
+        assert_eq!((plan.gross_amount, plan.paid_through), (40, 120));
+        assert_eq!(
+            plan_withdrawal(&stream, 150, Some(101), Some(&accrual)).unwrap_err(),
+            StreamError::InsufficientWithdrawableBalance.into()
+        );
+
+        // Without its price the schedule alone never pays a pegged stream
+        assert_eq!(
+            plan_withdrawal(&stream, 150, None, None).unwrap_err(),
+            StreamFlowError::OraclePriceFeedNotFound.into()
+        );
+
+        // The accrual never exceeds what the schedule has vested
+        let cheap = mock_price(1, -4, 150);
+        assert_eq!(pegged_accrual(&stream, &cheap, 0, 150).unwrap().amount, 5_000);
+    }
+
+    #[test]
+    fn test_pegged_withdrawal_with_stale_price() {
+        let stream = pegged_stream();
+        let price = mock_price(50, -2, 80);
+        assert!(matches!(
+            price.ensure_fresh(150, stream.max_price_age),
+            Err(StreamFlowError::OraclePriceDataStale)
+        ));
+        price.ensure_fresh(140, stream.max_price_age).unwrap();
+    }
+
+    #[test]
+    fn test_usd_peg_configuration() {
+        let mut stream = StreamBuilder::new().build();
+        let feed = Some(Pubkey::new_unique());
+        for (rate, feed, max_age) in [(Some(0), feed, 60), (Some(1), None, 60), (Some(1), feed, 0), (None, feed, 0)] {
+            assert!(matches!(
+                stream.set_usd_peg(rate, feed, max_age),
+                Err(StreamFlowError::InvalidOracleConfiguration)
+            ));
+        }
+        stream.set_usd_peg(None, None, 0).unwrap();
+        assert_eq!(stream.usd_rate_per_second, None);
+
+        // Before the start there is nothing to price
+        let stream = pegged_stream();
+        let accrual = pegged_accrual(&stream, &mock_price(50, -2, 90), 0, 90).unwrap();
+        assert_eq!(accrual.amount, 0);
+        assert_eq!(
+            resolve_withdrawal_amount(&stream, 90, None, Some(&accrual)).unwrap_err(),
+            StreamError::StreamNotStarted.into()
+        );
+    }
+
+    #[test]
+    fn test_percentage_of_withdrawable() {
+        let stream = StreamBuilder::new().amount(1_000).build();
+        let withdrawable = stream.calculate_withdrawable_amount(150).unwrap();
+        assert_eq!(withdrawable, 500);
+
+        assert_eq!(percentage_of_withdrawable(withdrawable, 0).unwrap(), 0);
+        assert_eq!(percentage_of_withdrawable(withdrawable, 5_000).unwrap(), 250);
+        assert_eq!(percentage_of_withdrawable(withdrawable, MAX_BPS).unwrap(), 500);
+        // Rounds down
+        assert_eq!(percentage_of_withdrawable(withdrawable, 1).unwrap(), 0);
+        assert_eq!(percentage_of_withdrawable(u64::MAX, MAX_BPS).unwrap(), u64::MAX);
+
+        // The amounts go through the normal withdrawal path
+        assert_eq!(
+            plan_withdrawal(&stream, 150, Some(250), None).unwrap().gross_amount,
+            250
+        );
+        assert_eq!(
+            plan_withdrawal(&stream, 150, Some(500), None).unwrap().gross_amount,
+            500
+        );
+    }
+
+    #[test]
+    fn test_percentage_above_whole_is_rejected() {
+        assert_eq!(
+            percentage_of_withdrawable(500, MAX_BPS + 1).unwrap_err(),
+            StreamError::InvalidAmount.into()
+        );
+        assert_eq!(
+            percentage_of_withdrawable(500, u16::MAX).unwrap_err(),
+            StreamError::InvalidAmount.into()
+        );
+    }
 }
-```
\ No newline at end of file
+```
@@ -0,0 +1,272 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token::{Token, TokenAccount};
+use crate::state::*;
+use crate::errors::{StreamError, StreamFlowError};
+use super::withdraw::{
+    apply_withdrawal, check_destination, plan_manual_withdrawal, prepare_and_journal_withdrawal, resolve_fee_accounts,
+    transfer_withdrawal, WithdrawEvent, WithdrawalPlan,
+};
+
+/// Withdraw vested tokens and swap them into another token through a DEX.
+///
+/// The net withdrawal is paid to the recipient's usual destination, then
+/// `swap_program` is invoked with `SwapInstruction` data. `remaining_accounts`
+/// holds the fee accounts, as for `withdraw`, followed by the accounts the
+/// swap program expects. The swap is judged only by how much
+/// `output_token_account` gains.
+#[derive(Accounts)]
+pub struct WithdrawAndSwap<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &stream.nonce.to_le_bytes(),
+        ],
+        bump = stream.bump,
+        has_one = recipient @ StreamError::InvalidRecipient,
+        constraint = (stream.is_active() || stream.status == StreamStatus::Scheduled)
+            @ StreamError::StreamNotActive,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump = stream.escrow_bump,
+        token::mint = stream.mint,
+        token::authority = stream,
+    )]
+    pub stream_token_account: Account<'info, TokenAccount>,
+
+    /// The stream's withdrawal destination, which the swap spends from
+    #[account(mut)]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    /// Recipient's account of the token swapped into
+    #[account(
+        mut,
+        token::authority = recipient,
+        constraint = output_token_account.mint != stream.mint @ StreamFlowError::InvalidTokenMint,
+    )]
+    pub output_token_account: Account<'info, TokenAccount>,
+
+    /// Sender's aggregate rate cap for this recipient, if one is configured
+    #[account(
+        mut,
+        seeds = [
+            b"rate_cap",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+        ],
+        bump = rate_cap.bump,
+    )]
+    pub rate_cap: Option<Account<'info, RecipientRateCap>>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        mut,
+        seeds = [b"mint_stats", stream.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    /// CHECK: Any swap program; its effect is verified on `output_token_account`
+    #[account(executable)]
+    pub swap_program: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Instruction data `swap_program` is invoked with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct SwapInstruction {
+    /// Tokens of the stream's mint to sell
+    pub amount_in: u64,
+    /// Fewest output tokens the swap may return
+    pub min_out: u64,
+}
+
+pub fn handler<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, WithdrawAndSwap<'info>>,
+    amount: Option<u64>,
+    min_out: u64,
+) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    ctx.accounts.stream.ensure_not_cancelled()?;
+    ctx.accounts.stream.ensure_accepted()?;
+    // Swaps carry no invoice
+    ctx.accounts.stream.check_withdrawal_receipt(&[0; 32])?;
+    check_destination(
+        &ctx.accounts.stream,
+        &ctx.accounts.recipient_token_account.key(),
+        &ctx.accounts.recipient_token_account,
+    )?;
+
+    // Persist the guard so a re-entrant call through a CPI observes it
+    ctx.accounts.stream.begin_processing()?;
+    ctx.accounts.stream.exit(ctx.program_id)?;
+
+    let stream_key = ctx.accounts.stream.key();
+    let stream = &mut ctx.accounts.stream;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    prepare_and_journal_withdrawal(stream, stream_key, current_time)?;
+
+    let plan = plan_manual_withdrawal(stream, current_time, amount, None)?;
+    let WithdrawalPlan { gross_amount: withdrawal_amount, platform_fee, partner_fee, net_amount, .. } = plan;
+    let (platform_fee_account, partner_fee_account) =
+        resolve_fee_accounts(stream, ctx.remaining_accounts, false)?;
+    let fee_account_count =
+        usize::from(platform_fee_account.is_some()) + usize::from(partner_fee_account.is_some());
+    let swap_accounts = &ctx.remaining_accounts[fee_account_count..];
+
+    let completed = apply_withdrawal(
+        stream,
+        stream_key,
+        &plan,
+        current_time,
+        &mut ctx.accounts.mint_stats,
+        ctx.accounts.rate_cap.as_deref_mut(),
+    )?;
+
+    transfer_withdrawal(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.stream_token_account.to_account_info(),
+        stream,
+        ctx.accounts.recipient_token_account.to_account_info(),
+        &plan,
+        platform_fee_account.cloned(),
+        partner_fee_account.cloned(),
+    )?;
+
+    // The recipient signed the transaction, so the swap may spend from their account
+    let output_before = ctx.accounts.output_token_account.amount;
+    let swap = SwapInstruction { amount_in: net_amount, min_out };
+    let instruction = Instruction {
+        program_id: ctx.accounts.swap_program.key(),
+        accounts: swap_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: swap.try_to_vec()?,
+    };
+    let mut account_infos = swap_accounts.to_vec();
+    account_infos.push(ctx.accounts.swap_program.to_account_info());
+    invoke(&instruction, &account_infos)?;
+
+    ctx.accounts.output_token_account.reload()?;
+    let amount_out = swap_output(output_before, ctx.accounts.output_token_account.amount, min_out)?;
+
+    ctx.accounts.stream.end_processing();
+
+    emit!(WithdrawEvent::new(
+        stream_key,
+        &ctx.accounts.stream,
+        withdrawal_amount,
+        platform_fee,
+        partner_fee,
+        current_time,
+        [0; 32],
+    ));
+    emit!(WithdrawSwapped {
+        stream: stream_key,
+        recipient: ctx.accounts.recipient.key(),
+        output_mint: ctx.accounts.output_token_account.mint,
+        amount_in: net_amount,
+        amount_out,
+        timestamp: current_time,
+    });
+    if let Some(completed) = completed {
+        emit!(completed);
+    }
+
+    Ok(())
+}
+
+/// Tokens a swap delivered, given the output account's balance before and
+/// after it. A swap that leaves the balance lower failed outright; one that
+/// delivers less than `min_out` exceeded the slippage tolerance.
+pub fn swap_output(balance_before: u64, balance_after: u64, min_out: u64) -> Result<u64> {
+    let amount_out = balance_after
+        .checked_sub(balance_before)
+        .ok_or(StreamFlowError::SwapFailed)?;
+    require!(amount_out >= min_out, StreamFlowError::SlippageToleranceExceeded);
+    Ok(amount_out)
+}
+
+#[event]
+pub struct WithdrawSwapped {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub output_mint: Pubkey,
+    /// Stream tokens sold, after fees
+    pub amount_in: u64,
+    /// Output tokens received
+    pub amount_out: u64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    /// A swap program quoting `price_bps` output tokens per 10,000 input,
+    /// returning the output account's balance afterwards
+    fn mock_swap(balance: u64, swap: SwapInstruction, price_bps: u64) -> u64 {
+        balance + swap.amount_in * price_bps / 10_000
+    }
+
+    fn net_withdrawal() -> u64 {
+        let stream = StreamBuilder::new().amount(1_000).build();
+        plan_manual_withdrawal(&stream, 150, None, None).unwrap().net_amount
+    }
+
+    #[test]
+    fn test_swap_above_minimum() {
+        let swap = SwapInstruction { amount_in: net_withdrawal(), min_out: 900 };
+        assert_eq!(swap.amount_in, 500);
+        let after = mock_swap(50, swap, 20_000);
+        assert_eq!(swap_output(50, after, swap.min_out).unwrap(), 1_000);
+        // Exactly the minimum is accepted
+        let after = mock_swap(50, swap, 18_000);
+        assert_eq!(swap_output(50, after, swap.min_out).unwrap(), 900);
+    }
+
+    #[test]
+    fn test_swap_below_minimum_reverts() {
+        let swap = SwapInstruction { amount_in: net_withdrawal(), min_out: 900 };
+        let after = mock_swap(50, swap, 17_980);
+        assert_eq!(
+            swap_output(50, after, swap.min_out).unwrap_err(),
+            StreamFlowError::SlippageToleranceExceeded.into()
+        );
+    }
+
+    #[test]
+    fn test_swap_draining_output_fails() {
+        assert_eq!(swap_output(50, 49, 0).unwrap_err(), StreamFlowError::SwapFailed.into());
+        assert_eq!(swap_output(50, 50, 0).unwrap(), 0);
+    }
+}
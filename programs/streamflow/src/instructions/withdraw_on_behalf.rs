@@ -0,0 +1,244 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+use crate::state::*;
+use crate::errors::{StreamError, StreamFlowError};
+use super::withdraw::{
+    apply_withdrawal, load_pegged_accrual, plan_manual_withdrawal, prepare_and_journal_withdrawal,
+    resolve_fee_accounts, transfer_withdrawal, PeggedAccrual, WithdrawEvent, WithdrawalPlan,
+};
+
+/// Sender-initiated payout of vested tokens to the recipient's associated
+/// token account, for payroll senders that push instead of waiting for claims.
+/// Fees are taken as for `withdraw`, with the fee accounts in `remaining_accounts`.
+#[derive(Accounts)]
+pub struct WithdrawOnBehalf<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &stream.nonce.to_le_bytes(),
+        ],
+        bump = stream.bump,
+        constraint = (stream.is_active() || stream.status == StreamStatus::Scheduled)
+            @ StreamError::StreamNotActive,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"escrow",
+            stream.key().as_ref(),
+        ],
+        bump = stream.escrow_bump,
+        token::mint = stream.mint,
+        token::authority = stream,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// The recipient's associated token account for the stream's mint
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    /// Sender's aggregate rate cap for this recipient, if one is configured
+    #[account(
+        mut,
+        seeds = [
+            b"rate_cap",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+        ],
+        bump = rate_cap.bump,
+    )]
+    pub rate_cap: Option<Account<'info, RecipientRateCap>>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        mut,
+        seeds = [b"mint_stats", stream.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// CHECK: Only used to derive the associated token account
+    #[account(address = stream.recipient @ StreamError::InvalidRecipient)]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// CHECK: Used to derive the associated token account and for the
+    /// decimals of a USD-pegged stream
+    #[account(address = stream.mint @ StreamError::InvalidMint)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Pyth price account of a USD-pegged stream, decoded by `oracle::Price`
+    #[account(
+        address = stream.price_feed.unwrap_or_default() @ StreamFlowError::OraclePriceFeedNotFound,
+    )]
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub sender: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, WithdrawOnBehalf<'info>>,
+    amount: u64,
+) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    ctx.accounts.stream.ensure_not_cancelled()?;
+    ctx.accounts.stream.ensure_accepted()?;
+    // Pushes carry no invoice, so receipt-mode streams must be claimed by the recipient
+    ctx.accounts.stream.check_withdrawal_receipt(&[0; 32])?;
+
+    // Persist the guard so a re-entrant call through a CPI observes it
+    ctx.accounts.stream.begin_processing()?;
+    ctx.accounts.stream.exit(ctx.program_id)?;
+
+    let stream_key = ctx.accounts.stream.key();
+    let sender = ctx.accounts.sender.key();
+    let current_time = Clock::get()?.unix_timestamp;
+    prepare_and_journal_withdrawal(&mut ctx.accounts.stream, stream_key, current_time)?;
+    let pegged = load_pegged_accrual(
+        &ctx.accounts.stream,
+        ctx.accounts.price_feed.as_deref(),
+        &ctx.accounts.mint,
+        current_time,
+    )?;
+    let stream = &mut ctx.accounts.stream;
+
+    let plan = on_behalf_plan(stream, &sender, current_time, amount, pegged.as_ref())?;
+    let (platform_fee_account, partner_fee_account) =
+        resolve_fee_accounts(stream, ctx.remaining_accounts, false)?;
+
+    let completed = apply_withdrawal(
+        stream,
+        stream_key,
+        &plan,
+        current_time,
+        &mut ctx.accounts.mint_stats,
+        ctx.accounts.rate_cap.as_deref_mut(),
+    )?;
+    transfer_withdrawal(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.escrow_token_account.to_account_info(),
+        stream,
+        ctx.accounts.recipient_token_account.to_account_info(),
+        &plan,
+        platform_fee_account.cloned(),
+        partner_fee_account.cloned(),
+    )?;
+
+    stream.end_processing();
+
+    emit!(WithdrawEvent::new(
+        stream_key,
+        stream,
+        plan.gross_amount,
+        plan.platform_fee,
+        plan.partner_fee,
+        current_time,
+        [0; 32],
+    ));
+    if let Some(completed) = completed {
+        emit!(completed);
+    }
+
+    Ok(())
+}
+
+/// Plan a push of `amount` by `authority` at `current_time`. Only the
+/// sender of an `automatic_withdrawal` stream may push, never more than a
+/// recipient's own withdrawal could take, and with the same fees.
+pub fn on_behalf_plan(
+    stream: &Stream,
+    authority: &Pubkey,
+    current_time: i64,
+    amount: u64,
+    pegged: Option<&PeggedAccrual>,
+) -> Result<WithdrawalPlan> {
+    require_keys_eq!(*authority, stream.sender, StreamError::UnauthorizedSender);
+    require!(stream.automatic_withdrawal, StreamFlowError::FeatureNotEnabled);
+    require!(amount > 0, StreamFlowError::InvalidAmount);
+
+    plan_manual_withdrawal(stream, current_time, Some(amount), pegged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn payroll_stream() -> Stream {
+        let mut stream = StreamBuilder::new().amount(1_000).build();
+        stream.automatic_withdrawal = true;
+        stream
+    }
+
+    #[test]
+    fn test_sender_can_push_vested_tokens() {
+        let stream = payroll_stream();
+        assert_eq!(on_behalf_plan(&stream, &stream.sender, 150, 500, None).unwrap().gross_amount, 500);
+        assert_eq!(on_behalf_plan(&stream, &stream.sender, 150, 200, None).unwrap().gross_amount, 200);
+        assert_eq!(
+            on_behalf_plan(&stream, &stream.sender, 150, 501, None).unwrap_err(),
+            StreamError::InsufficientWithdrawableBalance.into()
+        );
+    }
+
+    #[test]
+    fn test_non_sender_cannot_push() {
+        let stream = payroll_stream();
+        for authority in [stream.recipient, Pubkey::new_unique()] {
+            assert_eq!(
+                on_behalf_plan(&stream, &authority, 150, 100, None).unwrap_err(),
+                StreamError::UnauthorizedSender.into()
+            );
+        }
+    }
+
+    #[test]
+    fn test_push_requires_automatic_withdrawal() {
+        let mut stream = payroll_stream();
+        stream.automatic_withdrawal = false;
+        assert_eq!(
+            on_behalf_plan(&stream, &stream.sender, 150, 100, None).unwrap_err(),
+            StreamFlowError::FeatureNotEnabled.into()
+        );
+    }
+
+    #[test]
+    fn test_push_charges_fees() {
+        let mut stream = StreamBuilder::new().amount(20_000).fees(50, 100).build();
+        stream.automatic_withdrawal = true;
+        stream.partner_fee_active = true;
+
+        let plan = on_behalf_plan(&stream, &stream.sender, 150, 10_000, None).unwrap();
+        assert_eq!(plan.gross_amount, 10_000);
+        assert_eq!((plan.platform_fee, plan.partner_fee, plan.net_amount), (50, 100, 9_850));
+    }
+
+    #[test]
+    fn test_push_respects_withdrawal_cooldown() {
+        let mut stream = payroll_stream();
+        stream.min_withdrawal_interval = 60;
+        stream.last_withdrawn_at = 120;
+        assert_eq!(
+            on_behalf_plan(&stream, &stream.sender, 150, 100, None).unwrap_err(),
+            StreamFlowError::RateLimitExceeded.into()
+        );
+        assert_eq!(on_behalf_plan(&stream, &stream.sender, 180, 100, None).unwrap().gross_amount, 100);
+    }
+}
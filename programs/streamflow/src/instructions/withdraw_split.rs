@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::{StreamError, StreamFlowError};
+use super::withdraw::validate_fee_account;
+
+/// Withdraw the signer's weighted share of a split stream's vested amount,
+/// under the same pause, acceptance, cooldown and fee rules as `withdraw`.
+/// The platform and partner fee accounts, for the fees the split charges,
+/// follow in `remaining_accounts`.
+#[derive(Accounts)]
+pub struct WithdrawSplit<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"split_stream",
+            split_stream.sender.as_ref(),
+            &split_stream.split_id.to_le_bytes(),
+        ],
+        bump = split_stream.bump,
+    )]
+    pub split_stream: Account<'info, SplitStream>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"escrow",
+            split_stream.key().as_ref(),
+        ],
+        bump = split_stream.escrow_bump,
+        token::mint = split_stream.mint,
+        token::authority = split_stream,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = split_stream.mint,
+        token::authority = recipient,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    pub recipient: Signer<'info>,
+
+    /// Program-wide totals for the mint
+    #[account(
+        mut,
+        seeds = [b"mint_stats", split_stream.mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+
+    /// Program-wide settings, checked for an emergency pause
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, WithdrawSplit<'info>>,
+) -> Result<()> {
+    ctx.accounts.program_config.ensure_not_paused()?;
+    let current_time = Clock::get()?.unix_timestamp;
+    let recipient = ctx.accounts.recipient.key();
+
+    let split_stream = &mut ctx.accounts.split_stream;
+    let index = split_stream.position(&recipient)?;
+    split_stream.ensure_accepted(index)?;
+    split_stream.check_withdrawal_cooldown(index, current_time)?;
+
+    let amount = split_stream.withdrawable_for(&recipient, current_time)?;
+    require!(amount > 0, StreamError::NoTokensToWithdraw);
+    let (net_amount, platform_fee, partner_fee) = split_stream.split_withdrawal(amount)?;
+
+    let mut fee_accounts = ctx.remaining_accounts.iter();
+    let mut fee_account = |fee: u64, fee_recipient: Option<Pubkey>| -> Result<Option<AccountInfo<'info>>> {
+        if fee == 0 {
+            return Ok(None);
+        }
+        let fee_recipient = fee_recipient.ok_or(StreamFlowError::InvalidFeeConfiguration)?;
+        let account = fee_accounts.next().ok_or(StreamFlowError::InvalidFeeConfiguration)?;
+        validate_fee_account(account, &fee_recipient, &split_stream.mint)?;
+        Ok(Some(account.clone()))
+    };
+    let platform_fee_account = fee_account(platform_fee, split_stream.fee_recipient)?;
+    let partner_fee_account = fee_account(partner_fee, split_stream.partner_fee_recipient)?;
+
+    let share = &mut split_stream.recipients[index];
+    share.withdrawn_amount = share
+        .withdrawn_amount
+        .checked_add(amount)
+        .ok_or(StreamError::MathOverflow)?;
+    share.last_withdrawn_at = current_time;
+    ctx.accounts.mint_stats.record_withdrawal(amount)?;
+
+    let seeds = &[
+        b"split_stream",
+        split_stream.sender.as_ref(),
+        &split_stream.split_id.to_le_bytes(),
+        &[split_stream.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let payouts = [
+        (Some(ctx.accounts.recipient_token_account.to_account_info()), net_amount),
+        (platform_fee_account, platform_fee),
+        (partner_fee_account, partner_fee),
+    ];
+    for (to, payout) in payouts {
+        let to = match to {
+            Some(to) if payout > 0 => to,
+            _ => continue,
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow_token_account.to_account_info(),
+                    to,
+                    authority: split_stream.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            payout,
+        )?;
+    }
+
+    emit!(SplitWithdrawn {
+        split_stream: split_stream.key(),
+        recipient,
+        amount,
+        platform_fee,
+        partner_fee,
+        withdrawn_amount: split_stream.recipients[index].withdrawn_amount,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct SplitWithdrawn {
+    pub split_stream: Pubkey,
+    pub recipient: Pubkey,
+    /// Gross amount, fees included
+    pub amount: u64,
+    pub platform_fee: u64,
+    pub partner_fee: u64,
+    /// Cumulative amount this recipient has withdrawn
+    pub withdrawn_amount: u64,
+    pub timestamp: i64,
+}
@@ -1,7 +1,19 @@
 ```rust
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
-use std::mem::size_of;
+
+pub mod bps;
+pub mod calc;
+pub mod error;
+pub mod instructions;
+pub mod oracle;
+pub mod state;
+
+pub use error as errors;
+
+use errors::StreamError;
+use instructions::*;
+use state::*;
 
 declare_id!("11111111111111111111111111111112");
 
@@ -15,193 +27,92 @@ pub mod streamflow {
         deposit_amount: u64,
         start_time: i64,
         end_time: i64,
-        cliff_time: Option<i64>,
+        cliff_time: i64,
+        cliff_amount: u64,
         cancelable_by_sender: bool,
         cancelable_by_recipient: bool,
         transferable_by_sender: bool,
         transferable_by_recipient: bool,
+        stream_name: String,
+        rent_treasury: Option<Pubkey>,
+        interest_rate_bps: u16,
+        allow_distant_start: bool,
+        can_topup: bool,
+        fee_config: FeeConfig,
+        receipt_required: bool,
+        can_update_rate: bool,
+        lock_rate_after_start: bool,
+        allow_backdated: bool,
+        allow_partial_withdraw: bool,
         automatic_withdrawal: bool,
         withdrawal_frequency: u64,
+        require_acceptance: bool,
+        cancel_approvers: Vec<Pubkey>,
+        cancel_threshold: u8,
+        clawback_authority: Option<Pubkey>,
+        min_withdrawal_interval: u64,
+        usd_rate_per_second: Option<u64>,
+        price_feed: Option<Pubkey>,
+        max_price_age: u64,
+        max_pause_duration: u64,
+        start_on_acceptance: bool,
     ) -> Result<()> {
-        require!(start_time < end_time, StreamError::InvalidTimeRange);
-        require!(deposit_amount > 0, StreamError::InvalidAmount);
-        
-        if let Some(cliff) = cliff_time {
-            require!(cliff >= start_time && cliff <= end_time, StreamError::InvalidCliffTime);
-        }
-
-        let stream = &mut ctx.accounts.stream;
-        let clock = Clock::get()?;
-
-        stream.sender = ctx.accounts.sender.key();
-        stream.recipient = recipient;
-        stream.mint = ctx.accounts.sender_token_account.mint;
-        stream.escrow_token_account = ctx.accounts.escrow_token_account.key();
-        stream.deposit_amount = deposit_amount;
-        stream.withdrawn_amount = 0;
-        stream.start_time = start_time;
-        stream.end_time = end_time;
-        stream.cliff_time = cliff_time;
-        stream.cancelable_by_sender = cancelable_by_sender;
-        stream.cancelable_by_recipient = cancelable_by_recipient;
-        stream.transferable_by_sender = transferable_by_sender;
-        stream.transferable_by_recipient = transferable_by_recipient;
-        stream.automatic_withdrawal = automatic_withdrawal;
-        stream.withdrawal_frequency = withdrawal_frequency;
-        stream.last_withdrawal_time = start_time;
-        stream.created_at = clock.unix_timestamp;
-        stream.canceled_at = None;
-        stream.canceled_by = None;
-        stream.paused = false;
-        stream.bump = *ctx.bumps.get("stream").unwrap();
-
-        // Transfer tokens to escrow
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.sender_token_account.to_account_info(),
-            to: ctx.accounts.escrow_token_account.to_account_info(),
-            authority: ctx.accounts.sender.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::transfer(cpi_ctx, deposit_amount)?;
-
-        emit!(StreamCreated {
-            stream: stream.key(),
-            sender: stream.sender,
-            recipient: stream.recipient,
+        instructions::create_stream::create_stream(
+            ctx,
+            recipient,
             deposit_amount,
             start_time,
             end_time,
-        });
-
-        Ok(())
+            cliff_time,
+            cliff_amount,
+            cancelable_by_sender,
+            cancelable_by_recipient,
+            transferable_by_sender,
+            transferable_by_recipient,
+            stream_name,
+            rent_treasury,
+            interest_rate_bps,
+            allow_distant_start,
+            can_topup,
+            fee_config,
+            receipt_required,
+            can_update_rate,
+            lock_rate_after_start,
+            allow_backdated,
+            allow_partial_withdraw,
+            automatic_withdrawal,
+            withdrawal_frequency,
+            require_acceptance,
+            cancel_approvers,
+            cancel_threshold,
+            clawback_authority,
+            min_withdrawal_interval,
+            usd_rate_per_second,
+            price_feed,
+            max_price_age,
+            max_pause_duration,
+            start_on_acceptance,
+        )
     }
 
-    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
-        let stream = &mut ctx.accounts.stream;
-        let clock = Clock::get()?;
-        let current_time = clock.unix_timestamp;
-
-        require!(!stream.paused, StreamError::StreamPaused);
-        require!(stream.canceled_at.is_none(), StreamError::StreamCanceled);
-        require!(
-            ctx.accounts.recipient.key() == stream.recipient,
-            StreamError::UnauthorizedRecipient
-        );
-
-        let withdrawable_amount = calculate_withdrawable_amount(stream, current_time)?;
-        require!(amount <= withdrawable_amount, StreamError::InsufficientFunds);
-
-        stream.withdrawn_amount += amount;
-        stream.last_withdrawal_time = current_time;
-
-        // Transfer tokens from escrow to recipient
-        let seeds = &[
-            b"stream",
-            stream.sender.as_ref(),
-            stream.recipient.as_ref(),
-            stream.mint.as_ref(),
-            &[stream.bump],
-        ];
-        let signer = &[&seeds[..]];
-
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.escrow_token_account.to_account_info(),
-            to: ctx.accounts.recipient_token_account.to_account_info(),
-            authority: stream.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        token::transfer(cpi_ctx, amount)?;
-
-        emit!(Withdrawal {
-            stream: stream.key(),
-            recipient: stream.recipient,
-            amount,
-            withdrawn_amount: stream.withdrawn_amount,
-        });
+    pub fn withdraw<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, Withdraw<'info>>,
+        amount: Option<u64>,
+        receipt_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::withdraw::handler(ctx, amount, receipt_hash)
+    }
 
-        Ok(())
+    pub fn withdraw_percentage<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, Withdraw<'info>>,
+        bps: u16,
+        receipt_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::withdraw::percentage_handler(ctx, bps, receipt_hash)
     }
 
     pub fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
-        let stream = &mut ctx.accounts.stream;
-        let clock = Clock::get()?;
-        let current_time = clock.unix_timestamp;
-
-        require!(stream.canceled_at.is_none(), StreamError::StreamAlreadyCanceled);
-        require!(!stream.paused, StreamError::StreamPaused);
-
-        let authority = ctx.accounts.authority.key();
-        let can_cancel = if authority == stream.sender {
-            stream.cancelable_by_sender
-        } else if authority == stream.recipient {
-            stream.cancelable_by_recipient
-        } else {
-            false
-        };
-
-        require!(can_cancel, StreamError::UnauthorizedCancel);
-
-        let withdrawable_amount = calculate_withdrawable_amount(stream, current_time)?;
-        let remaining_amount = stream.deposit_amount - stream.withdrawn_amount;
-
-        stream.canceled_at = Some(current_time);
-        stream.canceled_by = Some(authority);
-
-        // Transfer withdrawable amount to recipient if any
-        if withdrawable_amount > 0 {
-            stream.withdrawn_amount += withdrawable_amount;
-
-            let seeds = &[
-                b"stream",
-                stream.sender.as_ref(),
-                stream.recipient.as_ref(),
-                stream.mint.as_ref(),
-                &[stream.bump],
-            ];
-            let signer = &[&seeds[..]];
-
-            let cpi_accounts = Transfer {
-                from: ctx.accounts.escrow_token_account.to_account_info(),
-                to: ctx.accounts.recipient_token_account.to_account_info(),
-                authority: stream.to_account_info(),
-            };
-            let cpi_program = ctx.accounts.token_program.to_account_info();
-            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-            token::transfer(cpi_ctx, withdrawable_amount)?;
-        }
-
-        // Return remaining amount to sender
-        let return_amount = remaining_amount - withdrawable_amount;
-        if return_amount > 0 {
-            let seeds = &[
-                b"stream",
-                stream.sender.as_ref(),
-                stream.recipient.as_ref(),
-                stream.mint.as_ref(),
-                &[stream.bump],
-            ];
-            let signer = &[&seeds[..]];
-
-            let cpi_accounts = Transfer {
-                from: ctx.accounts.escrow_token_account.to_account_info(),
-                to: ctx.accounts.sender_token_account.to_account_info(),
-                authority: stream.to_account_info(),
-            };
-            let cpi_program = ctx.accounts.token_program.to_account_info();
-            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-            token::transfer(cpi_ctx, return_amount)?;
-        }
-
-        emit!(StreamCanceled {
-            stream: stream.key(),
-            canceled_by: authority,
-            recipient_amount: withdrawable_amount,
-            sender_amount: return_amount,
-        });
-
-        Ok(())
+        instructions::cancel_stream::handler(ctx)
     }
 
     pub fn pause_stream(ctx: Context<PauseStream>) -> Result<()> {
@@ -211,10 +122,8 @@ pub mod streamflow {
             ctx.accounts.authority.key() == stream.sender,
             StreamError::UnauthorizedSender
         );
-        require!(!stream.paused, StreamError::StreamAlreadyPaused);
-        require!(stream.canceled_at.is_none(), StreamError::StreamCanceled);
 
-        stream.paused = true;
+        stream.pause(Clock::get()?.unix_timestamp)?;
 
         emit!(StreamPaused {
             stream: stream.key(),
@@ -226,15 +135,14 @@ pub mod streamflow {
 
     pub fn resume_stream(ctx: Context<ResumeStream>) -> Result<()> {
         let stream = &mut ctx.accounts.stream;
+        let current_time = Clock::get()?.unix_timestamp;
         
         require!(
             ctx.accounts.authority.key() == stream.sender,
             StreamError::UnauthorizedSender
         );
-        require!(stream.paused, StreamError::StreamNotPaused);
-        require!(stream.canceled_at.is_none(), StreamError::StreamCanceled);
 
-        stream.paused = false;
+        stream.resume(current_time)?;
 
         emit!(StreamResumed {
             stream: stream.key(),
@@ -244,6 +152,21 @@ pub mod streamflow {
         Ok(())
     }
 
+    /// Resume a stream paused for longer than its `max_pause_duration`;
+    /// anyone may call this
+    pub fn force_resume(ctx: Context<ForceResume>) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+
+        stream.force_resume(Clock::get()?.unix_timestamp)?;
+
+        emit!(StreamResumed {
+            stream: stream.key(),
+            resumed_by: ctx.accounts.caller.key(),
+        });
+
+        Ok(())
+    }
+
     pub fn transfer_stream(ctx: Context<TransferStream>, new_recipient: Pubkey) -> Result<()> {
         let stream = &mut ctx.accounts.stream;
         
@@ -257,10 +180,13 @@ pub mod streamflow {
         };
 
         require!(can_transfer, StreamError::UnauthorizedTransfer);
-        require!(stream.canceled_at.is_none(), StreamError::StreamCanceled);
+        require!(stream.status != StreamStatus::Cancelled, StreamError::StreamCanceled);
 
-        let old_recipient = stream.recipient;
-        stream.recipient = new_recipient;
+        let current_time = Clock::get()?.unix_timestamp;
+        // The reservation belongs to the old recipient's cap
+        stream.release_rate_cap(ctx.accounts.rate_cap.as_deref_mut())?;
+        let old_recipient = stream.reassign_recipient(new_recipient, current_time)?;
+        stream.touch(current_time);
 
         emit!(StreamTransferred {
             stream: stream.key(),
@@ -272,7 +198,11 @@ pub mod streamflow {
         Ok(())
     }
 
-    pub fn topup_stream(ctx: Context<TopupStream>, amount: u64) -> Result<()> {
+    pub fn topup_stream(
+        ctx: Context<TopupStream>,
+        amount: u64,
+        extend_end_time_by: Option<i64>,
+    ) -> Result<()> {
         let stream = &mut ctx.accounts.stream;
         
         require!(
@@ -280,9 +210,13 @@ pub mod streamflow {
             StreamError::UnauthorizedSender
         );
         require!(amount > 0, StreamError::InvalidAmount);
-        require!(stream.canceled_at.is_none(), StreamError::StreamCanceled);
+        require!(stream.status != StreamStatus::Cancelled, StreamError::StreamCanceled);
+        stream.ensure_topup_allowed()?;
 
-        stream.deposit_amount += amount;
+        let current_time = Clock::get()?.unix_timestamp;
+        stream.top_up(amount, extend_end_time_by, current_time)?;
+        stream.touch(current_time);
+        ctx.accounts.mint_stats.record_deposit(amount)?;
 
         // Transfer additional tokens to escrow
         let cpi_accounts = Transfer {
@@ -297,140 +231,448 @@ pub mod streamflow {
         emit!(StreamToppedUp {
             stream: stream.key(),
             amount,
-            new_deposit_amount: stream.deposit_amount,
+            new_deposit_amount: stream.deposited_amount,
+            new_end_time: stream.end_time,
         });
 
         Ok(())
     }
-}
 
-fn calculate_withdrawable_amount(stream: &Stream, current_time: i64) -> Result<u64> {
-    if current_time < stream.start_time {
-        return Ok(0);
+    pub fn register_recipient_token_account(
+        ctx: Context<RegisterRecipientTokenAccount>,
+    ) -> Result<()> {
+        instructions::register_recipient_token_account::handler(ctx)
     }
 
-    if let Some(cliff_time) = stream.cliff_time {
-        if current_time < cliff_time {
-            return Ok(0);
-        }
+    pub fn set_auto_withdraw_destination(ctx: Context<SetAutoWithdrawDestination>) -> Result<()> {
+        instructions::set_auto_withdraw_destination::handler(ctx)
     }
 
-    let elapsed_time = if current_time >= stream.end_time {
-        stream.end_time - stream.start_time
-    } else {
-        current_time - stream.start_time
-    };
+    pub fn set_withdraw_destination(
+        ctx: Context<SetWithdrawDestination>,
+        destination: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::set_withdraw_destination::handler(ctx, destination)
+    }
 
-    let total_duration = stream.end_time - stream.start_time;
-    let vested_amount = (stream.deposit_amount as u128 * elapsed_time as u128 / total_duration as u128) as u64;
-    
-    Ok(vested_amount.saturating_sub(stream.withdrawn_amount))
+    pub fn update_metadata(
+        ctx: Context<UpdateMetadata>,
+        category: Option<String>,
+        external_id: Option<String>,
+    ) -> Result<()> {
+        instructions::update_metadata::handler(ctx, category, external_id)
+    }
+
+    pub fn set_stream_name(ctx: Context<UpdateMetadata>, name: String) -> Result<()> {
+        instructions::update_metadata::name_handler(ctx, name)
+    }
+
+    pub fn lock_metadata(ctx: Context<LockMetadata>) -> Result<()> {
+        instructions::update_metadata::lock_handler(ctx)
+    }
+
+    pub fn close_stream(ctx: Context<CloseStream>) -> Result<()> {
+        instructions::close_stream::handler(ctx)
+    }
+
+    pub fn claim_parked(ctx: Context<ClaimParked>) -> Result<()> {
+        instructions::claim_parked::handler(ctx)
+    }
+
+    pub fn create_instant_stream(
+        ctx: Context<CreateInstantStream>,
+        recipient: Pubkey,
+        amount: u64,
+        start_time: i64,
+        cancelable_by_sender: bool,
+    ) -> Result<()> {
+        instructions::create_instant_stream::handler(ctx, recipient, amount, start_time, cancelable_by_sender)
+    }
+
+    pub fn onboard_recipient(
+        ctx: Context<OnboardRecipient>,
+        start_time: i64,
+        drip_sol: bool,
+    ) -> Result<()> {
+        instructions::onboard_recipient::handler(ctx, start_time, drip_sol)
+    }
+
+    pub fn schedule_fee_change(
+        ctx: Context<ScheduleFeeChange>,
+        new_fee_percentage: u16,
+        effective_at: i64,
+    ) -> Result<()> {
+        instructions::schedule_fee_change::handler(ctx, new_fee_percentage, effective_at)
+    }
+
+    pub fn sweep_surplus(ctx: Context<SweepSurplus>) -> Result<()> {
+        instructions::sweep_surplus::handler(ctx)
+    }
+
+    pub fn set_recipient_rate_cap(
+        ctx: Context<SetRecipientRateCap>,
+        recipient: Pubkey,
+        max_rate: u64,
+    ) -> Result<()> {
+        instructions::set_recipient_rate_cap::handler(ctx, recipient, max_rate)
+    }
+
+    pub fn get_stream_status(ctx: Context<GetStreamStatus>) -> Result<StreamStatus> {
+        instructions::get_stream_status::handler(ctx)
+    }
+
+    pub fn withdraw_with_authorization<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, WithdrawWithAuthorization<'info>>,
+        amount: u64,
+        nonce: u64,
+        receipt_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::withdraw_with_authorization::handler(ctx, amount, nonce, receipt_hash)
+    }
+
+    pub fn create_split_stream(
+        ctx: Context<CreateSplitStream>,
+        split_id: u64,
+        recipients: Vec<(Pubkey, u16)>,
+        deposit_amount: u64,
+        start_time: i64,
+        end_time: i64,
+        fee_config: FeeConfig,
+        require_acceptance: bool,
+        min_withdrawal_interval: u64,
+    ) -> Result<()> {
+        instructions::create_split_stream::handler(
+            ctx,
+            split_id,
+            recipients,
+            deposit_amount,
+            start_time,
+            end_time,
+            fee_config,
+            require_acceptance,
+            min_withdrawal_interval,
+        )
+    }
+
+    pub fn accept_split_share(ctx: Context<AcceptSplitShare>) -> Result<()> {
+        instructions::accept_split_share::handler(ctx)
+    }
+
+    pub fn withdraw_split<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, WithdrawSplit<'info>>,
+    ) -> Result<()> {
+        instructions::withdraw_split::handler(ctx)
+    }
+
+    pub fn reduce_stream(ctx: Context<ReduceStream>, new_deposit_amount: u64) -> Result<()> {
+        instructions::reduce_stream::handler(ctx, new_deposit_amount)
+    }
+
+    pub fn update_rate(
+        ctx: Context<UpdateRate>,
+        new_rate_amount: u64,
+        new_rate_interval: u64,
+    ) -> Result<()> {
+        instructions::update_rate::handler(ctx, new_rate_amount, new_rate_interval)
+    }
+
+    pub fn crank_withdrawal<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, CrankWithdrawal<'info>>,
+    ) -> Result<()> {
+        instructions::crank_withdrawal::handler(ctx)
+    }
+
+    pub fn batch_create_from_template<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, BatchCreateFromTemplate<'info>>,
+        entries: Vec<TemplateEntry>,
+    ) -> Result<()> {
+        instructions::batch_create_from_template::handler(ctx, entries)
+    }
+
+    pub fn simulate_withdraw(
+        ctx: Context<SimulateWithdraw>,
+        amount: Option<u64>,
+        receipt_hash: [u8; 32],
+    ) -> Result<WithdrawSimulation> {
+        instructions::simulate_withdraw::handler(ctx, amount, receipt_hash)
+    }
+
+    pub fn withdraw_on_behalf<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, WithdrawOnBehalf<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::withdraw_on_behalf::handler(ctx, amount)
+    }
+
+    pub fn create_treasury(ctx: Context<CreateTreasury>, treasury_id: u64) -> Result<()> {
+        instructions::create_treasury::handler(ctx, treasury_id)
+    }
+
+    pub fn treasury_deposit(ctx: Context<TreasuryDeposit>, amount: u64) -> Result<()> {
+        instructions::treasury_deposit::handler(ctx, amount)
+    }
+
+    pub fn treasury_withdraw(ctx: Context<TreasuryWithdraw>, amount: u64) -> Result<()> {
+        instructions::treasury_withdraw::handler(ctx, amount)
+    }
+
+    pub fn add_treasury_member(
+        ctx: Context<ManageTreasuryMembers>,
+        member: Pubkey,
+        role: TreasuryRole,
+    ) -> Result<()> {
+        instructions::manage_treasury_members::add_member_handler(ctx, member, role)
+    }
+
+    pub fn remove_treasury_member(ctx: Context<ManageTreasuryMembers>, member: Pubkey) -> Result<()> {
+        instructions::manage_treasury_members::remove_member_handler(ctx, member)
+    }
+
+    pub fn create_template(
+        ctx: Context<CreateTemplate>,
+        template_id: u64,
+        params: TemplateParams,
+    ) -> Result<()> {
+        instructions::manage_template::create_handler(ctx, template_id, params)
+    }
+
+    pub fn create_stream_from_template(
+        ctx: Context<CreateStreamFromTemplate>,
+        recipient: Pubkey,
+        deposit_amount: u64,
+    ) -> Result<()> {
+        instructions::create_stream_from_template::handler(ctx, recipient, deposit_amount)
+    }
+
+    pub fn delete_template(ctx: Context<DeleteTemplate>) -> Result<()> {
+        instructions::manage_template::delete_handler(ctx)
+    }
+
+    pub fn create_streams_batch<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, CreateStreamsBatch<'info>>,
+        params: Vec<CreateStreamParams>,
+    ) -> Result<()> {
+        instructions::create_streams_batch::handler(ctx, params)
+    }
+
+    pub fn get_stream_details(ctx: Context<GetStreamDetails>) -> Result<StreamView> {
+        instructions::get_stream_details::handler(ctx)
+    }
+
+    pub fn accept_stream(ctx: Context<AcceptStream>) -> Result<()> {
+        instructions::accept_stream::handler(ctx)
+    }
+
+    pub fn initialize_program_config(ctx: Context<InitializeProgramConfig>) -> Result<()> {
+        instructions::manage_program_config::initialize_handler(ctx)
+    }
+
+    pub fn set_emergency_pause(ctx: Context<SetEmergencyPause>, paused: bool) -> Result<()> {
+        instructions::manage_program_config::pause_handler(ctx, paused)
+    }
+
+    pub fn set_max_interest_rate(ctx: Context<SetMaxInterestRate>, max_interest_rate_bps: u16) -> Result<()> {
+        instructions::manage_program_config::interest_rate_handler(ctx, max_interest_rate_bps)
+    }
+
+    pub fn fund_yield_reserve(ctx: Context<FundYieldReserve>, amount: u64) -> Result<()> {
+        instructions::manage_program_config::fund_yield_reserve_handler(ctx, amount)
+    }
+
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        beneficiary: Pubkey,
+        total_amount: u64,
+        vesting_type: VestingType,
+        start_time: i64,
+        cliff_time: Option<i64>,
+        end_time: i64,
+    ) -> Result<()> {
+        instructions::create_vesting::handler(
+            ctx,
+            beneficiary,
+            total_amount,
+            vesting_type,
+            start_time,
+            cliff_time,
+            end_time,
+        )
+    }
+
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        instructions::claim_vested::handler(ctx)
+    }
+
+    pub fn configure_governance(
+        ctx: Context<ConfigureGovernance>,
+        governance_mint: Pubkey,
+        quorum: u64,
+    ) -> Result<()> {
+        instructions::manage_program_config::governance_handler(ctx, governance_mint, quorum)
+    }
+
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        proposal_id: u64,
+        proposal_type: ProposalType,
+        voting_period: i64,
+    ) -> Result<()> {
+        instructions::governance::create_proposal_handler(ctx, proposal_id, proposal_type, voting_period)
+    }
+
+    pub fn vote(ctx: Context<Vote>, support: bool) -> Result<()> {
+        instructions::governance::vote_handler(ctx, support)
+    }
+
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        instructions::governance::execute_proposal_handler(ctx)
+    }
+
+    pub fn approve_cancel(ctx: Context<ApproveCancel>) -> Result<()> {
+        instructions::approve_cancel::handler(ctx)
+    }
+
+    pub fn clawback(ctx: Context<Clawback>) -> Result<()> {
+        instructions::clawback::handler(ctx)
+    }
+
+    pub fn withdraw_and_swap<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, WithdrawAndSwap<'info>>,
+        amount: Option<u64>,
+        min_out: u64,
+    ) -> Result<()> {
+        instructions::withdraw_and_swap::handler(ctx, amount, min_out)
+    }
+
+    pub fn crank_batch<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, CrankBatch<'info>>,
+        streams: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::crank_batch::handler(ctx, streams)
+    }
+
+    pub fn preview_vesting(ctx: Context<PreviewVesting>, sample_points: u8) -> Result<Vec<(i64, u64)>> {
+        instructions::preview_vesting::handler(ctx, sample_points)
+    }
+
+    pub fn initialize_fee_vault(ctx: Context<InitializeFeeVault>) -> Result<()> {
+        instructions::manage_fee_vault::initialize_handler(ctx)
+    }
+
+    pub fn claim_fees(ctx: Context<ClaimFees>, amount: u64) -> Result<()> {
+        instructions::manage_fee_vault::claim_handler(ctx, amount)
+    }
+
+    pub fn diff_schedule(ctx: Context<DiffSchedule>, proposed: Vec<UnlockPoint>) -> Result<()> {
+        instructions::diff_schedule::handler(ctx, proposed)
+    }
 }
 
 #[derive(Accounts)]
-#[instruction(recipient: Pubkey)]
-pub struct CreateStream<'info> {
-    #[account(
-        init,
-        payer = sender,
-        space = 8 + size_of::<Stream>(),
-        seeds = [b"stream", sender.key().as_ref(), recipient.as_ref(), sender_token_account.mint.as_ref()],
-        bump
-    )]
+pub struct PauseStream<'info> {
+    #[account(mut)]
     pub stream: Account<'info, Stream>,
     
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResumeStream<'info> {
     #[account(mut)]
-    pub sender: Signer<'info>,
-    
-    #[account(
-        mut,
-        constraint = sender_token_account.owner == sender.key()
-    )]
-    pub sender_token_account: Account<'info, TokenAccount>,
-    
-    #[account(
-        init,
-        payer = sender,
-        token::mint = sender_token_account.mint,
-        token::authority = stream,
-        seeds = [b"escrow", stream.key().as_ref()],
-        bump
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub stream: Account<'info, Stream>,
     
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct Withdraw<'info> {
+pub struct ForceResume<'info> {
+    #[account(mut)]
+    pub stream: Account<'info, Stream>,
+
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TransferStream<'info> {
     #[account(mut)]
     pub stream: Account<'info, Stream>,
     
-    pub recipient: Signer<'info>,
-    
-    #[account(
-        mut,
-        seeds = [b"escrow", stream.key().as_ref()],
-        bump
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
-    
+    /// Sender's aggregate rate cap for the current recipient, if one is configured
     #[account(
         mut,
-        constraint = recipient_token_account.owner == recipient.key(),
-        constraint = recipient_token_account.mint == stream.mint
+        seeds = [
+            b"rate_cap",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+        ],
+        bump = rate_cap.bump,
     )]
-    pub recipient_token_account: Account<'info, TokenAccount>,
+    pub rate_cap: Option<Account<'info, RecipientRateCap>>,
     
-    pub token_program: Program<'info, Token>,
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct CancelStream<'info> {
-    #[account(mut)]
+pub struct TopupStream<'info> {
+    #[account(
+        mut,
+        has_one = sender @ StreamError::UnauthorizedSender,
+    )]
     pub stream: Account<'info, Stream>,
     
-    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"escrow", stream.key().as_ref()],
-        bump
+        constraint = sender_token_account.mint == stream.mint @ StreamError::InvalidMint,
+        constraint = sender_token_account.owner == sender.key() @ StreamError::InvalidTokenAccount,
     )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub sender_token_account: Account<'info, TokenAccount>,
     
     #[account(
         mut,
-        constraint = recipient_token_account.mint == stream.mint
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump,
     )]
-    pub recipient_token_account: Account<'info, TokenAccount>,
+    pub escrow_token_account: Account<'info, TokenAccount>,
     
+    /// Program-wide totals for the mint
     #[account(
         mut,
-        constraint = sender_token_account.mint == stream.mint
+        seeds = [b"mint_stats", stream.mint.as_ref()],
+        bump = mint_stats.bump,
     )]
-    pub sender_token_account: Account<'info, TokenAccount>,
+    pub mint_stats: Account<'info, MintStats>,
     
     pub token_program: Program<'info, Token>,
 }
 
-#[derive(Accounts)]
-pub struct PauseStream<'info> {
-    #[account(mut)]
-    pub stream: Account<'info, Stream>,
-    
-    pub authority: Signer<'info>,
+#[event]
+pub struct StreamPaused {
+    pub stream: Pubkey,
+    pub paused_by: Pubkey,
 }
 
-#[derive(Accounts)]
-pub struct ResumeStream<'info> {
-    #[account(mut)]
-    pub stream: Account<'info, Stream>,
-    
-    pub authority: Signer<'info>,
+#[event]
+pub struct StreamResumed {
+    pub stream: Pubkey,
+    pub resumed_by: Pubkey,
 }
 
-#[derive(Accounts)]
-pub struct Transfer
\ No newline at end of file
+#[event]
+pub struct StreamTransferred {
+    pub stream: Pubkey,
+    pub old_recipient: Pubkey,
+    pub new_recipient: Pubkey,
+    pub transferred_by: Pubkey,
+}
+
+#[event]
+pub struct StreamToppedUp {
+    pub stream: Pubkey,
+    pub amount: u64,
+    pub new_deposit_amount: u64,
+    pub new_end_time: i64,
+}
+```
@@ -0,0 +1,172 @@
+//! Price feeds for USD-pegged streams
+//!
+//! Prices are read from a Pyth v2 price account. Only the fields the program
+//! needs are decoded: the account header, the exponent and the aggregate price
+//! with its publish time.
+
+use crate::errors::{StreamFlowError, StreamFlowResult};
+
+/// Magic number at the start of every Pyth account
+pub const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+/// Pyth account type of a price account
+pub const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+/// Smallest price account holding the aggregate price
+pub const PYTH_PRICE_ACCOUNT_MIN_LEN: usize = 240;
+
+const MAGIC_OFFSET: usize = 0;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPONENT_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const AGGREGATE_PRICE_OFFSET: usize = 208;
+const AGGREGATE_STATUS_OFFSET: usize = 224;
+
+/// Aggregate status of a price that is currently being published
+const STATUS_TRADING: u32 = 1;
+
+/// USD amounts are expressed in micro-dollars
+pub const USD_DECIMALS: u32 = 6;
+
+/// A price of one whole token in USD: `price * 10^exponent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Price {
+    pub price: i64,
+    pub exponent: i32,
+    /// Unix time the price was published
+    pub publish_time: i64,
+}
+
+impl Price {
+    /// Decode the aggregate price from a Pyth price account's data
+    pub fn from_pyth_account(data: &[u8]) -> StreamFlowResult<Price> {
+        if data.len() < PYTH_PRICE_ACCOUNT_MIN_LEN
+            || read_u32(data, MAGIC_OFFSET) != PYTH_MAGIC
+            || read_u32(data, ACCOUNT_TYPE_OFFSET) != PYTH_PRICE_ACCOUNT_TYPE
+        {
+            return Err(StreamFlowError::InvalidOracleConfiguration);
+        }
+        // A halted or unknown feed keeps its last price, which must not be used
+        if read_u32(data, AGGREGATE_STATUS_OFFSET) != STATUS_TRADING {
+            return Err(StreamFlowError::OraclePriceDataStale);
+        }
+        Ok(Price {
+            price: read_i64(data, AGGREGATE_PRICE_OFFSET),
+            exponent: read_u32(data, EXPONENT_OFFSET) as i32,
+            publish_time: read_i64(data, TIMESTAMP_OFFSET),
+        })
+    }
+
+    /// Fail when the price was published more than `max_age` seconds before `current_time`
+    pub fn ensure_fresh(&self, current_time: i64, max_age: u64) -> StreamFlowResult<()> {
+        let age = current_time.saturating_sub(self.publish_time);
+        if age < 0 || age as u64 > max_age {
+            return Err(StreamFlowError::OraclePriceDataStale);
+        }
+        Ok(())
+    }
+
+    /// Token base units worth `usd` micro-dollars, for a mint with `decimals`,
+    /// rounded down
+    pub fn usd_to_tokens(&self, usd: u128, decimals: u8) -> StreamFlowResult<u64> {
+        if self.price <= 0 {
+            return Err(StreamFlowError::InvalidOracleConfiguration);
+        }
+        let pow10 = |exp: u32| 10u128.checked_pow(exp).ok_or(StreamFlowError::ArithmeticOverflow);
+
+        let mut numerator = usd
+            .checked_mul(pow10(u32::from(decimals))?)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        let mut denominator = (self.price as u128)
+            .checked_mul(pow10(USD_DECIMALS)?)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        if self.exponent < 0 {
+            numerator = numerator
+                .checked_mul(pow10(self.exponent.unsigned_abs())?)
+                .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        } else {
+            denominator = denominator
+                .checked_mul(pow10(self.exponent as u32)?)
+                .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        }
+
+        u64::try_from(numerator / denominator).map_err(|_| StreamFlowError::ArithmeticOverflow)
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    i64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Bytes of a Pyth price account holding `price * 10^exponent`
+    pub fn mock_price_account(price: i64, exponent: i32, publish_time: i64) -> Vec<u8> {
+        let mut data = vec![0u8; PYTH_PRICE_ACCOUNT_MIN_LEN];
+        data[MAGIC_OFFSET..MAGIC_OFFSET + 4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[ACCOUNT_TYPE_OFFSET..ACCOUNT_TYPE_OFFSET + 4]
+            .copy_from_slice(&PYTH_PRICE_ACCOUNT_TYPE.to_le_bytes());
+        data[EXPONENT_OFFSET..EXPONENT_OFFSET + 4].copy_from_slice(&exponent.to_le_bytes());
+        data[TIMESTAMP_OFFSET..TIMESTAMP_OFFSET + 8].copy_from_slice(&publish_time.to_le_bytes());
+        data[AGGREGATE_PRICE_OFFSET..AGGREGATE_PRICE_OFFSET + 8].copy_from_slice(&price.to_le_bytes());
+        data[AGGREGATE_STATUS_OFFSET..AGGREGATE_STATUS_OFFSET + 4]
+            .copy_from_slice(&STATUS_TRADING.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_decode_price_account() {
+        let data = mock_price_account(2_500_000_000, -8, 1_000);
+        let price = Price::from_pyth_account(&data).unwrap();
+        assert_eq!(price, Price { price: 2_500_000_000, exponent: -8, publish_time: 1_000 });
+
+        let mut wrong_type = data.clone();
+        wrong_type[ACCOUNT_TYPE_OFFSET] = 2;
+        assert!(matches!(
+            Price::from_pyth_account(&wrong_type),
+            Err(StreamFlowError::InvalidOracleConfiguration)
+        ));
+        assert!(matches!(
+            Price::from_pyth_account(&data[..200]),
+            Err(StreamFlowError::InvalidOracleConfiguration)
+        ));
+
+        let mut halted = data;
+        halted[AGGREGATE_STATUS_OFFSET] = 2;
+        assert!(matches!(Price::from_pyth_account(&halted), Err(StreamFlowError::OraclePriceDataStale)));
+    }
+
+    #[test]
+    fn test_fresh_and_stale_prices() {
+        let data = mock_price_account(100_000_000, -8, 1_000);
+        let price = Price::from_pyth_account(&data).unwrap();
+        assert!(price.ensure_fresh(1_000, 60).is_ok());
+        assert!(price.ensure_fresh(1_060, 60).is_ok());
+        assert!(matches!(price.ensure_fresh(1_061, 60), Err(StreamFlowError::OraclePriceDataStale)));
+        // A price from the future is not trusted either
+        assert!(matches!(price.ensure_fresh(999, 60), Err(StreamFlowError::OraclePriceDataStale)));
+    }
+
+    #[test]
+    fn test_usd_to_tokens() {
+        // $25 per token, 9 decimals: $50 buys 2 tokens
+        let price = Price { price: 2_500_000_000, exponent: -8, publish_time: 0 };
+        assert_eq!(price.usd_to_tokens(50_000_000, 9).unwrap(), 2_000_000_000);
+        // $0.000001 buys 40 base units
+        assert_eq!(price.usd_to_tokens(1, 9).unwrap(), 40);
+
+        let positive_exponent = Price { price: 5, exponent: 1, publish_time: 0 };
+        assert_eq!(positive_exponent.usd_to_tokens(100_000_000, 6).unwrap(), 2_000_000);
+
+        let zero = Price { price: 0, exponent: -8, publish_time: 0 };
+        assert!(matches!(zero.usd_to_tokens(1, 6), Err(StreamFlowError::InvalidOracleConfiguration)));
+    }
+}
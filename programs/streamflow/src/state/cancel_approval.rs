@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+use super::constants::MAX_CANCEL_APPROVERS;
+use super::Stream;
+use crate::errors::StreamError;
+
+/// Approvals collected, one transaction at a time, towards cancelling a
+/// stream that has a cancel multisig
+#[account]
+#[derive(Debug, Default)]
+pub struct CancelApproval {
+    /// Stream the approvals are for
+    pub stream: Pubkey,
+    /// Approvers that have signed so far, each at most once
+    pub approvals: Vec<Pubkey>,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl CancelApproval {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // stream
+        4 + MAX_CANCEL_APPROVERS * 32 + // approvals
+        1; // bump
+
+    /// Record `approver`'s approval; approving twice counts once
+    pub fn approve(&mut self, stream_key: Pubkey, stream: &Stream, approver: Pubkey, bump: u8) -> Result<()> {
+        require!(
+            stream.cancel_approvers.contains(&approver),
+            StreamError::UnauthorizedCancel
+        );
+        if self.stream == Pubkey::default() {
+            self.stream = stream_key;
+            self.bump = bump;
+        }
+        if !self.approvals.contains(&approver) {
+            self.approvals.push(approver);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::StreamFlowError;
+    use crate::state::stream::StreamBuilder;
+
+    fn two_of_three() -> (Stream, Vec<Pubkey>) {
+        let approvers = vec![Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut stream = StreamBuilder::new().build();
+        stream.set_cancel_multisig(approvers.clone(), 2).unwrap();
+        (stream, approvers)
+    }
+
+    #[test]
+    fn test_two_of_three_reaches_threshold() {
+        let (stream, approvers) = two_of_three();
+        let stream_key = Pubkey::new_unique();
+        let mut approval = CancelApproval::default();
+
+        approval.approve(stream_key, &stream, approvers[0], 255).unwrap();
+        // A repeated approval does not count twice
+        approval.approve(stream_key, &stream, approvers[0], 255).unwrap();
+        assert!(matches!(
+            stream.check_cancel_approvals(Some(&approval)),
+            Err(StreamFlowError::MultisigThresholdNotMet)
+        ));
+
+        approval.approve(stream_key, &stream, approvers[2], 255).unwrap();
+        assert_eq!(approval.approvals, vec![approvers[0], approvers[2]]);
+        assert!(stream.check_cancel_approvals(Some(&approval)).is_ok());
+    }
+
+    #[test]
+    fn test_premature_cancel_fails() {
+        let (stream, _) = two_of_three();
+        assert!(matches!(
+            stream.check_cancel_approvals(None),
+            Err(StreamFlowError::MultisigThresholdNotMet)
+        ));
+        // Streams without a multisig need no approvals
+        assert!(StreamBuilder::new().build().check_cancel_approvals(None).is_ok());
+    }
+
+    #[test]
+    fn test_only_approvers_can_approve() {
+        let (stream, _) = two_of_three();
+        let mut approval = CancelApproval::default();
+        assert_eq!(
+            approval.approve(Pubkey::new_unique(), &stream, stream.sender, 255).unwrap_err(),
+            StreamError::UnauthorizedCancel.into()
+        );
+        assert!(approval.approvals.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_multisig_configurations() {
+        let key = Pubkey::new_unique();
+        let mut stream = StreamBuilder::new().build();
+        for (approvers, threshold) in [
+            (vec![key], 2),
+            (vec![key, key], 2),
+            (vec![key], 0),
+            ((0..=MAX_CANCEL_APPROVERS).map(|_| Pubkey::new_unique()).collect(), 1),
+        ] {
+            assert!(matches!(
+                stream.set_cancel_multisig(approvers, threshold),
+                Err(StreamFlowError::InvalidMultisigConfiguration)
+            ));
+        }
+        assert!(stream.set_cancel_multisig(Vec::new(), 0).is_ok());
+        assert_eq!(stream.cancel_threshold, 0);
+    }
+}
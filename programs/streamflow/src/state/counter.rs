@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+use crate::errors::{StreamFlowError, StreamFlowResult};
+
+/// Number of streams ever created between a (sender, recipient) pair
+///
+/// Each stream PDA is seeded with the counter value at its creation, so every
+/// stream between two parties can be derived without `getProgramAccounts`.
+#[account]
+#[derive(Debug)]
+pub struct StreamCounter {
+    /// The sender of the streams
+    pub sender: Pubkey,
+    /// The recipient of the streams
+    pub recipient: Pubkey,
+    /// Nonce of the next stream to be created
+    pub count: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl StreamCounter {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // sender
+        32 + // recipient
+        8 + // count
+        1; // bump
+
+    /// Take the nonce for a new stream, initializing the counter on first use
+    pub fn claim_nonce(
+        &mut self,
+        sender: Pubkey,
+        recipient: Pubkey,
+        bump: u8,
+    ) -> StreamFlowResult<u64> {
+        if self.count == 0 {
+            self.sender = sender;
+            self.recipient = recipient;
+            self.bump = bump;
+        }
+        let nonce = self.count;
+        self.count = nonce
+            .checked_add(1)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        Ok(nonce)
+    }
+
+    /// Addresses of every stream created between the pair, in creation order
+    pub fn list_streams_between(&self, program_id: &Pubkey) -> Vec<Pubkey> {
+        (0..self.count)
+            .map(|nonce| stream_address(&self.sender, &self.recipient, nonce, program_id).0)
+            .collect()
+    }
+}
+
+/// Derive the stream PDA for the given pair and nonce
+pub fn stream_address(
+    sender: &Pubkey,
+    recipient: &Pubkey,
+    nonce: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"stream", sender.as_ref(), recipient.as_ref(), &nonce.to_le_bytes()],
+        program_id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enumerate_streams_between_parties() {
+        let program_id = Pubkey::new_unique();
+        let mut counter = StreamCounter {
+            sender: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            count: 0,
+            bump: 255,
+        };
+
+        // Create three streams, each seeded with the counter at creation
+        let mut created = Vec::new();
+        for _ in 0..3 {
+            let (stream, _) = stream_address(&counter.sender, &counter.recipient, counter.count, &program_id);
+            created.push(stream);
+            counter.count += 1;
+        }
+
+        let listed = counter.list_streams_between(&program_id);
+        assert_eq!(listed, created);
+        assert_ne!(listed[0], listed[1]);
+        assert_ne!(listed[1], listed[2]);
+    }
+}
@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use super::constants::MAX_DIRECTORY_STREAMS;
+use crate::errors::{StreamFlowError, StreamFlowResult};
+
+/// Streams a sender has created with `create_stream` and not yet closed, so
+/// clients can list them without scanning every program account
+#[account]
+#[derive(Debug, Default)]
+pub struct StreamDirectory {
+    /// Sender whose streams are listed
+    pub sender: Pubkey,
+    /// Stream addresses in creation order
+    pub streams: Vec<Pubkey>,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl StreamDirectory {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // sender
+        4 + MAX_DIRECTORY_STREAMS * 32 + // streams
+        1; // bump
+
+    /// Set the sender and bump the first time the account is used
+    pub fn initialize_if_needed(&mut self, sender: Pubkey, bump: u8) {
+        if self.sender == Pubkey::default() {
+            self.sender = sender;
+            self.bump = bump;
+        }
+    }
+
+    /// List a newly created stream
+    pub fn add(&mut self, stream: Pubkey) -> StreamFlowResult<()> {
+        if self.streams.len() >= MAX_DIRECTORY_STREAMS {
+            return Err(StreamFlowError::CapacityLimitReached);
+        }
+        self.streams.push(stream);
+        Ok(())
+    }
+
+    /// Drop a closed stream, returning whether it was listed
+    pub fn remove(&mut self, stream: &Pubkey) -> bool {
+        match self.streams.iter().position(|listed| listed == stream) {
+            Some(index) => {
+                self.streams.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::counter::stream_address;
+
+    #[test]
+    fn test_directory_lists_created_streams() {
+        let sender = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let mut directory = StreamDirectory::default();
+        directory.initialize_if_needed(sender, 254);
+
+        let created: Vec<Pubkey> = (0..3)
+            .map(|nonce| stream_address(&sender, &recipient, nonce, &crate::ID).0)
+            .collect();
+        for stream in &created {
+            directory.add(*stream).unwrap();
+        }
+        assert_eq!(directory.sender, sender);
+        assert_eq!(directory.streams, created);
+
+        // Closing the middle stream keeps the others in order
+        assert!(directory.remove(&created[1]));
+        assert_eq!(directory.streams, vec![created[0], created[2]]);
+        assert!(!directory.remove(&created[1]));
+    }
+
+    #[test]
+    fn test_full_directory_is_rejected() {
+        let mut directory = StreamDirectory::default();
+        for _ in 0..MAX_DIRECTORY_STREAMS {
+            directory.add(Pubkey::new_unique()).unwrap();
+        }
+        assert!(matches!(
+            directory.add(Pubkey::new_unique()),
+            Err(StreamFlowError::CapacityLimitReached)
+        ));
+
+        // Closing a stream frees a slot
+        let closed = directory.streams[0];
+        directory.remove(&closed);
+        directory.add(Pubkey::new_unique()).unwrap();
+        assert_eq!(directory.streams.len(), MAX_DIRECTORY_STREAMS);
+    }
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use crate::errors::{StreamError, StreamFlowError};
+
+/// Platform fees of one mint held for a fee recipient across all of their
+/// streams, until the recipient claims them
+#[account]
+#[derive(Debug, Default)]
+pub struct FeeVault {
+    /// Fee recipient allowed to claim
+    pub fee_recipient: Pubkey,
+    /// Mint the fees are paid in
+    pub mint: Pubkey,
+    /// Token account holding the fees, owned by this vault
+    pub token_account: Pubkey,
+    /// Fees paid into the vault so far
+    pub total_fees_collected: u64,
+    /// Fees the recipient has claimed so far
+    pub total_fees_claimed: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl FeeVault {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // fee_recipient
+        32 + // mint
+        32 + // token_account
+        8 + // total_fees_collected
+        8 + // total_fees_claimed
+        1; // bump
+
+    /// Fees collected and not yet claimed
+    pub fn unclaimed(&self) -> u64 {
+        self.total_fees_collected.saturating_sub(self.total_fees_claimed)
+    }
+
+    /// Record a fee paid into the vault by a withdrawal
+    pub fn record_fee(&mut self, amount: u64) -> Result<()> {
+        self.total_fees_collected = self.total_fees_collected
+            .checked_add(amount)
+            .ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Record a claim of `amount`, which may not exceed the unclaimed fees
+    pub fn claim(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, StreamFlowError::InvalidAmount);
+        require!(amount <= self.unclaimed(), StreamError::InsufficientFunds);
+        self.total_fees_claimed += amount;
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+use anchor_lang::prelude::*;
+use crate::errors::{StreamFlowError, StreamFlowResult};
+use super::ProgramConfig;
+
+/// Change to the program's settings put to a token-weighted vote
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ProposalType {
+    /// Turn the emergency pause on or off
+    SetEmergencyPause { paused: bool },
+    /// Hand the `ProgramConfig` admin role to a new key
+    SetAdmin { admin: Pubkey },
+}
+
+impl Default for ProposalType {
+    fn default() -> Self {
+        ProposalType::SetEmergencyPause { paused: false }
+    }
+}
+
+impl ProposalType {
+    /// Apply the change to the program's settings
+    pub fn apply(&self, config: &mut ProgramConfig) {
+        match *self {
+            ProposalType::SetEmergencyPause { paused } => config.emergency_paused = paused,
+            ProposalType::SetAdmin { admin } => config.admin = admin,
+        }
+    }
+}
+
+/// A proposed settings change and its running tally
+#[account]
+#[derive(Debug, Default)]
+pub struct Proposal {
+    /// Account that opened the proposal
+    pub proposer: Pubkey,
+    /// Identifier distinguishing the proposer's proposals in the PDA seeds
+    pub proposal_id: u64,
+    pub proposal_type: ProposalType,
+    /// Governance tokens voted in favour
+    pub votes_for: u64,
+    /// Governance tokens voted against
+    pub votes_against: u64,
+    /// Total votes needed, copied from `ProgramConfig` at creation
+    pub quorum: u64,
+    /// Votes are accepted until this time; execution only after it
+    pub voting_deadline: i64,
+    pub executed: bool,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Proposal {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // proposer
+        8 + // proposal_id
+        (1 + 32) + // proposal_type
+        8 + // votes_for
+        8 + // votes_against
+        8 + // quorum
+        8 + // voting_deadline
+        1 + // executed
+        1; // bump
+
+    /// Add `weight` votes on one side
+    pub fn record_vote(&mut self, support: bool, weight: u64, current_time: i64) -> StreamFlowResult<()> {
+        if current_time >= self.voting_deadline {
+            return Err(StreamFlowError::ProposalVotingExpired);
+        }
+        if weight == 0 {
+            return Err(StreamFlowError::InsufficientVotingPower);
+        }
+        let tally = if support { &mut self.votes_for } else { &mut self.votes_against };
+        *tally = tally
+            .checked_add(weight)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Ensure the proposal can be executed: voting has closed, enough votes
+    /// were cast, and more of them were in favour than against
+    pub fn check_executable(&self, current_time: i64) -> StreamFlowResult<()> {
+        if self.executed {
+            return Err(StreamFlowError::ProposalAlreadyExecuted);
+        }
+        if current_time < self.voting_deadline {
+            return Err(StreamFlowError::TimelockPeriodNotElapsed);
+        }
+        let turnout = self.votes_for.saturating_add(self.votes_against);
+        if turnout < self.quorum {
+            return Err(StreamFlowError::QuorumNotReached);
+        }
+        if self.votes_for <= self.votes_against {
+            return Err(StreamFlowError::ProposalExecutionFailed);
+        }
+        Ok(())
+    }
+}
+
+/// One voter's ballot on a proposal; its PDA makes a second vote impossible
+#[account]
+#[derive(Debug, Default)]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub support: bool,
+    /// Governance tokens held by the voter when the vote was cast
+    pub weight: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl VoteRecord {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // proposal
+        32 + // voter
+        1 + // support
+        8 + // weight
+        1; // bump
+
+    /// Fill in a fresh record, rejecting one that has already been used
+    pub fn cast(&mut self, proposal: Pubkey, voter: Pubkey, support: bool, weight: u64, bump: u8) -> StreamFlowResult<()> {
+        if self.voter != Pubkey::default() {
+            return Err(StreamFlowError::AlreadyVoted);
+        }
+        self.proposal = proposal;
+        self.voter = voter;
+        self.support = support;
+        self.weight = weight;
+        self.bump = bump;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proposal(proposal_type: ProposalType) -> Proposal {
+        Proposal {
+            proposer: Pubkey::new_unique(),
+            proposal_type,
+            quorum: 1_000,
+            voting_deadline: 500,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_passing_proposal_executes() {
+        let new_admin = Pubkey::new_unique();
+        let mut proposal = proposal(ProposalType::SetAdmin { admin: new_admin });
+        proposal.record_vote(true, 700, 100).unwrap();
+        proposal.record_vote(false, 400, 200).unwrap();
+
+        assert!(matches!(
+            proposal.check_executable(499),
+            Err(StreamFlowError::TimelockPeriodNotElapsed)
+        ));
+        proposal.check_executable(500).unwrap();
+
+        let mut config = ProgramConfig { admin: Pubkey::new_unique(), ..Default::default() };
+        proposal.proposal_type.apply(&mut config);
+        assert_eq!(config.admin, new_admin);
+
+        proposal.executed = true;
+        assert!(matches!(
+            proposal.check_executable(600),
+            Err(StreamFlowError::ProposalAlreadyExecuted)
+        ));
+    }
+
+    #[test]
+    fn test_failing_proposals_are_not_executable() {
+        // Too few votes cast
+        let mut short = proposal(ProposalType::SetEmergencyPause { paused: true });
+        short.record_vote(true, 999, 100).unwrap();
+        assert!(matches!(short.check_executable(500), Err(StreamFlowError::QuorumNotReached)));
+
+        // Quorum met, but outvoted
+        let mut rejected = proposal(ProposalType::SetEmergencyPause { paused: true });
+        rejected.record_vote(true, 500, 100).unwrap();
+        rejected.record_vote(false, 500, 100).unwrap();
+        assert!(matches!(
+            rejected.check_executable(500),
+            Err(StreamFlowError::ProposalExecutionFailed)
+        ));
+
+        // Votes after the deadline do not count
+        assert!(matches!(
+            short.record_vote(true, 1, 500),
+            Err(StreamFlowError::ProposalVotingExpired)
+        ));
+        assert_eq!(short.votes_for, 999);
+    }
+
+    #[test]
+    fn test_one_vote_per_voter() {
+        let proposal = Pubkey::new_unique();
+        let voter = Pubkey::new_unique();
+        let mut record = VoteRecord::default();
+        record.cast(proposal, voter, true, 100, 255).unwrap();
+        assert!(matches!(
+            record.cast(proposal, voter, false, 100, 255),
+            Err(StreamFlowError::AlreadyVoted)
+        ));
+        assert!(record.support);
+
+        let mut empty_handed = Proposal::default();
+        empty_handed.voting_deadline = 500;
+        assert!(matches!(
+            empty_handed.record_vote(true, 0, 100),
+            Err(StreamFlowError::InsufficientVotingPower)
+        ));
+    }
+}
@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+use crate::errors::{StreamFlowError, StreamFlowResult};
+
+/// Program-wide totals for one mint, so total value locked can be read from a
+/// single account instead of scanning every stream
+#[account]
+#[derive(Debug, Default)]
+pub struct MintStats {
+    /// Mint the totals are kept for
+    pub mint: Pubkey,
+    /// Tokens ever deposited into stream escrows
+    pub total_deposited: u64,
+    /// Tokens ever released from stream escrows, to recipients or back to senders
+    pub total_withdrawn: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl MintStats {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // mint
+        8 + // total_deposited
+        8 + // total_withdrawn
+        1; // bump
+
+    /// Set the mint and bump the first time the account is used
+    pub fn initialize_if_needed(&mut self, mint: Pubkey, bump: u8) {
+        if self.mint == Pubkey::default() {
+            self.mint = mint;
+            self.bump = bump;
+        }
+    }
+
+    /// Count tokens moved into an escrow
+    pub fn record_deposit(&mut self, amount: u64) -> StreamFlowResult<()> {
+        self.total_deposited = self
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Count tokens moved out of an escrow
+    pub fn record_withdrawal(&mut self, amount: u64) -> StreamFlowResult<()> {
+        self.total_withdrawn = self
+            .total_withdrawn
+            .checked_add(amount)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Tokens currently locked in escrows of this mint
+    pub fn total_value_locked(&self) -> StreamFlowResult<u64> {
+        self.total_deposited
+            .checked_sub(self.total_withdrawn)
+            .ok_or(StreamFlowError::ArithmeticUnderflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::cancel_stream::cancel_split;
+    use crate::state::stream::StreamBuilder;
+
+    #[test]
+    fn test_stats_track_create_withdraw_cancel() {
+        let mint = Pubkey::new_unique();
+        let mut stats = MintStats::default();
+        stats.initialize_if_needed(mint, 254);
+        assert_eq!(stats.mint, mint);
+
+        // Create: 1000 tokens over [100, 200]
+        let mut stream = StreamBuilder::new().amount(1_000).build();
+        stats.record_deposit(stream.deposited_amount).unwrap();
+        assert_eq!(stats.total_value_locked().unwrap(), 1_000);
+
+        // Withdraw at the midpoint
+        let withdrawn = stream.withdrawable_amount(150).unwrap();
+        stream.withdrawn_amount += withdrawn;
+        stats.record_withdrawal(withdrawn).unwrap();
+        assert_eq!(stats.total_value_locked().unwrap(), 500);
+
+        // Cancel later: the recipient's vested share and the sender's refund both leave escrow
+        let escrow = stream.deposited_amount - stream.withdrawn_amount;
+        let (to_recipient, to_sender) = cancel_split(&stream, 175, escrow, None).unwrap();
+        assert_eq!((to_recipient, to_sender), (250, 250));
+        stats.record_withdrawal(to_recipient + to_sender).unwrap();
+
+        assert_eq!(stats.total_deposited, 1_000);
+        assert_eq!(stats.total_withdrawn, 1_000);
+        assert_eq!(stats.total_value_locked().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_stats_overflow_is_rejected() {
+        let mut stats = MintStats { total_deposited: u64::MAX, ..Default::default() };
+        assert!(matches!(stats.record_deposit(1), Err(StreamFlowError::ArithmeticOverflow)));
+        assert_eq!(stats.total_deposited, u64::MAX);
+
+        stats.total_withdrawn = u64::MAX;
+        assert!(matches!(stats.record_withdrawal(1), Err(StreamFlowError::ArithmeticOverflow)));
+    }
+
+    #[test]
+    fn test_initialize_only_once() {
+        let mint = Pubkey::new_unique();
+        let mut stats = MintStats::default();
+        stats.initialize_if_needed(mint, 254);
+        stats.initialize_if_needed(Pubkey::new_unique(), 1);
+        assert_eq!(stats.mint, mint);
+        assert_eq!(stats.bump, 254);
+    }
+}
@@ -4,11 +4,33 @@
 //! This module contains all the account state definitions and related functionality
 //! for the StreamFlow token streaming and vesting platform.
 
+pub mod cancel_approval;
+pub mod counter;
+pub mod directory;
+pub mod fee_vault;
+pub mod governance;
+pub mod mint_stats;
+pub mod program_config;
+pub mod rate_cap;
+pub mod schedule;
+pub mod split;
 pub mod stream;
+pub mod template;
 pub mod treasury;
 pub mod vesting;
 
+pub use cancel_approval::*;
+pub use counter::*;
+pub use directory::*;
+pub use fee_vault::*;
+pub use governance::*;
+pub use mint_stats::*;
+pub use program_config::*;
+pub use rate_cap::*;
+pub use schedule::*;
+pub use split::*;
 pub use stream::*;
+pub use template::*;
 pub use treasury::*;
 pub use vesting::*;
 
@@ -120,6 +142,17 @@ impl Default for TreasuryRole {
     }
 }
 
+/// Operations that can be processed in batch instructions
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum BatchOp {
+    /// Create a stream: two account inits plus a token transfer per item
+    Create,
+    /// Withdraw from a stream: one token transfer per item
+    Withdraw,
+    /// Cancel a stream: up to two token transfers per item
+    Cancel,
+}
+
 /// Common constants used across state modules
 pub mod constants {
     /// Maximum number of recipients per stream
@@ -131,12 +164,27 @@ pub mod constants {
     /// Maximum number of treasury members
     pub const MAX_TREASURY_MEMBERS: usize = 20;
     
+    /// Maximum number of keys that can approve cancelling a stream
+    pub const MAX_CANCEL_APPROVERS: usize = 5;
+    
+    /// Maximum number of previous recipients kept on a stream, and so of transfers
+    pub const MAX_RECIPIENT_HISTORY: usize = 10;
+    
+    /// Maximum number of open streams listed in a sender's directory
+    pub const MAX_DIRECTORY_STREAMS: usize = 256;
+    
+    /// Maximum number of points sampled by a vesting preview
+    pub const MAX_VESTING_PREVIEW_POINTS: usize = 64;
+    
     /// Minimum stream duration in seconds (1 minute)
     pub const MIN_STREAM_DURATION: u64 = 60;
     
     /// Maximum stream duration in seconds (10 years)
     pub const MAX_STREAM_DURATION: u64 = 315360000;
     
+    /// Maximum distance of a stream's start time into the future (2 years)
+    pub const MAX_FUTURE_START: u64 = 63072000;
+    
     /// Minimum stream amount (1 lamport)
     pub const MIN_STREAM_AMOUNT: u64 = 1;
     
@@ -145,6 +193,12 @@ pub mod constants {
     
     /// Maximum platform fee basis points (5%)
     pub const MAX_PLATFORM_FEE_BPS: u16 = 500;
+    
+    /// Minimum notice before an announced fee change takes effect (7 days)
+    pub const MIN_FEE_CHANGE_NOTICE: i64 = 604800;
+    
+    /// Seconds in a 365 day year, used to annualize interest rates
+    pub const SECONDS_PER_YEAR: u64 = 31536000;
 }
 
 /// Error codes for state validation
@@ -200,6 +254,18 @@ pub enum StateError {
     
     #[msg("Platform fee exceeds maximum")]
     PlatformFeeExceedsMaximum,
+    
+    #[msg("At least one recipient must have a nonzero weight")]
+    NoWeightedRecipients,
+    
+    #[msg("Recipient weights must sum to 10000 bps")]
+    InvalidSplitWeights,
+    
+    #[msg("Account is already a treasury member")]
+    DuplicateTreasuryMember,
+    
+    #[msg("Account is not a treasury member")]
+    TreasuryMemberNotFound,
 }
 
 /// Utility functions for state management
@@ -213,7 +279,8 @@ pub mod utils {
         end_time: i64,
         current_time: i64,
     ) -> u64 {
-        if current_time <= start_time {
+        // Negative clock readings are treated as pre-start
+        if current_time < 0 || current_time <= start_time {
             return 0;
         }
         
@@ -221,10 +288,11 @@ pub mod utils {
             return total_amount;
         }
         
-        let elapsed = (current_time - start_time) as u64;
-        let duration = (end_time - start_time) as u64;
+        let elapsed = (current_time - start_time) as u128;
+        let duration = (end_time - start_time) as u128;
         
-        (total_amount * elapsed) / duration
+        // Below `end_time` the result is strictly less than `total_amount`
+        ((total_amount as u128 * elapsed) / duration) as u64
     }
     
     /// Calculates the vested amount based on vesting type and schedule
@@ -259,17 +327,22 @@ pub mod utils {
     }
     
     /// Validates that a status transition is allowed
+    ///
+    /// `Cancelled` and `Completed` are terminal. A stream paused before its
+    /// start time resumes back into `Scheduled`.
     pub fn is_valid_status_transition(from: StreamStatus, to: StreamStatus) -> bool {
-        match (from, to) {
-            (StreamStatus::Scheduled, StreamStatus::Streaming) => true,
-            (StreamStatus::Scheduled, StreamStatus::Cancelled) => true,
-            (StreamStatus::Streaming, StreamStatus::Paused) => true,
-            (StreamStatus::Streaming, StreamStatus::Cancelled) => true,
-            (StreamStatus::Streaming, StreamStatus::Completed) => true,
-            (StreamStatus::Paused, StreamStatus::Streaming) => true,
-            (StreamStatus::Paused, StreamStatus::Cancelled) => true,
-            _ => false,
-        }
+        matches!(
+            (from, to),
+            (StreamStatus::Scheduled, StreamStatus::Streaming)
+                | (StreamStatus::Scheduled, StreamStatus::Paused)
+                | (StreamStatus::Scheduled, StreamStatus::Cancelled)
+                | (StreamStatus::Streaming, StreamStatus::Paused)
+                | (StreamStatus::Streaming, StreamStatus::Cancelled)
+                | (StreamStatus::Streaming, StreamStatus::Completed)
+                | (StreamStatus::Paused, StreamStatus::Scheduled)
+                | (StreamStatus::Paused, StreamStatus::Streaming)
+                | (StreamStatus::Paused, StreamStatus::Cancelled)
+        )
     }
     
     /// Calculates platform fee for a given amount
@@ -277,6 +350,59 @@ pub mod utils {
         (amount * fee_bps as u64) / 10000
     }
     
+    /// Splits `total` across recipients in proportion to their weights
+    ///
+    /// Zero-weight recipients always receive nothing. Rounding leftovers are
+    /// handed out one unit at a time to nonzero-weight recipients in order,
+    /// so the shares always sum to `total`.
+    pub fn distribute_by_weight(total: u64, weights: &[u16]) -> Result<Vec<u64>> {
+        let total_weight: u128 = weights.iter().map(|w| *w as u128).sum();
+        require!(total_weight > 0, StateError::NoWeightedRecipients);
+
+        let mut shares: Vec<u64> = weights
+            .iter()
+            .map(|w| ((total as u128 * *w as u128) / total_weight) as u64)
+            .collect();
+
+        let mut leftover = total - shares.iter().sum::<u64>();
+        for (share, weight) in shares.iter_mut().zip(weights) {
+            if leftover == 0 {
+                break;
+            }
+            if *weight > 0 {
+                *share += 1;
+                leftover -= 1;
+            }
+        }
+
+        Ok(shares)
+    }
+    
+    /// Maximum number of items a batch instruction may process for `op`
+    ///
+    /// Budgeted against the 1.4M CU transaction cap using per-item estimates:
+    /// create ~95k CU (stream + escrow init, transfer), withdraw ~42k CU,
+    /// cancel ~70k CU (two transfers). Limits leave ~40% headroom for the
+    /// instruction's fixed overhead and cost variance across token programs,
+    /// and stay within the transaction's account lock limit. Re-measure with
+    /// `solana-test-validator` logs when the per-item instructions change.
+    pub fn max_batch_size_for_op(op: BatchOp) -> usize {
+        match op {
+            BatchOp::Create => 8,
+            BatchOp::Withdraw => 20,
+            BatchOp::Cancel => 12,
+        }
+    }
+
+    /// Reject batches larger than `max_batch_size_for_op(op)`
+    pub fn check_batch_size(op: BatchOp, len: usize) -> Result<()> {
+        require!(
+            len <= max_batch_size_for_op(op),
+            crate::errors::StreamFlowError::BatchOperationLimitExceeded
+        );
+        Ok(())
+    }
+    
     /// Validates treasury role permissions
     pub fn has_treasury_permission(role: TreasuryRole, required_role: TreasuryRole) -> bool {
         match (role, required_role) {
@@ -316,6 +442,13 @@ mod tests {
         assert_eq!(calculate_streamed_amount(total, start, end, 50), 500);
         assert_eq!(calculate_streamed_amount(total, start, end, 100), 1000);
         assert_eq!(calculate_streamed_amount(total, start, end, 150), 1000);
+        assert_eq!(calculate_streamed_amount(u64::MAX, start, end, 50), u64::MAX / 2);
+    }
+    
+    #[test]
+    fn test_calculate_streamed_amount_negative_timestamps() {
+        assert_eq!(calculate_streamed_amount(1000, -100, 100, -50), 0);
+        assert_eq!(calculate_streamed_amount(1000, 0, 100, i64::MIN), 0);
     }
     
     #[test]
@@ -326,6 +459,35 @@ mod tests {
         assert!(!is_valid_status_transition(StreamStatus::Cancelled, StreamStatus::Streaming));
     }
     
+    #[test]
+    fn test_full_status_transition_matrix() {
+        use StreamStatus::*;
+        let all = [Scheduled, Streaming, Paused, Cancelled, Completed];
+        let allowed = [
+            (Scheduled, Streaming),
+            (Scheduled, Paused),
+            (Scheduled, Cancelled),
+            (Streaming, Paused),
+            (Streaming, Cancelled),
+            (Streaming, Completed),
+            (Paused, Scheduled),
+            (Paused, Streaming),
+            (Paused, Cancelled),
+        ];
+        
+        for from in all {
+            for to in all {
+                assert_eq!(
+                    is_valid_status_transition(from, to),
+                    allowed.contains(&(from, to)),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+    
     #[test]
     fn test_platform_fee_calculation() {
         assert_eq!(calculate_platform_fee(10000, 50), 50); // 0.5%
@@ -333,6 +495,23 @@ mod tests {
         assert_eq!(calculate_platform_fee(10000, 500), 500); // 5%
     }
     
+    #[test]
+    fn test_distribute_skips_zero_weight_recipients() {
+        let shares = distribute_by_weight(1001, &[5000, 0, 3000, 2000]).unwrap();
+        assert_eq!(shares, vec![501, 0, 300, 200]);
+        assert_eq!(shares.iter().sum::<u64>(), 1001);
+
+        let shares = distribute_by_weight(10, &[0, 3333, 3333, 3334]).unwrap();
+        assert_eq!(shares[0], 0);
+        assert_eq!(shares.iter().sum::<u64>(), 10);
+    }
+    
+    #[test]
+    fn test_distribute_requires_nonzero_weight() {
+        assert!(distribute_by_weight(1000, &[0, 0]).is_err());
+        assert!(distribute_by_weight(1000, &[]).is_err());
+    }
+    
     #[test]
     fn test_treasury_permissions() {
         assert!(has_treasury_permission(TreasuryRole::Owner, TreasuryRole::Admin));
@@ -340,5 +519,28 @@ mod tests {
         assert!(!has_treasury_permission(TreasuryRole::Member, TreasuryRole::Admin));
         assert!(!has_treasury_permission(TreasuryRole::Viewer, TreasuryRole::Member));
     }
+
+    #[test]
+    fn test_batch_limits_differ_per_operation() {
+        assert!(max_batch_size_for_op(BatchOp::Create) < max_batch_size_for_op(BatchOp::Cancel));
+        assert!(max_batch_size_for_op(BatchOp::Cancel) < max_batch_size_for_op(BatchOp::Withdraw));
+    }
+
+    #[test]
+    fn test_batch_limit_enforced_per_operation() {
+        for op in [BatchOp::Create, BatchOp::Withdraw, BatchOp::Cancel] {
+            let limit = max_batch_size_for_op(op);
+            assert!(check_batch_size(op, 0).is_ok());
+            assert!(check_batch_size(op, limit).is_ok());
+            assert_eq!(
+                check_batch_size(op, limit + 1).unwrap_err(),
+                crate::errors::StreamFlowError::BatchOperationLimitExceeded.into()
+            );
+        }
+
+        // A withdraw-sized batch is too large for creation
+        let withdraw_limit = max_batch_size_for_op(BatchOp::Withdraw);
+        assert!(check_batch_size(BatchOp::Create, withdraw_limit).is_err());
+    }
 }
 ```
\ No newline at end of file
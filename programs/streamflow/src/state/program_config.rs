@@ -0,0 +1,173 @@
+use anchor_lang::prelude::*;
+use crate::bps::MAX_BPS;
+use crate::errors::{StreamFlowError, StreamFlowResult};
+
+/// Program-wide settings, held in a single PDA
+#[account]
+#[derive(Debug, Default)]
+pub struct ProgramConfig {
+    /// Authority allowed to change the settings
+    pub admin: Pubkey,
+    /// While set, every payout from an escrow and all stream creation are blocked
+    pub emergency_paused: bool,
+    /// Token whose holders vote on proposals; unset until governance is configured
+    pub governance_mint: Pubkey,
+    /// Votes a proposal needs, for and against combined, to be executed
+    pub quorum: u64,
+    /// Highest interest rate (basis points per year) a stream may accrue on
+    /// delayed claims; zero disables interest
+    pub max_interest_rate_bps: u16,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ProgramConfig {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // admin
+        1 + // emergency_paused
+        32 + // governance_mint
+        8 + // quorum
+        2 + // max_interest_rate_bps
+        1; // bump
+
+    /// Fail while the emergency pause is active
+    pub fn ensure_not_paused(&self) -> StreamFlowResult<()> {
+        if self.emergency_paused {
+            return Err(StreamFlowError::EmergencyPauseActivated);
+        }
+        Ok(())
+    }
+
+    /// Turn the emergency pause on or off; only the admin may do so
+    pub fn set_emergency_pause(&mut self, authority: &Pubkey, paused: bool) -> StreamFlowResult<()> {
+        if *authority != self.admin {
+            return Err(StreamFlowError::UnauthorizedAccess);
+        }
+        self.emergency_paused = paused;
+        Ok(())
+    }
+
+    /// Set the governance token and quorum; only the admin may do so
+    pub fn configure_governance(
+        &mut self,
+        authority: &Pubkey,
+        governance_mint: Pubkey,
+        quorum: u64,
+    ) -> StreamFlowResult<()> {
+        if *authority != self.admin {
+            return Err(StreamFlowError::UnauthorizedAccess);
+        }
+        if governance_mint == Pubkey::default() || quorum == 0 {
+            return Err(StreamFlowError::InvalidGovernanceConfiguration);
+        }
+        self.governance_mint = governance_mint;
+        self.quorum = quorum;
+        Ok(())
+    }
+
+    /// Set the highest interest rate streams may be created with; only the
+    /// admin may do so
+    pub fn set_max_interest_rate(&mut self, authority: &Pubkey, max_interest_rate_bps: u16) -> StreamFlowResult<()> {
+        if *authority != self.admin {
+            return Err(StreamFlowError::UnauthorizedAccess);
+        }
+        if max_interest_rate_bps > MAX_BPS {
+            return Err(StreamFlowError::InvalidBasisPoints);
+        }
+        self.max_interest_rate_bps = max_interest_rate_bps;
+        Ok(())
+    }
+
+    /// Fail unless a stream may accrue interest at `interest_rate_bps`
+    pub fn check_interest_rate(&self, interest_rate_bps: u16) -> StreamFlowResult<()> {
+        if interest_rate_bps > self.max_interest_rate_bps {
+            return Err(StreamFlowError::InterestRateExceedsMaximum);
+        }
+        Ok(())
+    }
+
+    /// Governance token and quorum, once configured
+    pub fn governance(&self) -> StreamFlowResult<(Pubkey, u64)> {
+        if self.governance_mint == Pubkey::default() {
+            return Err(StreamFlowError::InvalidGovernanceConfiguration);
+        }
+        Ok((self.governance_mint, self.quorum))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_blocks_until_lifted() {
+        let admin = Pubkey::new_unique();
+        let mut config = ProgramConfig { admin, ..Default::default() };
+        assert!(config.ensure_not_paused().is_ok());
+
+        config.set_emergency_pause(&admin, true).unwrap();
+        assert!(matches!(
+            config.ensure_not_paused(),
+            Err(StreamFlowError::EmergencyPauseActivated)
+        ));
+
+        config.set_emergency_pause(&admin, false).unwrap();
+        assert!(config.ensure_not_paused().is_ok());
+    }
+
+    #[test]
+    fn test_only_admin_can_pause() {
+        let mut config = ProgramConfig { admin: Pubkey::new_unique(), ..Default::default() };
+        assert!(matches!(
+            config.set_emergency_pause(&Pubkey::new_unique(), true),
+            Err(StreamFlowError::UnauthorizedAccess)
+        ));
+        assert!(!config.emergency_paused);
+    }
+
+    #[test]
+    fn test_governance_configuration() {
+        let admin = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut config = ProgramConfig { admin, ..Default::default() };
+        assert!(matches!(config.governance(), Err(StreamFlowError::InvalidGovernanceConfiguration)));
+
+        assert!(matches!(
+            config.configure_governance(&Pubkey::new_unique(), mint, 100),
+            Err(StreamFlowError::UnauthorizedAccess)
+        ));
+        assert!(matches!(
+            config.configure_governance(&admin, mint, 0),
+            Err(StreamFlowError::InvalidGovernanceConfiguration)
+        ));
+        config.configure_governance(&admin, mint, 100).unwrap();
+        assert_eq!(config.governance().unwrap(), (mint, 100));
+    }
+
+    #[test]
+    fn test_interest_rate_is_capped_by_admin() {
+        let admin = Pubkey::new_unique();
+        let mut config = ProgramConfig { admin, ..Default::default() };
+        // Interest is off until the admin allows it
+        assert!(config.check_interest_rate(0).is_ok());
+        assert!(matches!(
+            config.check_interest_rate(1),
+            Err(StreamFlowError::InterestRateExceedsMaximum)
+        ));
+
+        assert!(matches!(
+            config.set_max_interest_rate(&Pubkey::new_unique(), 500),
+            Err(StreamFlowError::UnauthorizedAccess)
+        ));
+        assert!(matches!(
+            config.set_max_interest_rate(&admin, MAX_BPS + 1),
+            Err(StreamFlowError::InvalidBasisPoints)
+        ));
+        config.set_max_interest_rate(&admin, 500).unwrap();
+        assert!(config.check_interest_rate(500).is_ok());
+        assert!(matches!(
+            config.check_interest_rate(u16::MAX),
+            Err(StreamFlowError::InterestRateExceedsMaximum)
+        ));
+    }
+}
@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use crate::errors::{StreamFlowError, StreamFlowResult};
+
+/// Ceiling on the aggregate tokens-per-second a sender streams to one recipient
+#[account]
+#[derive(Debug)]
+pub struct RecipientRateCap {
+    /// Sender whose streams count against the cap
+    pub authority: Pubkey,
+    /// Recipient the cap applies to
+    pub recipient: Pubkey,
+    /// Maximum aggregate rate (tokens per second)
+    pub max_rate: u64,
+    /// Aggregate rate of the sender's live streams to the recipient
+    pub current_rate: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RecipientRateCap {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // authority
+        32 + // recipient
+        8 + // max_rate
+        8 + // current_rate
+        1; // bump
+
+    /// Count a new stream's rate against the cap
+    pub fn reserve(&mut self, rate: u64) -> StreamFlowResult<()> {
+        let new_rate = self
+            .current_rate
+            .checked_add(rate)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        if new_rate > self.max_rate {
+            return Err(StreamFlowError::CapacityLimitReached);
+        }
+        self.current_rate = new_rate;
+        Ok(())
+    }
+
+    /// Release a finished stream's rate
+    pub fn release(&mut self, rate: u64) {
+        self.current_rate = self.current_rate.saturating_sub(rate);
+    }
+}
+
+/// Tokens per second of a stream, rounded up so short streams still count
+pub fn stream_rate_per_second(amount: u64, duration: i64) -> u64 {
+    if duration <= 0 {
+        return amount;
+    }
+    let duration = duration as u64;
+    amount / duration + u64::from(amount % duration != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cap(max_rate: u64) -> RecipientRateCap {
+        RecipientRateCap {
+            authority: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            max_rate,
+            current_rate: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_streams_up_to_and_beyond_cap() {
+        let mut cap = cap(100);
+        // Three streams of 40, 40 and 20 tokens/second fill the cap exactly
+        cap.reserve(stream_rate_per_second(40_000, 1_000)).unwrap();
+        cap.reserve(stream_rate_per_second(80_000, 2_000)).unwrap();
+        cap.reserve(stream_rate_per_second(20_000, 1_000)).unwrap();
+        assert_eq!(cap.current_rate, 100);
+
+        // Any further stream is rejected and leaves the aggregate untouched
+        assert!(matches!(cap.reserve(1), Err(StreamFlowError::CapacityLimitReached)));
+        assert_eq!(cap.current_rate, 100);
+
+        // Cancelling or completing a stream frees its share
+        cap.release(40);
+        cap.reserve(stream_rate_per_second(30_000, 1_000)).unwrap();
+        assert_eq!(cap.current_rate, 90);
+    }
+
+    #[test]
+    fn test_rate_rounds_up() {
+        assert_eq!(stream_rate_per_second(1, 1_000), 1);
+        assert_eq!(stream_rate_per_second(1_000, 1_000), 1);
+        assert_eq!(stream_rate_per_second(1_001, 1_000), 2);
+        assert_eq!(stream_rate_per_second(500, 0), 500);
+    }
+}
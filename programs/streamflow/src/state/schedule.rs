@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+
+/// Maximum number of unlock points in a custom schedule
+pub const MAX_UNLOCK_POINTS: usize = 32;
+
+/// Piecewise unlock schedule for `StreamType::Custom` streams
+#[account]
+#[derive(Debug)]
+pub struct CustomSchedule {
+    /// The stream this schedule belongs to
+    pub stream: Pubkey,
+    /// Unlock points ordered by timestamp
+    pub points: Vec<UnlockPoint>,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct UnlockPoint {
+    /// When the amount unlocks (Unix timestamp)
+    pub timestamp: i64,
+    /// Amount unlocked at this point
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ScheduleChangeKind {
+    /// Unlock point only present in the proposed schedule
+    Added,
+    /// Unlock point only present in the current schedule
+    Removed,
+    /// Unlock point present in both with a different amount
+    Changed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ScheduleChange {
+    pub kind: ScheduleChangeKind,
+    pub timestamp: i64,
+    pub old_amount: u64,
+    pub new_amount: u64,
+}
+
+impl CustomSchedule {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // stream
+        4 + MAX_UNLOCK_POINTS * (8 + 8) + // points
+        1; // bump
+
+    /// Compare the stored schedule against a proposed one, keyed by timestamp
+    pub fn diff(&self, proposed: &[UnlockPoint]) -> Vec<ScheduleChange> {
+        let mut changes = Vec::new();
+
+        for current in &self.points {
+            match proposed.iter().find(|p| p.timestamp == current.timestamp) {
+                Some(p) if p.amount != current.amount => changes.push(ScheduleChange {
+                    kind: ScheduleChangeKind::Changed,
+                    timestamp: current.timestamp,
+                    old_amount: current.amount,
+                    new_amount: p.amount,
+                }),
+                Some(_) => {}
+                None => changes.push(ScheduleChange {
+                    kind: ScheduleChangeKind::Removed,
+                    timestamp: current.timestamp,
+                    old_amount: current.amount,
+                    new_amount: 0,
+                }),
+            }
+        }
+
+        for p in proposed {
+            if !self.points.iter().any(|current| current.timestamp == p.timestamp) {
+                changes.push(ScheduleChange {
+                    kind: ScheduleChangeKind::Added,
+                    timestamp: p.timestamp,
+                    old_amount: 0,
+                    new_amount: p.amount,
+                });
+            }
+        }
+
+        changes.sort_by_key(|change| change.timestamp);
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(timestamp: i64, amount: u64) -> UnlockPoint {
+        UnlockPoint { timestamp, amount }
+    }
+
+    fn schedule(points: Vec<UnlockPoint>) -> CustomSchedule {
+        CustomSchedule {
+            stream: Pubkey::new_unique(),
+            points,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_diff_identical_is_empty() {
+        let current = schedule(vec![point(100, 10), point(200, 20)]);
+        assert!(current.diff(&[point(100, 10), point(200, 20)]).is_empty());
+    }
+
+    #[test]
+    fn test_diff_additions_removals_and_changes() {
+        let current = schedule(vec![point(100, 10), point(200, 20), point(300, 30)]);
+        let proposed = [point(100, 10), point(200, 25), point(400, 40)];
+
+        assert_eq!(
+            current.diff(&proposed),
+            vec![
+                ScheduleChange { kind: ScheduleChangeKind::Changed, timestamp: 200, old_amount: 20, new_amount: 25 },
+                ScheduleChange { kind: ScheduleChangeKind::Removed, timestamp: 300, old_amount: 30, new_amount: 0 },
+                ScheduleChange { kind: ScheduleChangeKind::Added, timestamp: 400, old_amount: 0, new_amount: 40 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use anchor_lang::prelude::*;
+use super::constants::MAX_RECIPIENTS;
+use super::utils::distribute_by_weight;
+use super::{FeeConfig, StateError};
+use crate::calc;
+use crate::errors::{StreamError, StreamFlowError, StreamFlowResult};
+
+/// Weights of a split stream are basis points of the vested amount
+pub const SPLIT_TOTAL_WEIGHT_BPS: u32 = 10_000;
+
+/// One recipient of a split stream and what they have claimed so far
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct SplitShare {
+    pub recipient: Pubkey,
+    /// Share of the vested amount (basis points)
+    pub weight_bps: u16,
+    pub withdrawn_amount: u64,
+    /// Set once the recipient accepts a split created with `require_acceptance`
+    pub accepted: bool,
+    /// When this recipient last withdrew, for the withdrawal cooldown
+    pub last_withdrawn_at: i64,
+}
+
+impl SplitShare {
+    pub const LEN: usize = 32 + 2 + 8 + 1 + 8;
+}
+
+/// Linear stream whose vested amount is shared between several recipients
+#[account]
+#[derive(Debug)]
+pub struct SplitStream {
+    pub sender: Pubkey,
+    pub mint: Pubkey,
+    /// Escrow token account holding the deposit
+    pub escrow_tokens: Pubkey,
+    /// Identifier distinguishing the sender's split streams in the PDA seeds
+    pub split_id: u64,
+    pub deposited_amount: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub recipients: Vec<SplitShare>,
+    /// Platform fee on each withdrawal (basis points)
+    pub fee_percentage: u16,
+    pub fee_recipient: Option<Pubkey>,
+    /// Partner fee on each withdrawal (basis points)
+    pub partner_fee_percentage: u16,
+    pub partner_fee_recipient: Option<Pubkey>,
+    /// Every recipient must accept the split before withdrawing
+    pub require_acceptance: bool,
+    /// Minimum seconds between a recipient's withdrawals
+    pub min_withdrawal_interval: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Bump seed for the escrow token account PDA
+    pub escrow_bump: u8,
+}
+
+impl SplitStream {
+    /// Account size for a split between `recipient_count` recipients
+    pub fn space(recipient_count: usize) -> usize {
+        8 + // discriminator
+        32 + // sender
+        32 + // mint
+        32 + // escrow_tokens
+        8 + // split_id
+        8 + // deposited_amount
+        8 + // start_time
+        8 + // end_time
+        4 + SplitShare::LEN * recipient_count + // recipients
+        2 + // fee_percentage
+        33 + // fee_recipient
+        2 + // partner_fee_percentage
+        33 + // partner_fee_recipient
+        1 + // require_acceptance
+        8 + // min_withdrawal_interval
+        1 + // bump
+        1 // escrow_bump
+    }
+
+    /// Check a recipient list: 1..=MAX_RECIPIENTS distinct recipients whose
+    /// weights sum to exactly 10000 bps
+    pub fn validate_recipients(recipients: &[(Pubkey, u16)]) -> Result<()> {
+        require!(!recipients.is_empty(), StateError::NoWeightedRecipients);
+        require!(recipients.len() <= MAX_RECIPIENTS, StateError::TooManyRecipients);
+
+        let total_weight: u32 = recipients.iter().map(|(_, weight)| *weight as u32).sum();
+        require!(total_weight == SPLIT_TOTAL_WEIGHT_BPS, StateError::InvalidSplitWeights);
+
+        for (i, (recipient, _)) in recipients.iter().enumerate() {
+            require!(
+                recipients[..i].iter().all(|(other, _)| other != recipient),
+                StreamError::InvalidRecipient
+            );
+        }
+        Ok(())
+    }
+
+    /// Amount of the deposit vested across all recipients at `current_time`
+    pub fn vested_amount(&self, current_time: i64) -> Result<u64> {
+        calc::linear_amount(self.deposited_amount, self.start_time, self.end_time, current_time)
+            .map_err(|e| StreamFlowError::from(e).into())
+    }
+
+    /// Each recipient's share of the vested amount, in recipient order
+    pub fn vested_shares(&self, current_time: i64) -> Result<Vec<u64>> {
+        let weights: Vec<u16> = self.recipients.iter().map(|r| r.weight_bps).collect();
+        distribute_by_weight(self.vested_amount(current_time)?, &weights)
+    }
+
+    /// Index of `recipient` in the split
+    pub fn position(&self, recipient: &Pubkey) -> Result<usize> {
+        self.recipients
+            .iter()
+            .position(|r| r.recipient == *recipient)
+            .ok_or_else(|| StreamError::UnauthorizedRecipient.into())
+    }
+
+    /// Validate and store the fee routing, checked against every recipient
+    pub fn apply_fee_config(&mut self, config: &FeeConfig) -> Result<()> {
+        for share in &self.recipients {
+            config.validate(&self.sender, &share.recipient)?;
+        }
+        self.fee_percentage = config.fee_percentage;
+        self.fee_recipient = config.fee_recipient;
+        self.partner_fee_percentage = config.partner_fee_percentage;
+        self.partner_fee_recipient = config.partner_fee_recipient;
+        Ok(())
+    }
+
+    /// Fail unless the recipient at `index` may withdraw without accepting first
+    pub fn ensure_accepted(&self, index: usize) -> Result<()> {
+        require!(
+            !self.require_acceptance || self.recipients[index].accepted,
+            StreamError::StreamNotAccepted
+        );
+        Ok(())
+    }
+
+    /// Record `recipient`'s acceptance of the split
+    pub fn accept(&mut self, recipient: &Pubkey) -> Result<()> {
+        let index = self.position(recipient)?;
+        let share = &mut self.recipients[index];
+        require!(!share.accepted, StreamError::StreamAlreadyAccepted);
+        share.accepted = true;
+        Ok(())
+    }
+
+    /// Reject a withdrawal made within `min_withdrawal_interval` of the same
+    /// recipient's last one
+    pub fn check_withdrawal_cooldown(&self, index: usize, current_time: i64) -> StreamFlowResult<()> {
+        let since_last = current_time.saturating_sub(self.recipients[index].last_withdrawn_at);
+        if since_last < 0 || (since_last as u64) < self.min_withdrawal_interval {
+            return Err(StreamFlowError::RateLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// Split a gross withdrawal into (net, platform_fee, partner_fee), with the
+    /// net derived by subtraction as for a single stream
+    pub fn split_withdrawal(&self, gross: u64) -> StreamFlowResult<(u64, u64, u64)> {
+        let fee = |bps: u16| -> StreamFlowResult<u64> {
+            Ok((gross as u128)
+                .checked_mul(bps as u128)
+                .ok_or(StreamFlowError::ArithmeticOverflow)?
+                .checked_div(SPLIT_TOTAL_WEIGHT_BPS as u128)
+                .ok_or(StreamFlowError::ArithmeticOverflow)? as u64)
+        };
+        let platform_fee = fee(self.fee_percentage)?;
+        let partner_fee = fee(self.partner_fee_percentage)?;
+        let net = gross
+            .checked_sub(platform_fee)
+            .and_then(|net| net.checked_sub(partner_fee))
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        Ok((net, platform_fee, partner_fee))
+    }
+
+    /// Amount `recipient` can withdraw at `current_time`
+    pub fn withdrawable_for(&self, recipient: &Pubkey, current_time: i64) -> Result<u64> {
+        let index = self.position(recipient)?;
+        let vested = self.vested_shares(current_time)?[index];
+        Ok(vested.saturating_sub(self.recipients[index].withdrawn_amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(weights: &[u16]) -> SplitStream {
+        SplitStream {
+            sender: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            escrow_tokens: Pubkey::new_unique(),
+            split_id: 0,
+            deposited_amount: 10_000,
+            start_time: 1_000,
+            end_time: 2_000,
+            recipients: weights
+                .iter()
+                .map(|weight| SplitShare {
+                    recipient: Pubkey::new_unique(),
+                    weight_bps: *weight,
+                    withdrawn_amount: 0,
+                    accepted: false,
+                    last_withdrawn_at: 0,
+                })
+                .collect(),
+            fee_percentage: 0,
+            fee_recipient: None,
+            partner_fee_percentage: 0,
+            partner_fee_recipient: None,
+            require_acceptance: false,
+            min_withdrawal_interval: 0,
+            bump: 255,
+            escrow_bump: 255,
+        }
+    }
+
+    fn as_args(split: &SplitStream) -> Vec<(Pubkey, u16)> {
+        split.recipients.iter().map(|r| (r.recipient, r.weight_bps)).collect()
+    }
+
+    #[test]
+    fn test_three_way_split_withdrawable_amounts() {
+        let mut split = split(&[5_000, 3_000, 2_000]);
+        SplitStream::validate_recipients(&as_args(&split)).unwrap();
+        let [a, b, c] = [0, 1, 2].map(|i| split.recipients[i].recipient);
+
+        // Halfway through, 5000 tokens have vested
+        assert_eq!(split.withdrawable_for(&a, 1_500).unwrap(), 2_500);
+        assert_eq!(split.withdrawable_for(&b, 1_500).unwrap(), 1_500);
+        assert_eq!(split.withdrawable_for(&c, 1_500).unwrap(), 1_000);
+
+        // One recipient withdrawing does not change the others' shares
+        split.recipients[0].withdrawn_amount = 2_500;
+        assert_eq!(split.withdrawable_for(&a, 1_500).unwrap(), 0);
+        assert_eq!(split.withdrawable_for(&b, 1_500).unwrap(), 1_500);
+
+        // At the end the whole deposit is shared out
+        assert_eq!(split.withdrawable_for(&a, 2_000).unwrap(), 2_500);
+        assert_eq!(split.withdrawable_for(&b, 2_000).unwrap(), 3_000);
+        assert_eq!(split.withdrawable_for(&c, 2_000).unwrap(), 2_000);
+    }
+
+    #[test]
+    fn test_weights_must_sum_to_full_split() {
+        let short = split(&[5_000, 3_000, 1_000]);
+        assert_eq!(
+            SplitStream::validate_recipients(&as_args(&short)).unwrap_err(),
+            StateError::InvalidSplitWeights.into()
+        );
+
+        let over = split(&[5_000, 3_000, 3_000]);
+        assert_eq!(
+            SplitStream::validate_recipients(&as_args(&over)).unwrap_err(),
+            StateError::InvalidSplitWeights.into()
+        );
+    }
+
+    #[test]
+    fn test_recipient_limit() {
+        let mut recipients: Vec<(Pubkey, u16)> =
+            (0..MAX_RECIPIENTS).map(|_| (Pubkey::new_unique(), 100)).collect();
+        SplitStream::validate_recipients(&recipients).unwrap();
+
+        recipients.push((Pubkey::new_unique(), 0));
+        assert_eq!(
+            SplitStream::validate_recipients(&recipients).unwrap_err(),
+            StateError::TooManyRecipients.into()
+        );
+    }
+
+    #[test]
+    fn test_duplicate_recipient_rejected() {
+        let recipient = Pubkey::new_unique();
+        assert_eq!(
+            SplitStream::validate_recipients(&[(recipient, 5_000), (recipient, 5_000)]).unwrap_err(),
+            StreamError::InvalidRecipient.into()
+        );
+    }
+
+    #[test]
+    fn test_unknown_recipient_cannot_withdraw() {
+        let split = split(&[10_000]);
+        assert_eq!(
+            split.withdrawable_for(&Pubkey::new_unique(), 1_500).unwrap_err(),
+            StreamError::UnauthorizedRecipient.into()
+        );
+    }
+
+    #[test]
+    fn test_withdrawal_gated_until_accepted() {
+        let mut split = split(&[5_000, 5_000]);
+        split.require_acceptance = true;
+        let a = split.recipients[0].recipient;
+        assert_eq!(split.ensure_accepted(0).unwrap_err(), StreamError::StreamNotAccepted.into());
+
+        split.accept(&a).unwrap();
+        assert!(split.ensure_accepted(0).is_ok());
+        // Each recipient accepts for themselves
+        assert_eq!(split.ensure_accepted(1).unwrap_err(), StreamError::StreamNotAccepted.into());
+        assert_eq!(split.accept(&a).unwrap_err(), StreamError::StreamAlreadyAccepted.into());
+    }
+
+    #[test]
+    fn test_withdrawal_cooldown_is_per_recipient() {
+        let mut split = split(&[5_000, 5_000]);
+        split.min_withdrawal_interval = 100;
+        split.recipients[0].last_withdrawn_at = 1_500;
+        assert!(matches!(
+            split.check_withdrawal_cooldown(0, 1_550),
+            Err(StreamFlowError::RateLimitExceeded)
+        ));
+        assert!(split.check_withdrawal_cooldown(0, 1_600).is_ok());
+        assert!(split.check_withdrawal_cooldown(1, 1_550).is_ok());
+    }
+
+    #[test]
+    fn test_fees_on_split_withdrawal() {
+        let mut split = split(&[10_000]);
+        let config = FeeConfig {
+            fee_percentage: 50,
+            fee_recipient: Some(Pubkey::new_unique()),
+            partner_fee_percentage: 100,
+            partner_fee_recipient: Some(Pubkey::new_unique()),
+            allow_self_routing: false,
+        };
+        split.apply_fee_config(&config).unwrap();
+        assert_eq!(split.split_withdrawal(10_000).unwrap(), (9_850, 50, 100));
+
+        // A platform fee routed to one of the recipients is rejected
+        let looped = FeeConfig { fee_recipient: Some(split.recipients[0].recipient), ..config };
+        assert_eq!(
+            split.apply_fee_config(&looped).unwrap_err(),
+            StreamFlowError::InvalidFeeConfiguration.into()
+        );
+    }
+
+    #[test]
+    fn test_split_fees_are_capped() {
+        let mut split = split(&[10_000]);
+        let config = FeeConfig {
+            fee_percentage: 300,
+            fee_recipient: Some(Pubkey::new_unique()),
+            partner_fee_percentage: 201,
+            partner_fee_recipient: Some(Pubkey::new_unique()),
+            allow_self_routing: false,
+        };
+        assert_eq!(
+            split.apply_fee_config(&config).unwrap_err(),
+            StateError::PlatformFeeExceedsMaximum.into()
+        );
+        assert_eq!(split.fee_percentage, 0);
+    }
+}
@@ -1,6 +1,18 @@
 ```rust
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::associated_token::get_associated_token_address;
 use anchor_spl::token::Mint;
+use super::constants::{
+    MAX_CANCEL_APPROVERS, MAX_PLATFORM_FEE_BPS, MAX_RECIPIENT_HISTORY, MIN_FEE_CHANGE_NOTICE,
+    SECONDS_PER_YEAR,
+};
+use super::utils::is_valid_status_transition;
+use super::{CancelApproval, RecipientRateCap, StateError, StateValidation, StreamStatus};
+use crate::bps::{percent_from_bps, MAX_BPS};
+use crate::calc;
+use crate::errors::{StreamError, StreamFlowError, StreamFlowResult};
+use crate::oracle::Price;
 
 #[account]
 #[derive(Debug)]
@@ -23,6 +35,10 @@ pub struct Stream {
     pub end_time: i64,
     /// The last withdrawal time (Unix timestamp)
     pub last_withdrawn_at: i64,
+    /// The creation time of the stream (Unix timestamp), mirrored in `metadata.created_at`
+    pub created_at: i64,
+    /// When the stream was cancelled (Unix timestamp), if it has been
+    pub cancelled_at: Option<i64>,
     /// The rate at which tokens are released per second
     pub rate_amount: u64,
     /// The interval at which tokens are released (in seconds)
@@ -31,12 +47,24 @@ pub struct Stream {
     pub cancelable_by_sender: bool,
     /// Whether the stream can be cancelled by the recipient
     pub cancelable_by_recipient: bool,
+    /// Whether the sender can reassign the stream to a new recipient
+    pub transferable_by_sender: bool,
+    /// Whether the recipient can reassign the stream to a new recipient
+    pub transferable_by_recipient: bool,
     /// Whether automatic withdrawal is enabled
     pub automatic_withdrawal: bool,
+    /// Minimum seconds between automatic withdrawals
+    pub withdrawal_frequency: u64,
+    /// Minimum seconds between manual withdrawals; zero for no limit
+    pub min_withdrawal_interval: u64,
     /// Whether the stream allows topup
     pub can_topup: bool,
     /// Whether the stream allows update rate
     pub can_update_rate: bool,
+    /// Reject rate updates once the stream has started
+    pub lock_rate_after_start: bool,
+    /// Whether a withdrawal may claim less than everything withdrawable
+    pub allow_partial_withdraw: bool,
     /// The current status of the stream
     pub status: StreamStatus,
     /// The type of stream (linear, cliff, etc.)
@@ -53,30 +81,82 @@ pub struct Stream {
     pub partner_fee_percentage: u16,
     /// The partner fee recipient
     pub partner_fee_recipient: Option<Pubkey>,
+    /// Annual interest (basis points) paid on vested-but-unclaimed tokens; 0 disables
+    pub interest_rate_bps: u16,
+    /// Cumulative withdrawals that must be exceeded before partner fees apply
+    pub partner_fee_activation_amount: u64,
+    /// Latched once `withdrawn_amount` exceeds `partner_fee_activation_amount`
+    pub partner_fee_active: bool,
+    /// Announced platform fee (bps) and the timestamp it takes effect
+    pub pending_fee: Option<(u16, i64)>,
     /// Stream name/identifier
     pub name: [u8; 64],
     /// Additional metadata
     pub metadata: StreamMetadata,
+    /// Replacement destination registered by the recipient (defaults to their ATA)
+    pub recipient_token_account: Option<Pubkey>,
+    /// Destination for automatic withdrawals, if different from manual ones
+    pub auto_withdraw_destination: Option<Pubkey>,
+    /// Token account the recipient has chosen to receive withdrawals, such as
+    /// a cold wallet's; unlike `recipient_token_account` it may be owned by anyone
+    pub withdraw_destination: Option<Pubkey>,
+    /// Whether metadata changes require both sender and recipient signatures
+    pub metadata_locked: bool,
+    /// Treasury allowed to receive reclaimed rent on close, besides the sender
+    pub rent_treasury: Option<Pubkey>,
+    /// Total number of amendments ever applied to the stream
+    pub amendment_count: u32,
+    /// Most recent amendments, as a ring buffer indexed by `amendment_count`
+    pub journal: [AmendmentEntry; MAX_JOURNAL_ENTRIES],
+    /// Withdrawals must carry a nonzero invoice `receipt_hash`
+    pub receipt_required: bool,
+    /// Vested tokens held in escrow for the recipient after a cancel could not deliver them
+    pub parked_amount: u64,
+    /// Rate counted against the sender's `RecipientRateCap` until the stream ends
+    pub capped_rate: u64,
+    /// Seconds the stream has spent paused over completed pauses
+    pub total_paused_duration: i64,
+    /// Start of the current pause, if the stream is paused
+    pub paused_at: Option<i64>,
+    /// Seconds a pause may last before anyone can resume the stream; zero for no limit
+    pub max_pause_duration: u64,
+    /// Highest nonce consumed by a relayed withdrawal authorization
+    pub authorization_nonce: u64,
+    /// Reentrancy guard, set while withdraw/cancel are making CPIs
+    pub processing: bool,
+    /// Template the stream was created from; counted in its `active_stream_count` until closed
+    pub template: Option<Pubkey>,
+    /// Withdrawals are blocked until the recipient accepts the stream
+    pub require_acceptance: bool,
+    /// Whether the recipient has accepted the stream
+    pub accepted: bool,
+    /// The schedule only fixes durations; it is moved to begin at acceptance
+    pub start_on_acceptance: bool,
+    /// Keys whose approvals are collected before the stream can be cancelled
+    pub cancel_approvers: Vec<Pubkey>,
+    /// Approvals needed to cancel; zero when no multisig is configured
+    pub cancel_threshold: u8,
+    /// Authority that may reclaim unvested tokens regardless of the cancel flags
+    pub clawback_authority: Option<Pubkey>,
+    /// Release rate in micro-USD per second, paid in tokens at the oracle
+    /// price; `None` for a stream that follows its token schedule
+    pub usd_rate_per_second: Option<u64>,
+    /// Pyth price account quoting the stream's token in USD
+    pub price_feed: Option<Pubkey>,
+    /// Oldest price, in seconds, a pegged withdrawal accepts
+    pub max_price_age: u64,
+    /// Previous recipients and when the stream was transferred away from each
+    pub recipient_history: Vec<(Pubkey, i64)>,
+    /// Per-(sender, recipient) nonce used in the stream PDA seeds
+    pub nonce: u64,
     /// Bump seed for PDA
     pub bump: u8,
+    /// Bump seed for the escrow token account PDA
+    pub escrow_bump: u8,
     /// Reserved space for future upgrades
     pub _reserved: [u8; 128],
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
-pub enum StreamStatus {
-    /// Stream is scheduled but not yet started
-    Scheduled,
-    /// Stream is currently active and streaming
-    Streaming,
-    /// Stream has been paused
-    Paused,
-    /// Stream has been cancelled
-    Cancelled,
-    /// Stream has completed successfully
-    Completed,
-}
-
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum StreamType {
     /// Linear vesting over time
@@ -87,6 +167,123 @@ pub enum StreamType {
     Step,
     /// Custom vesting schedule
     Custom,
+    /// Cliff lump followed by step-wise releases of the remainder
+    CliffStep,
+    /// Entire deposit claimable at `start_time` (airdrops)
+    Instant,
+}
+
+/// Number of amendments retained on the stream; older ones remain in event logs
+pub const MAX_JOURNAL_ENTRIES: usize = 8;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum AmendmentKind {
+    /// Placeholder for unused journal slots
+    #[default]
+    None,
+    /// Start/end times shifted
+    Reschedule,
+    /// Vesting brought forward
+    Accelerate,
+    /// Platform or partner fee changed
+    FeeChange,
+    /// Step rate or interval changed
+    RateChange,
+    /// Deposit increased or decreased
+    DepositChange,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct AmendmentEntry {
+    /// What was changed
+    pub kind: AmendmentKind,
+    /// SHA-256 of the value before the change
+    pub old_value_hash: [u8; 32],
+    /// SHA-256 of the value after the change
+    pub new_value_hash: [u8; 32],
+    /// When the change was applied (Unix timestamp)
+    pub timestamp: i64,
+    /// Who applied the change
+    pub authority: Pubkey,
+}
+
+impl AmendmentEntry {
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 32;
+}
+
+#[event]
+pub struct StreamAmended {
+    pub stream: Pubkey,
+    pub sequence: u32,
+    pub entry: AmendmentEntry,
+}
+
+/// Emitted once, when the last deposited token is withdrawn
+#[event]
+pub struct StreamCompleted {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub total_withdrawn: u64,
+    pub completed_at: i64,
+}
+
+/// Fee routing supplied at stream creation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct FeeConfig {
+    pub fee_percentage: u16,
+    pub fee_recipient: Option<Pubkey>,
+    pub partner_fee_percentage: u16,
+    pub partner_fee_recipient: Option<Pubkey>,
+    /// Permit fees to be routed back to the stream's own recipient or sender
+    pub allow_self_routing: bool,
+}
+
+impl FeeConfig {
+    /// Reject fees with nowhere to go, fees above the platform cap and fee
+    /// routing that loops back into the stream's own parties.
+    ///
+    /// A platform fee paid to the recipient lands in the same account as the
+    /// net withdrawal, and a partner fee paid to the sender claws back vested
+    /// tokens; both are almost always misconfigurations.
+    pub fn validate(&self, sender: &Pubkey, recipient: &Pubkey) -> Result<()> {
+        for (label, bps, fee_recipient) in [
+            ("Platform", self.fee_percentage, &self.fee_recipient),
+            ("Partner", self.partner_fee_percentage, &self.partner_fee_recipient),
+        ] {
+            if bps > MAX_BPS {
+                msg!("{} fee of {} bps ({}%) exceeds 100%", label, bps, percent_from_bps(bps));
+                return err!(StreamFlowError::InvalidFeeConfiguration);
+            }
+            if bps > 0 && fee_recipient.is_none() {
+                msg!("{} fee of {} bps ({}%) has no fee recipient", label, bps, percent_from_bps(bps));
+                return err!(StreamFlowError::InvalidFeeConfiguration);
+            }
+        }
+        self.validate_cap()?;
+        if self.allow_self_routing {
+            return Ok(());
+        }
+        require!(
+            self.fee_recipient.as_ref() != Some(recipient),
+            StreamFlowError::InvalidFeeConfiguration
+        );
+        require!(
+            self.partner_fee_recipient.as_ref() != Some(sender),
+            StreamFlowError::InvalidFeeConfiguration
+        );
+        Ok(())
+    }
+
+    /// Platform and partner fees together may take at most
+    /// `MAX_PLATFORM_FEE_BPS` of each withdrawal
+    pub fn validate_cap(&self) -> Result<()> {
+        let total_bps = u32::from(self.fee_percentage) + u32::from(self.partner_fee_percentage);
+        require!(
+            total_bps <= u32::from(MAX_PLATFORM_FEE_BPS),
+            StateError::PlatformFeeExceedsMaximum
+        );
+        Ok(())
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
@@ -114,13 +311,21 @@ impl Stream {
         8 + // start_time
         8 + // end_time
         8 + // last_withdrawn_at
+        8 + // created_at
+        9 + // cancelled_at (Option<i64>)
         8 + // rate_amount
         8 + // rate_interval_in_seconds
         1 + // cancelable_by_sender
         1 + // cancelable_by_recipient
+        1 + // transferable_by_sender
+        1 + // transferable_by_recipient
         1 + // automatic_withdrawal
+        8 + // withdrawal_frequency
+        8 + // min_withdrawal_interval
         1 + // can_topup
         1 + // can_update_rate
+        1 + // lock_rate_after_start
+        1 + // allow_partial_withdraw
         1 + // status (enum)
         1 + // stream_type (enum)
         8 + // cliff_amount
@@ -129,13 +334,68 @@ impl Stream {
         33 + // fee_recipient (Option<Pubkey>)
         2 + // partner_fee_percentage
         33 + // partner_fee_recipient (Option<Pubkey>)
+        2 + // interest_rate_bps
+        8 + // partner_fee_activation_amount
+        1 + // partner_fee_active
+        11 + // pending_fee (Option<(u16, i64)>)
         64 + // name
         (128 + 32 + 32 + 8 + 8) + // metadata
+        33 + // recipient_token_account (Option<Pubkey>)
+        33 + // auto_withdraw_destination (Option<Pubkey>)
+        33 + // withdraw_destination (Option<Pubkey>)
+        1 + // metadata_locked
+        33 + // rent_treasury (Option<Pubkey>)
+        4 + // amendment_count
+        AmendmentEntry::LEN * MAX_JOURNAL_ENTRIES + // journal
+        1 + // receipt_required
+        8 + // parked_amount
+        8 + // capped_rate
+        8 + // total_paused_duration
+        9 + // paused_at (Option<i64>)
+        8 + // max_pause_duration
+        8 + // authorization_nonce
+        1 + // processing
+        33 + // template (Option<Pubkey>)
+        1 + // require_acceptance
+        1 + // accepted
+        1 + // start_on_acceptance
+        4 + MAX_CANCEL_APPROVERS * 32 + // cancel_approvers
+        1 + // cancel_threshold
+        33 + // clawback_authority (Option<Pubkey>)
+        9 + // usd_rate_per_second (Option<u64>)
+        33 + // price_feed (Option<Pubkey>)
+        8 + // max_price_age
+        4 + MAX_RECIPIENT_HISTORY * (32 + 8) + // recipient_history
+        8 + // nonce
         1 + // bump
+        1 + // escrow_bump
         128; // reserved
 
+    /// Record the creation time on the stream and its metadata from one clock reading
+    pub fn set_created_at(&mut self, timestamp: i64) {
+        self.created_at = timestamp;
+        self.metadata.created_at = timestamp;
+        self.metadata.updated_at = timestamp;
+    }
+
+    /// Record activity at `timestamp` as the stream's last update. Called by
+    /// every instruction that mutates the stream.
+    pub fn touch(&mut self, timestamp: i64) {
+        self.metadata.updated_at = self.metadata.updated_at.max(timestamp);
+    }
+
+    /// Validate and store the fee routing for this stream
+    pub fn apply_fee_config(&mut self, config: &FeeConfig) -> Result<()> {
+        config.validate(&self.sender, &self.recipient)?;
+        self.fee_percentage = config.fee_percentage;
+        self.fee_recipient = config.fee_recipient;
+        self.partner_fee_percentage = config.partner_fee_percentage;
+        self.partner_fee_recipient = config.partner_fee_recipient;
+        Ok(())
+    }
+
     /// Calculate the amount of tokens that can be withdrawn at the current time
-    pub fn withdrawable_amount(&self, current_time: i64) -> Result<u64> {
+    pub fn withdrawable_amount(&self, current_time: i64) -> StreamFlowResult<u64> {
         if self.status != StreamStatus::Streaming {
             return Ok(0);
         }
@@ -144,111 +404,666 @@ impl Stream {
         Ok(total_streamed.saturating_sub(self.withdrawn_amount))
     }
 
+    /// Vested but not yet withdrawn amount at `current_time` according to the
+    /// schedule alone. Unlike `withdrawable_amount` this ignores `status`, so
+    /// it can be used before a scheduled stream has been marked as streaming.
+    pub fn calculate_withdrawable_amount(&self, current_time: i64) -> StreamFlowResult<u64> {
+        let total_streamed = self.calculate_streamed_amount(current_time)?;
+        Ok(total_streamed.saturating_sub(self.withdrawn_amount))
+    }
+
+    /// Move a scheduled stream to `Streaming` once its start time has passed.
+    /// Returns whether the status changed.
+    pub fn activate_if_started(&mut self, current_time: i64) -> bool {
+        if self.status == StreamStatus::Scheduled && current_time >= self.start_time {
+            self.status = StreamStatus::Streaming;
+            return true;
+        }
+        false
+    }
+
+    /// Store `name` in the fixed-size name field, zero padded
+    pub fn set_name(&mut self, name: &str) -> StreamFlowResult<()> {
+        let bytes = name.as_bytes();
+        if bytes.len() > self.name.len() {
+            return Err(StreamFlowError::StreamMetadataTooLarge);
+        }
+        self.name = [0; 64];
+        self.name[..bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+
     /// Calculate the total amount streamed up to a given time
-    pub fn calculate_streamed_amount(&self, current_time: i64) -> Result<u64> {
+    pub fn calculate_streamed_amount(&self, current_time: i64) -> StreamFlowResult<u64> {
+        // Negative clock readings are treated as pre-start
+        if current_time < 0 || current_time < self.start_time {
+            return Ok(0);
+        }
+
+        // Paused time does not accrue, for any vesting curve
+        let current_time = self.vesting_time(current_time);
         if current_time < self.start_time {
             return Ok(0);
         }
+        // Everything is released at the end, so rounding never strands dust in escrow
+        if current_time >= self.end_time {
+            return Ok(self.deposited_amount);
+        }
 
         match self.stream_type {
             StreamType::Linear => self.calculate_linear_amount(current_time),
             StreamType::Cliff => self.calculate_cliff_amount(current_time),
             StreamType::Step => self.calculate_step_amount(current_time),
             StreamType::Custom => self.calculate_custom_amount(current_time),
+            StreamType::CliffStep => self.calculate_cliff_step_amount(current_time),
+            // Pre-start is handled above, so everything is released from `start_time`
+            StreamType::Instant => Ok(self.deposited_amount),
         }
     }
 
     /// Calculate linear vesting amount
-    fn calculate_linear_amount(&self, current_time: i64) -> Result<u64> {
-        let effective_time = std::cmp::min(current_time, self.end_time);
-        let elapsed_time = effective_time.saturating_sub(self.start_time);
-        let total_duration = self.end_time.saturating_sub(self.start_time);
+    fn calculate_linear_amount(&self, current_time: i64) -> StreamFlowResult<u64> {
+        Ok(calc::linear_amount(
+            self.deposited_amount,
+            self.start_time,
+            self.end_time,
+            current_time,
+        )?)
+    }
 
-        if total_duration == 0 {
+    /// Calculate cliff vesting amount
+    fn calculate_cliff_amount(&self, current_time: i64) -> StreamFlowResult<u64> {
+        Ok(calc::cliff_amount(
+            self.deposited_amount,
+            self.cliff_amount,
+            self.start_time,
+            self.end_time,
+            self.cliff_time,
+            current_time,
+        )?)
+    }
+
+    /// Calculate step vesting amount
+    fn calculate_step_amount(&self, current_time: i64) -> StreamFlowResult<u64> {
+        // The schedule ends at `end_time` whatever the steps add up to
+        if current_time >= self.end_time {
             return Ok(self.deposited_amount);
         }
+        Ok(calc::step_amount(
+            self.deposited_amount,
+            self.rate_amount,
+            self.rate_interval_in_seconds,
+            self.start_time,
+            current_time,
+        )?)
+    }
 
-        let streamed_amount = (self.deposited_amount as u128)
-            .checked_mul(elapsed_time as u128)
-            .ok_or(ErrorCode::MathOverflow)?
-            .checked_div(total_duration as u128)
-            .ok_or(ErrorCode::MathOverflow)? as u64;
+    /// Calculate cliff-then-step vesting amount
+    fn calculate_cliff_step_amount(&self, current_time: i64) -> StreamFlowResult<u64> {
+        if current_time >= self.end_time {
+            return Ok(self.deposited_amount);
+        }
+        Ok(calc::cliff_step_amount(
+            self.deposited_amount,
+            self.cliff_amount,
+            self.cliff_time,
+            self.rate_amount,
+            self.rate_interval_in_seconds,
+            current_time,
+        )?)
+    }
+
+    /// Calculate custom vesting amount (placeholder for future implementation)
+    fn calculate_custom_amount(&self, _current_time: i64) -> StreamFlowResult<u64> {
+        // Custom vesting logic would be implemented here
+        // For now, fallback to linear
+        self.calculate_linear_amount(_current_time)
+    }
 
-        Ok(std::cmp::min(streamed_amount, self.deposited_amount))
+    /// Check if the stream is active
+    pub fn is_active(&self) -> bool {
+        matches!(self.status, StreamStatus::Streaming)
     }
 
-    /// Calculate cliff vesting amount
-    fn calculate_cliff_amount(&self, current_time: i64) -> Result<u64> {
-        if current_time < self.cliff_time {
-            return Ok(0);
+    /// Check if the stream has ended
+    pub fn has_ended(&self, current_time: i64) -> bool {
+        current_time >= self.end_time || self.status == StreamStatus::Completed
+    }
+
+    /// Ensure the stream was created toppable and can still accept deposits
+    pub fn ensure_topup_allowed(&self) -> Result<()> {
+        require!(self.can_topup, StreamFlowError::StreamModificationNotAllowed);
+        require!(
+            !matches!(self.status, StreamStatus::Cancelled | StreamStatus::Completed),
+            StreamFlowError::StreamModificationNotAllowed
+        );
+        Ok(())
+    }
+
+    /// Add `amount` to the deposit at `now`.
+    ///
+    /// Without an extension the top-up is spread over the existing schedule.
+    /// With `extend_end_time_by`, the stream is rebased like `update_rate`:
+    /// everything vested so far becomes a cliff released at `now`, and the
+    /// rest, top-up included, vests over the lengthened window. Step streams
+    /// get a new `rate_amount` sized to that window.
+    pub fn top_up(&mut self, amount: u64, extend_end_time_by: Option<i64>, now: i64) -> Result<()> {
+        let deposited_amount = self
+            .deposited_amount
+            .checked_add(amount)
+            .ok_or(StreamError::MathOverflow)?;
+        let extension = match extend_end_time_by {
+            Some(extension) => extension,
+            None => {
+                self.deposited_amount = deposited_amount;
+                return Ok(());
+            }
+        };
+        require!(extension > 0, ErrorCode::InvalidTimeParams);
+
+        let vested = self.calculate_streamed_amount(now)?;
+        let vesting_now = self.vesting_time(now);
+        let end_time = self
+            .end_time
+            .checked_add(extension)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        let started = match self.stream_type {
+            StreamType::Cliff | StreamType::CliffStep => vesting_now >= self.cliff_time,
+            _ => vesting_now > self.start_time,
+        };
+
+        match self.stream_type {
+            StreamType::Linear | StreamType::Cliff => {
+                if started {
+                    self.stream_type = StreamType::Cliff;
+                    self.cliff_time = vesting_now;
+                    self.cliff_amount = vested;
+                }
+                self.deposited_amount = deposited_amount;
+                self.end_time = end_time;
+            }
+            StreamType::Step | StreamType::CliffStep => {
+                require!(self.rate_interval_in_seconds > 0, ErrorCode::InvalidTimeParams);
+                if started {
+                    self.stream_type = StreamType::CliffStep;
+                    self.cliff_time = vesting_now;
+                    self.cliff_amount = vested;
+                }
+                self.deposited_amount = deposited_amount;
+
+                let (steps_from, to_vest) = match self.stream_type {
+                    StreamType::CliffStep => {
+                        (self.cliff_time, deposited_amount.saturating_sub(self.cliff_amount))
+                    }
+                    _ => (self.start_time, deposited_amount),
+                };
+                let window = end_time
+                    .checked_sub(steps_from)
+                    .ok_or(StreamFlowError::ArithmeticOverflow)?;
+                let steps = u64::try_from(window).unwrap_or(0) / self.rate_interval_in_seconds;
+                require!(steps > 0, ErrorCode::InvalidTimeParams);
+                self.rate_amount = (to_vest / steps + u64::from(to_vest % steps != 0)).max(1);
+                self.end_time = self.schedule_end().ok_or(StreamFlowError::ArithmeticOverflow)?;
+            }
+            _ => return err!(StreamFlowError::InvalidStreamType),
         }
 
-        if current_time < self.start_time {
-            return Ok(0);
+        require!(
+            self.calculate_streamed_amount(now)? == vested,
+            StreamFlowError::DataIntegrityCheckFailed
+        );
+        Ok(())
+    }
+
+    /// Reject a stream that has been cancelled, whichever of `status` and
+    /// `cancelled_at` records it
+    pub fn ensure_not_cancelled(&self) -> Result<()> {
+        require!(
+            self.status != StreamStatus::Cancelled && self.cancelled_at.is_none(),
+            StreamError::StreamCanceled
+        );
+        Ok(())
+    }
+
+    /// Reject withdrawals while the stream still awaits the recipient's acceptance
+    pub fn ensure_accepted(&self) -> Result<()> {
+        require!(!self.require_acceptance || self.accepted, StreamError::StreamNotAccepted);
+        Ok(())
+    }
+
+    /// Reject a manual withdrawal made within `min_withdrawal_interval` of the last one
+    pub fn check_withdrawal_cooldown(&self, current_time: i64) -> StreamFlowResult<()> {
+        let since_last = current_time.saturating_sub(self.last_withdrawn_at);
+        if since_last < 0 || (since_last as u64) < self.min_withdrawal_interval {
+            return Err(StreamFlowError::RateLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// Record the recipient's acceptance of the stream
+    pub fn accept(&mut self, now: i64) -> Result<()> {
+        self.ensure_not_cancelled()?;
+        require!(!self.accepted, StreamError::StreamAlreadyAccepted);
+        if self.start_on_acceptance {
+            // Keep the schedule's durations, anchored at the acceptance time
+            self.offset_schedule(now.saturating_sub(self.start_time))?;
+            self.last_withdrawn_at = self.start_time;
+            self.activate_if_started(now);
         }
+        self.accepted = true;
+        self.touch(now);
+        Ok(())
+    }
 
-        // Cliff amount is immediately available after cliff time
-        let cliff_released = if current_time >= self.cliff_time {
-            self.cliff_amount
+    /// Require `threshold` of `approvers` to approve a cancel. A zero
+    /// threshold with no approvers turns the requirement off.
+    pub fn set_cancel_multisig(&mut self, approvers: Vec<Pubkey>, threshold: u8) -> StreamFlowResult<()> {
+        let unique = approvers
+            .iter()
+            .enumerate()
+            .all(|(i, key)| !approvers[..i].contains(key));
+        let valid = if threshold == 0 {
+            approvers.is_empty()
         } else {
-            0
+            usize::from(threshold) <= approvers.len() && approvers.len() <= MAX_CANCEL_APPROVERS && unique
+        };
+        if !valid {
+            return Err(StreamFlowError::InvalidMultisigConfiguration);
+        }
+        self.cancel_approvers = approvers;
+        self.cancel_threshold = threshold;
+        Ok(())
+    }
+
+    /// Ensure enough approvals have been collected when a cancel multisig is configured
+    pub fn check_cancel_approvals(&self, approval: Option<&CancelApproval>) -> StreamFlowResult<()> {
+        if self.cancel_threshold == 0 {
+            return Ok(());
+        }
+        let approvals = approval.map_or(0, |approval| approval.approvals.len());
+        if approvals < usize::from(self.cancel_threshold) {
+            return Err(StreamFlowError::MultisigThresholdNotMet);
+        }
+        Ok(())
+    }
+
+    /// Peg the release rate to `usd_rate_per_second` micro-USD, priced by
+    /// `price_feed` no older than `max_price_age` seconds
+    pub fn set_usd_peg(
+        &mut self,
+        usd_rate_per_second: Option<u64>,
+        price_feed: Option<Pubkey>,
+        max_price_age: u64,
+    ) -> StreamFlowResult<()> {
+        let valid = match usd_rate_per_second {
+            Some(rate) => rate > 0 && price_feed.is_some() && max_price_age > 0,
+            None => price_feed.is_none(),
+        };
+        if !valid {
+            return Err(StreamFlowError::InvalidOracleConfiguration);
+        }
+        self.usd_rate_per_second = usd_rate_per_second;
+        self.price_feed = price_feed;
+        self.max_price_age = max_price_age;
+        Ok(())
+    }
+
+    /// Tokens worth the USD accrued since the last withdrawal at `price`,
+    /// before the schedule's cap is applied
+    pub fn usd_pegged_amount(&self, price: &Price, decimals: u8, current_time: i64) -> StreamFlowResult<u64> {
+        let rate = match self.usd_rate_per_second {
+            Some(rate) => rate,
+            None => return Err(StreamFlowError::InvalidOracleConfiguration),
         };
+        let elapsed = current_time.saturating_sub(self.last_withdrawn_at).max(0) as u128;
+        let usd = elapsed
+            .checked_mul(u128::from(rate))
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        price.usd_to_tokens(usd, decimals)
+    }
+
+    /// Ensure `authority` is the stream's clawback authority and the stream
+    /// has not already ended
+    pub fn authorize_clawback(&self, authority: &Pubkey) -> Result<()> {
+        require!(
+            self.clawback_authority == Some(*authority),
+            StreamError::UnauthorizedCancel
+        );
+        require!(
+            matches!(
+                self.status,
+                StreamStatus::Scheduled | StreamStatus::Streaming | StreamStatus::Paused
+            ),
+            StreamError::StreamNotActive
+        );
+        Ok(())
+    }
+
+    /// Ensure a withdrawal carries an invoice receipt when the stream requires one
+    pub fn check_withdrawal_receipt(&self, receipt_hash: &[u8; 32]) -> Result<()> {
+        if self.receipt_required {
+            require!(
+                receipt_hash.iter().any(|b| *b != 0),
+                StreamError::MissingWithdrawalReceipt
+            );
+        }
+        Ok(())
+    }
+
+    /// Consume a relayed withdrawal authorization nonce. Nonces must strictly
+    /// increase, so an authorization can never be replayed.
+    pub fn consume_authorization_nonce(&mut self, nonce: u64) -> StreamFlowResult<()> {
+        if nonce <= self.authorization_nonce {
+            return Err(StreamFlowError::NonceAlreadyUsed);
+        }
+        self.authorization_nonce = nonce;
+        Ok(())
+    }
+
+    /// Mark the stream completed once everything deposited has been withdrawn,
+    /// releasing its share of `rate_cap`, and return the event to emit when it is
+    pub fn complete_if_fully_withdrawn(
+        &mut self,
+        stream_key: Pubkey,
+        current_time: i64,
+        rate_cap: Option<&mut RecipientRateCap>,
+    ) -> Result<Option<StreamCompleted>> {
+        if self.withdrawn_amount < self.deposited_amount
+            || !is_valid_status_transition(self.status, StreamStatus::Completed)
+        {
+            return Ok(None);
+        }
+        self.release_rate_cap(rate_cap)?;
+        self.status = StreamStatus::Completed;
+        Ok(Some(StreamCompleted {
+            stream: stream_key,
+            recipient: self.recipient,
+            total_withdrawn: self.withdrawn_amount,
+            completed_at: current_time,
+        }))
+    }
+
+    /// Give back the rate this stream reserved on the sender's cap. Every
+    /// instruction that ends a stream calls this, so a stream holding a
+    /// reservation cannot end without its cap account.
+    pub fn release_rate_cap(&mut self, rate_cap: Option<&mut RecipientRateCap>) -> Result<()> {
+        if self.capped_rate == 0 {
+            return Ok(());
+        }
+        let rate_cap = rate_cap.ok_or(StreamError::RateCapRequired)?;
+        rate_cap.release(self.capped_rate);
+        self.capped_rate = 0;
+        Ok(())
+    }
+
+    /// Ensure the stream's rate may still be changed at `current_time`
+    pub fn ensure_rate_update_allowed(&self, current_time: i64) -> Result<()> {
+        require!(self.can_update_rate, StreamFlowError::StreamModificationNotAllowed);
+        if self.lock_rate_after_start {
+            require!(
+                current_time < self.start_time,
+                StreamFlowError::StreamModificationNotAllowed
+            );
+        }
+        Ok(())
+    }
+
+    /// Invariant: the recipient can never have withdrawn more than has vested.
+    /// Checked after every mutation of `withdrawn_amount`.
+    pub fn check_withdrawn_within_vested(&self, current_time: i64) -> StreamFlowResult<()> {
+        if self.withdrawn_amount > self.calculate_streamed_amount(current_time)? {
+            return Err(StreamFlowError::DataIntegrityCheckFailed);
+        }
+        Ok(())
+    }
+
+    /// Announce a platform fee change taking effect at `effective_at`, at least
+    /// `MIN_FEE_CHANGE_NOTICE` from now; the current fee keeps applying until
+    /// then. The new fee is held to the same cap as fees set at creation.
+    pub fn schedule_fee_change(
+        &mut self,
+        new_fee_percentage: u16,
+        effective_at: i64,
+        current_time: i64,
+    ) -> Result<()> {
+        require!(
+            !matches!(self.status, StreamStatus::Cancelled | StreamStatus::Completed),
+            StreamFlowError::StreamModificationNotAllowed
+        );
+        require!(
+            effective_at >= current_time.saturating_add(MIN_FEE_CHANGE_NOTICE),
+            ErrorCode::InvalidTimeParams
+        );
+        let total_bps = u32::from(new_fee_percentage) + u32::from(self.partner_fee_percentage);
+        if total_bps > u32::from(MAX_PLATFORM_FEE_BPS) {
+            msg!(
+                "Fee of {} bps ({}%) plus the partner fee exceeds the {} bps cap",
+                new_fee_percentage,
+                percent_from_bps(new_fee_percentage),
+                MAX_PLATFORM_FEE_BPS
+            );
+            return err!(StateError::PlatformFeeExceedsMaximum);
+        }
+        require!(
+            new_fee_percentage == 0 || self.fee_recipient.is_some(),
+            StreamFlowError::InvalidFeeConfiguration
+        );
+        self.pending_fee = Some((new_fee_percentage, effective_at));
+        Ok(())
+    }
 
-        // Linear vesting for remaining amount after start time
-        let remaining_amount = self.deposited_amount.saturating_sub(self.cliff_amount);
-        let linear_amount = if current_time > self.start_time && remaining_amount > 0 {
-            let effective_time = std::cmp::min(current_time, self.end_time);
-            let elapsed_time = effective_time.saturating_sub(self.start_time);
-            let total_duration = self.end_time.saturating_sub(self.start_time);
-
-            if total_duration > 0 {
-                (remaining_amount as u128)
-                    .checked_mul(elapsed_time as u128)
-                    .ok_or(ErrorCode::MathOverflow)?
-                    .checked_div(total_duration as u128)
-                    .ok_or(ErrorCode::MathOverflow)? as u64
-            } else {
-                remaining_amount
+    /// Apply the announced fee once its effective time has passed, returning
+    /// the (old, new) fee when a change was applied
+    pub fn apply_pending_fee(&mut self, current_time: i64) -> Option<(u16, u16)> {
+        match self.pending_fee {
+            Some((new_fee, effective_at)) if current_time >= effective_at => {
+                let old_fee = self.fee_percentage;
+                self.fee_percentage = new_fee;
+                self.pending_fee = None;
+                Some((old_fee, new_fee))
             }
+            _ => None,
+        }
+    }
+
+    /// `current_time` on a clock that stops while the stream is paused
+    pub fn vesting_time(&self, current_time: i64) -> i64 {
+        current_time.saturating_sub(self.paused_duration_at(current_time))
+    }
+
+    /// Fail with `InvalidStatusTransition` unless the lifecycle allows moving to `target`
+    pub fn check_transition(&self, target: StreamStatus) -> Result<()> {
+        require!(
+            is_valid_status_transition(self.status, target),
+            StateError::InvalidStatusTransition
+        );
+        Ok(())
+    }
+
+    /// Pause the stream at `current_time`
+    pub fn pause(&mut self, current_time: i64) -> Result<()> {
+        self.check_transition(StreamStatus::Paused)?;
+        self.record_pause(current_time)?;
+        self.status = StreamStatus::Paused;
+        Ok(())
+    }
+
+    /// Resume a paused stream, back to `Scheduled` if it has not started yet
+    pub fn resume(&mut self, current_time: i64) -> Result<()> {
+        let target = if current_time >= self.start_time {
+            StreamStatus::Streaming
         } else {
-            0
+            StreamStatus::Scheduled
         };
+        self.check_transition(target)?;
+        self.record_resume(current_time)?;
+        self.status = target;
+        Ok(())
+    }
 
-        Ok(cliff_released.saturating_add(linear_amount))
+    /// Resume a stream whose pause has outlasted `max_pause_duration`, on
+    /// anyone's behalf
+    pub fn force_resume(&mut self, current_time: i64) -> Result<()> {
+        let paused_at = self.paused_at.ok_or(StreamError::StreamNotPaused)?;
+        let paused_for = current_time.saturating_sub(paused_at);
+        require!(
+            self.max_pause_duration > 0 && paused_for > 0 && paused_for as u64 > self.max_pause_duration,
+            StreamError::PauseLimitNotReached
+        );
+        self.resume(current_time)
     }
 
-    /// Calculate step vesting amount
-    fn calculate_step_amount(&self, current_time: i64) -> Result<u64> {
-        if current_time < self.start_time {
-            return Ok(0);
-        }
+    /// Record the start of a pause
+    pub fn record_pause(&mut self, current_time: i64) -> Result<()> {
+        require!(self.paused_at.is_none(), StreamError::StreamAlreadyPaused);
+        self.paused_at = Some(current_time);
+        self.touch(current_time);
+        Ok(())
+    }
 
-        let elapsed_time = current_time.saturating_sub(self.start_time);
-        let intervals_passed = elapsed_time / self.rate_interval_in_seconds;
-        let amount_per_interval = self.rate_amount;
+    /// Fold the pause that just ended into `total_paused_duration`
+    pub fn record_resume(&mut self, current_time: i64) -> Result<()> {
+        let paused_at = self.paused_at.take().ok_or(StreamError::StreamNotPaused)?;
+        let paused_for = current_time.saturating_sub(paused_at).max(0);
+        self.total_paused_duration = self
+            .total_paused_duration
+            .checked_add(paused_for)
+            .ok_or(StreamError::MathOverflow)?;
+        self.touch(current_time);
+        Ok(())
+    }
+
+    /// Seconds spent paused up to `current_time`, including an ongoing pause
+    pub fn paused_duration_at(&self, current_time: i64) -> i64 {
+        let ongoing = match self.paused_at {
+            Some(paused_at) => current_time.saturating_sub(paused_at).max(0),
+            None => 0,
+        };
+        self.total_paused_duration.saturating_add(ongoing)
+    }
 
-        let total_released = intervals_passed
-            .checked_mul(amount_per_interval as i64)
-            .ok_or(ErrorCode::MathOverflow)? as u64;
+    /// Effective status at `current_time`, derived from the schedule and
+    /// balances rather than the possibly stale stored `status`
+    pub fn derive_status(&self, current_time: i64) -> Result<StreamStatus> {
+        let derived = if self.status == StreamStatus::Cancelled {
+            StreamStatus::Cancelled
+        } else if self.withdrawn_amount >= self.deposited_amount
+            || self.calculate_streamed_amount(current_time)? >= self.deposited_amount
+        {
+            StreamStatus::Completed
+        } else if self.paused_at.is_some() || self.status == StreamStatus::Paused {
+            StreamStatus::Paused
+        } else if current_time < self.start_time {
+            StreamStatus::Scheduled
+        } else {
+            // Started, even if a cliff has not been reached yet
+            StreamStatus::Streaming
+        };
 
-        Ok(std::cmp::min(total_released, self.deposited_amount))
+        // The stored status may lag by one step (e.g. never moved to Streaming)
+        let reachable = derived == self.status
+            || is_valid_status_transition(self.status, derived)
+            || (is_valid_status_transition(self.status, StreamStatus::Streaming)
+                && is_valid_status_transition(StreamStatus::Streaming, derived));
+        require!(reachable, StateError::InvalidStatusTransition);
+
+        Ok(derived)
     }
 
-    /// Calculate custom vesting amount (placeholder for future implementation)
-    fn calculate_custom_amount(&self, _current_time: i64) -> Result<u64> {
-        // Custom vesting logic would be implemented here
-        // For now, fallback to linear
-        self.calculate_linear_amount(_current_time)
+    /// Timestamp at which everything deposited will have vested, given the
+    /// pauses observed up to `now`. Returns `i64::MAX` for a step schedule
+    /// that can never release the full deposit.
+    pub fn projected_completion(&self, now: i64) -> i64 {
+        match self.schedule_end() {
+            Some(end) => end.saturating_add(self.paused_duration_at(now)),
+            None => i64::MAX,
+        }
     }
 
-    /// Check if the stream is active
-    pub fn is_active(&self) -> bool {
-        matches!(self.status, StreamStatus::Streaming)
+    /// End of the vesting schedule on the vesting clock (paused time excluded),
+    /// or `None` for a step schedule that can never release the full deposit
+    fn schedule_end(&self) -> Option<i64> {
+        let steps_needed = |amount: u64| -> Option<i64> {
+            if amount == 0 {
+                return Some(0);
+            }
+            if self.rate_amount == 0 || self.rate_interval_in_seconds == 0 {
+                return None;
+            }
+            let steps = amount / self.rate_amount + u64::from(amount % self.rate_amount != 0);
+            i64::try_from(steps.checked_mul(self.rate_interval_in_seconds)?).ok()
+        };
+
+        match self.stream_type {
+            StreamType::Linear | StreamType::Cliff | StreamType::Custom => Some(self.end_time),
+            StreamType::Instant => Some(self.start_time),
+            StreamType::Step => steps_needed(self.deposited_amount)
+                .and_then(|span| self.start_time.checked_add(span)),
+            StreamType::CliffStep => steps_needed(self.deposited_amount.saturating_sub(self.cliff_amount))
+                .and_then(|span| self.cliff_time.checked_add(span)),
+        }
     }
 
-    /// Check if the stream has ended
-    pub fn has_ended(&self, current_time: i64) -> bool {
-        current_time >= self.end_time || self.status == StreamStatus::Completed
+    /// Ensure a step schedule's steps release the deposit by `end_time`, in the
+    /// last interval before it, so `end_time` and the rate agree to within a step
+    pub fn check_step_schedule(&self) -> StreamFlowResult<()> {
+        if !matches!(self.stream_type, StreamType::Step | StreamType::CliffStep) {
+            return Ok(());
+        }
+        let schedule_end = self
+            .schedule_end()
+            .ok_or(StreamFlowError::StreamRateCalculationFailed)?;
+        let interval = i64::try_from(self.rate_interval_in_seconds)
+            .map_err(|_| StreamFlowError::StreamRateCalculationFailed)?;
+        if schedule_end > self.end_time || self.end_time - schedule_end >= interval {
+            return Err(StreamFlowError::StreamRateCalculationFailed);
+        }
+        Ok(())
+    }
+
+    /// Change the step rate of a step-based stream at `now` and recompute
+    /// `end_time` from the balance still to vest.
+    ///
+    /// Once steps have started, the stream is rebased into a cliff-step
+    /// stream whose "cliff" is everything vested so far, released at `now`,
+    /// so the new rate only applies going forward and nothing already vested
+    /// changes.
+    pub fn update_rate(&mut self, new_rate_amount: u64, new_rate_interval: u64, now: i64) -> Result<()> {
+        self.ensure_rate_update_allowed(now)?;
+        require!(self.status != StreamStatus::Cancelled, StreamError::StreamCanceled);
+        require!(
+            self.status != StreamStatus::Completed,
+            StreamFlowError::StreamModificationNotAllowed
+        );
+        require!(
+            matches!(self.stream_type, StreamType::Step | StreamType::CliffStep),
+            StreamFlowError::InvalidStreamType
+        );
+        require!(new_rate_interval > 0, ErrorCode::InvalidTimeParams);
+        require!(new_rate_amount > 0, StreamError::InvalidAmount);
+
+        let vested = self.calculate_streamed_amount(now)?;
+        let vesting_now = self.vesting_time(now);
+        let steps_started = match self.stream_type {
+            StreamType::CliffStep => vesting_now >= self.cliff_time,
+            _ => vesting_now >= self.start_time,
+        };
+        if steps_started {
+            self.stream_type = StreamType::CliffStep;
+            self.cliff_time = vesting_now;
+            self.cliff_amount = vested;
+        }
+
+        self.rate_amount = new_rate_amount;
+        self.rate_interval_in_seconds = new_rate_interval;
+        self.end_time = self.schedule_end().ok_or(StreamFlowError::ArithmeticOverflow)?;
+        self.touch(now);
+
+        require!(
+            self.calculate_streamed_amount(now)? == vested,
+            StreamFlowError::DataIntegrityCheckFailed
+        );
+        Ok(())
     }
 
     /// Check if the stream can be cancelled by the given authority
@@ -262,24 +1077,25 @@ impl Stream {
         }
     }
 
-    /// Calculate fees for a given amount
-    pub fn calculate_fees(&self, amount: u64) -> Result<(u64, u64)> {
+    /// Calculate fees for a given amount, rounding each fee down so rounding
+    /// always favours the recipient
+    pub fn calculate_fees(&self, amount: u64) -> StreamFlowResult<(u64, u64)> {
         let platform_fee = if self.fee_percentage > 0 {
             (amount as u128)
                 .checked_mul(self.fee_percentage as u128)
-                .ok_or(ErrorCode::MathOverflow)?
+                .ok_or(StreamFlowError::ArithmeticOverflow)?
                 .checked_div(10000)
-                .ok_or(ErrorCode::MathOverflow)? as u64
+                .ok_or(StreamFlowError::ArithmeticOverflow)? as u64
         } else {
             0
         };
 
-        let partner_fee = if self.partner_fee_percentage > 0 {
+        let partner_fee = if self.partner_fee_percentage > 0 && self.partner_fee_applies() {
             (amount as u128)
                 .checked_mul(self.partner_fee_percentage as u128)
-                .ok_or(ErrorCode::MathOverflow)?
+                .ok_or(StreamFlowError::ArithmeticOverflow)?
                 .checked_div(10000)
-                .ok_or(ErrorCode::MathOverflow)? as u64
+                .ok_or(StreamFlowError::ArithmeticOverflow)? as u64
         } else {
             0
         };
@@ -287,13 +1103,239 @@ impl Stream {
         Ok((platform_fee, partner_fee))
     }
 
+    /// Interest owed on the vested-but-unclaimed balance since the last withdrawal
+    ///
+    /// Uses the average of the unclaimed balance at the last withdrawal and now,
+    /// which is exact for linear accrual.
+    pub fn accrued_interest(&self, current_time: i64) -> StreamFlowResult<u64> {
+        if self.interest_rate_bps == 0 || current_time <= self.last_withdrawn_at {
+            return Ok(0);
+        }
+
+        let unclaimed_then = self.withdrawable_amount(self.last_withdrawn_at)? as u128;
+        let unclaimed_now = self.withdrawable_amount(current_time)? as u128;
+        let holding_period = current_time.saturating_sub(self.last_withdrawn_at) as u128;
+
+        let interest = unclaimed_then
+            .checked_add(unclaimed_now)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?
+            .checked_mul(self.interest_rate_bps as u128)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?
+            .checked_mul(holding_period)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?
+            .checked_div(2 * 10000 * SECONDS_PER_YEAR as u128)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+
+        u64::try_from(interest).map_err(|_| StreamFlowError::ArithmeticOverflow)
+    }
+
+    /// Check if partner fees apply to the next withdrawal
+    pub fn partner_fee_applies(&self) -> bool {
+        self.partner_fee_active || self.withdrawn_amount > self.partner_fee_activation_amount
+    }
+
+    /// Latch partner fee activation once cumulative withdrawals pass the threshold
+    pub fn update_partner_fee_activation(&mut self) {
+        if !self.partner_fee_active && self.withdrawn_amount > self.partner_fee_activation_amount {
+            self.partner_fee_active = true;
+        }
+    }
+
+    /// Split a gross withdrawal into (net, platform_fee, partner_fee)
+    ///
+    /// The net is derived by subtraction so `net + platform_fee + partner_fee`
+    /// always equals `gross` exactly.
+    pub fn split_withdrawal(&self, gross: u64) -> StreamFlowResult<(u64, u64, u64)> {
+        let (platform_fee, partner_fee) = self.calculate_fees(gross)?;
+        let total_fees = platform_fee
+            .checked_add(partner_fee)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        let net = gross
+            .checked_sub(total_fees)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+
+        Ok((net, platform_fee, partner_fee))
+    }
+
+    /// Re-derive the escrow PDA from the stored bump, exactly as `invoke_signed` does
+    pub fn escrow_address(&self, stream: &Pubkey, program_id: &Pubkey) -> Result<Pubkey> {
+        Pubkey::create_program_address(
+            &[b"escrow", stream.as_ref(), &[self.escrow_bump]],
+            program_id,
+        )
+        .map_err(|_| ErrorCode::InvalidStreamConfig.into())
+    }
+
+    /// Get the token account withdrawals are paid into
+    pub fn destination_token_account(&self) -> Pubkey {
+        self.withdraw_destination
+            .or(self.recipient_token_account)
+            .unwrap_or_else(|| get_associated_token_address(&self.recipient, &self.mint))
+    }
+
+    /// Hand the stream to `new_recipient`, returning the previous one. The
+    /// withdrawn amount carries over, so the new recipient can only claim
+    /// what has not already been withdrawn.
+    pub fn reassign_recipient(&mut self, new_recipient: Pubkey, current_time: i64) -> Result<Pubkey> {
+        require_keys_neq!(new_recipient, self.recipient, StreamError::InvalidRecipient);
+        require!(
+            self.recipient_history.len() < MAX_RECIPIENT_HISTORY,
+            StreamFlowError::StreamModificationNotAllowed
+        );
+        let old_recipient = self.recipient;
+        self.recipient_history.push((old_recipient, current_time));
+        self.recipient = new_recipient;
+        // Destinations registered by the previous recipient must not keep receiving funds
+        self.recipient_token_account = None;
+        self.auto_withdraw_destination = None;
+        self.withdraw_destination = None;
+        Ok(old_recipient)
+    }
+
+    /// Delay both `start_time` and `end_time` by `delay` seconds
+    pub fn shift_schedule(&mut self, delay: i64) -> Result<()> {
+        require!(delay >= 0, ErrorCode::InvalidTimeParams);
+        self.offset_schedule(delay)
+    }
+
+    /// Move `start_time`, `end_time` and any cliff by `delay` seconds, which
+    /// may be negative
+    fn offset_schedule(&mut self, delay: i64) -> Result<()> {
+        let start_time = self
+            .start_time
+            .checked_add(delay)
+            .ok_or(ErrorCode::InvalidTimeParams)?;
+        let end_time = self
+            .end_time
+            .checked_add(delay)
+            .ok_or(ErrorCode::InvalidTimeParams)?;
+        let cliff_time = if self.cliff_time != 0 {
+            self.cliff_time
+                .checked_add(delay)
+                .ok_or(ErrorCode::InvalidTimeParams)?
+        } else {
+            0
+        };
+
+        self.start_time = start_time;
+        self.end_time = end_time;
+        self.cliff_time = cliff_time;
+        Ok(())
+    }
+
+    /// Push `end_time` out by `extension` seconds
+    pub fn extend_end_time(&mut self, extension: i64) -> Result<()> {
+        require!(extension >= 0, ErrorCode::InvalidTimeParams);
+
+        self.end_time = self
+            .end_time
+            .checked_add(extension)
+            .ok_or(ErrorCode::InvalidTimeParams)?;
+        Ok(())
+    }
+
+    /// Mark the stream as mid-instruction, rejecting re-entrant calls
+    pub fn begin_processing(&mut self) -> Result<()> {
+        require!(!self.processing, StreamError::ReentrancyDetected);
+        self.processing = true;
+        Ok(())
+    }
+
+    /// Clear the reentrancy guard once all CPIs have completed
+    pub fn end_processing(&mut self) {
+        self.processing = false;
+    }
+
+    /// Append an amendment to the journal and emit `StreamAmended`
+    pub fn record_amendment(
+        &mut self,
+        stream: Pubkey,
+        kind: AmendmentKind,
+        old_value: &[u8],
+        new_value: &[u8],
+        authority: Pubkey,
+        timestamp: i64,
+    ) -> Result<AmendmentEntry> {
+        let entry = AmendmentEntry {
+            kind,
+            old_value_hash: hashv(&[old_value]).to_bytes(),
+            new_value_hash: hashv(&[new_value]).to_bytes(),
+            timestamp,
+            authority,
+        };
+
+        let sequence = self.amendment_count;
+        self.journal[sequence as usize % MAX_JOURNAL_ENTRIES] = entry;
+        self.amendment_count = sequence
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(StreamAmended {
+            stream,
+            sequence,
+            entry,
+        });
+
+        Ok(entry)
+    }
+
+    /// Journal entries still held on the stream, oldest first
+    pub fn journal_entries(&self) -> Vec<AmendmentEntry> {
+        let count = self.amendment_count as usize;
+        let retained = count.min(MAX_JOURNAL_ENTRIES);
+        (count - retained..count)
+            .map(|i| self.journal[i % MAX_JOURNAL_ENTRIES])
+            .collect()
+    }
+
+    /// Whether the stream is finished with and its accounts may be closed:
+    /// fully withdrawn, or cancelled. The escrow must also be empty.
+    pub fn is_closable(&self) -> bool {
+        matches!(self.status, StreamStatus::Completed | StreamStatus::Cancelled)
+            || self.withdrawn_amount >= self.deposited_amount
+    }
+
+    /// Check if reclaimed rent may be sent to the given account on close
+    pub fn is_valid_rent_destination(&self, destination: &Pubkey) -> bool {
+        *destination == self.sender || self.rent_treasury == Some(*destination)
+    }
+
+    /// Check if a metadata update signed by the given parties is allowed
+    pub fn can_update_metadata(&self, sender_signed: bool, recipient_signed: bool) -> bool {
+        if self.metadata_locked {
+            sender_signed && recipient_signed
+        } else {
+            sender_signed
+        }
+    }
+
+    /// Get the token account automatic withdrawals are paid into
+    pub fn auto_withdraw_token_account(&self) -> Pubkey {
+        self.auto_withdraw_destination
+            .unwrap_or_else(|| self.destination_token_account())
+    }
+
     /// Get the remaining balance in the stream
     pub fn remaining_balance(&self) -> u64 {
         self.deposited_amount.saturating_sub(self.withdrawn_amount)
     }
 
+    /// Tokens the escrow must keep holding for the recipient. A cancelled
+    /// stream has already returned the unvested share, leaving only parked payouts.
+    pub fn escrow_obligation(&self) -> u64 {
+        match self.status {
+            StreamStatus::Cancelled => self.parked_amount,
+            _ => self.remaining_balance(),
+        }
+    }
+
+    /// Escrow balance beyond the obligation, e.g. from external transfers
+    pub fn escrow_surplus(&self, escrow_balance: u64) -> u64 {
+        escrow_balance.saturating_sub(self.escrow_obligation())
+    }
+
     /// Get stream progress as a percentage (0-10000 basis points)
-    pub fn get_progress(&self, current_time: i64) -> Result<u16> {
+    pub fn get_progress(&self, current_time: i64) -> StreamFlowResult<u16> {
         if current_time < self.start_time {
             return Ok(0);
         }
@@ -311,17 +1353,38 @@ impl Stream {
 
         let progress = (elapsed as u128)
             .checked_mul(10000)
-            .ok_or(ErrorCode::MathOverflow)?
+            .ok_or(StreamFlowError::ArithmeticOverflow)?
             .checked_div(total_duration as u128)
-            .ok_or(ErrorCode::MathOverflow)? as u16;
+            .ok_or(StreamFlowError::ArithmeticOverflow)? as u16;
 
         Ok(std::cmp::min(progress, 10000))
     }
 }
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Mathematical operation resulted in overflow")]
+impl StateValidation for Stream {
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.metadata.created_at == self.created_at,
+            ErrorCode::InvalidStreamConfig
+        );
+        require!(
+            self.metadata.updated_at >= self.metadata.created_at,
+            ErrorCode::InvalidTimeParams
+        );
+        // Step curves divide elapsed time by the interval
+        if matches!(self.stream_type, StreamType::Step | StreamType::CliffStep) {
+            require!(
+                self.rate_interval_in_seconds > 0,
+                StreamFlowError::InvalidVestingSchedule
+            );
+        }
+        Ok(())
+    }
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Mathematical operation resulted in overflow")]
     MathOverflow,
     #[msg("Invalid stream configuration")]
     InvalidStreamConfig,
@@ -336,4 +1399,1292 @@ pub enum ErrorCode {
     #[msg("Invalid time parameters")]
     InvalidTimeParams,
 }
-```
\ No newline at end of file
+
+/// Test fixture for `Stream` with sensible defaults, so tests only spell out
+/// the fields they care about
+#[cfg(test)]
+pub struct StreamBuilder {
+    stream: Stream,
+}
+
+#[cfg(test)]
+impl Default for StreamBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+impl StreamBuilder {
+    /// A 1000 token linear stream running from t=100 to t=200
+    pub fn new() -> Self {
+        Self {
+            stream: Stream {
+                sender: Pubkey::new_unique(),
+                recipient: Pubkey::new_unique(),
+                mint: Pubkey::new_unique(),
+                escrow_tokens: Pubkey::new_unique(),
+                deposited_amount: 1000,
+                withdrawn_amount: 0,
+                start_time: 100,
+                end_time: 200,
+                last_withdrawn_at: 100,
+                created_at: 0,
+                cancelled_at: None,
+                rate_amount: 0,
+                rate_interval_in_seconds: 0,
+                cancelable_by_sender: true,
+                cancelable_by_recipient: false,
+                transferable_by_sender: false,
+                transferable_by_recipient: false,
+                automatic_withdrawal: false,
+                withdrawal_frequency: 0,
+                min_withdrawal_interval: 0,
+                can_topup: false,
+                can_update_rate: false,
+                lock_rate_after_start: false,
+                allow_partial_withdraw: true,
+                status: StreamStatus::Streaming,
+                stream_type: StreamType::Linear,
+                cliff_amount: 0,
+                cliff_time: 0,
+                fee_percentage: 0,
+                fee_recipient: None,
+                partner_fee_percentage: 0,
+                partner_fee_recipient: None,
+                interest_rate_bps: 0,
+                partner_fee_activation_amount: 0,
+                partner_fee_active: false,
+                pending_fee: None,
+                name: [0; 64],
+                metadata: StreamMetadata::default(),
+                recipient_token_account: None,
+                auto_withdraw_destination: None,
+                withdraw_destination: None,
+                metadata_locked: false,
+                rent_treasury: None,
+                amendment_count: 0,
+                journal: [AmendmentEntry::default(); MAX_JOURNAL_ENTRIES],
+                receipt_required: false,
+                parked_amount: 0,
+                capped_rate: 0,
+                total_paused_duration: 0,
+                paused_at: None,
+                max_pause_duration: 0,
+                authorization_nonce: 0,
+                processing: false,
+                template: None,
+                require_acceptance: false,
+                accepted: false,
+                start_on_acceptance: false,
+                cancel_approvers: Vec::new(),
+                cancel_threshold: 0,
+                clawback_authority: None,
+                usd_rate_per_second: None,
+                price_feed: None,
+                max_price_age: 0,
+                recipient_history: Vec::new(),
+                nonce: 0,
+                bump: 255,
+                escrow_bump: 255,
+                _reserved: [0; 128],
+            },
+        }
+    }
+
+    pub fn amount(mut self, amount: u64) -> Self {
+        self.stream.deposited_amount = amount;
+        self
+    }
+
+    pub fn withdrawn(mut self, amount: u64) -> Self {
+        self.stream.withdrawn_amount = amount;
+        self
+    }
+
+    /// Moves the start time, keeping the current duration
+    pub fn start_time(mut self, start_time: i64) -> Self {
+        let duration = self.stream.end_time - self.stream.start_time;
+        self.stream.start_time = start_time;
+        self.stream.end_time = start_time + duration;
+        self.stream.last_withdrawn_at = start_time;
+        self
+    }
+
+    pub fn duration(mut self, duration: i64) -> Self {
+        self.stream.end_time = self.stream.start_time + duration;
+        self
+    }
+
+    pub fn stream_type(mut self, stream_type: StreamType) -> Self {
+        self.stream.stream_type = stream_type;
+        self
+    }
+
+    pub fn status(mut self, status: StreamStatus) -> Self {
+        self.stream.status = status;
+        self
+    }
+
+    pub fn cliff(mut self, cliff_time: i64, cliff_amount: u64) -> Self {
+        self.stream.cliff_time = cliff_time;
+        self.stream.cliff_amount = cliff_amount;
+        self
+    }
+
+    pub fn rate(mut self, rate_amount: u64, rate_interval_in_seconds: u64) -> Self {
+        self.stream.rate_amount = rate_amount;
+        self.stream.rate_interval_in_seconds = rate_interval_in_seconds;
+        self
+    }
+
+    pub fn fees(mut self, fee_percentage: u16, partner_fee_percentage: u16) -> Self {
+        self.stream.fee_percentage = fee_percentage;
+        self.stream.partner_fee_percentage = partner_fee_percentage;
+        self
+    }
+
+    pub fn build(self) -> Stream {
+        self.stream
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_created_at_matches_metadata() {
+        let mut stream = StreamBuilder::new().build();
+        stream.set_created_at(1_700_000_000);
+
+        assert_eq!(stream.created_at, 1_700_000_000);
+        assert_eq!(stream.metadata.created_at, stream.created_at);
+        assert!(stream.validate().is_ok());
+
+        stream.metadata.created_at += 1;
+        assert!(stream.validate().is_err());
+    }
+
+    #[test]
+    fn test_escrow_signing_uses_stored_bump() {
+        let program_id = Pubkey::new_unique();
+        let stream_key = Pubkey::new_unique();
+        let (escrow, escrow_bump) =
+            Pubkey::find_program_address(&[b"escrow", stream_key.as_ref()], &program_id);
+
+        let mut stream = StreamBuilder::new().build();
+        stream.escrow_bump = escrow_bump;
+        assert_eq!(stream.escrow_address(&stream_key, &program_id).unwrap(), escrow);
+
+        // A stale bump no longer signs for the escrow
+        stream.escrow_bump = escrow_bump.wrapping_sub(1);
+        assert_ne!(stream.escrow_address(&stream_key, &program_id).ok(), Some(escrow));
+    }
+
+    #[test]
+    fn test_destination_defaults_to_recipient_ata() {
+        let stream = StreamBuilder::new().build();
+        assert_eq!(
+            stream.destination_token_account(),
+            get_associated_token_address(&stream.recipient, &stream.mint)
+        );
+    }
+
+    #[test]
+    fn test_replacement_destination_resumes_withdrawals() {
+        let mut stream = StreamBuilder::new().build();
+
+        // Recipient withdraws into their ATA, then closes it mid-stream
+        stream.withdrawn_amount = stream.withdrawable_amount(150).unwrap();
+        assert_eq!(stream.withdrawn_amount, 500);
+
+        // Recipient registers a replacement destination
+        let replacement = Pubkey::new_unique();
+        stream.recipient_token_account = Some(replacement);
+        assert_eq!(stream.destination_token_account(), replacement);
+
+        // Accrual is unaffected, so withdrawals resume where they left off
+        assert_eq!(stream.withdrawable_amount(200).unwrap(), 500);
+    }
+
+    #[test]
+    fn test_auto_withdraw_destination_is_separate_from_manual() {
+        let mut stream = StreamBuilder::new().build();
+        assert_eq!(stream.auto_withdraw_token_account(), stream.destination_token_account());
+
+        let custodian = Pubkey::new_unique();
+        stream.auto_withdraw_destination = Some(custodian);
+
+        assert_eq!(stream.auto_withdraw_token_account(), custodian);
+        assert_eq!(
+            stream.destination_token_account(),
+            get_associated_token_address(&stream.recipient, &stream.mint)
+        );
+    }
+
+    #[test]
+    fn test_unlocked_metadata_sender_alone() {
+        let stream = StreamBuilder::new().build();
+        assert!(stream.can_update_metadata(true, false));
+        assert!(stream.can_update_metadata(true, true));
+        assert!(!stream.can_update_metadata(false, true));
+    }
+
+    #[test]
+    fn test_locked_metadata_requires_cosign() {
+        let mut stream = StreamBuilder::new().build();
+        stream.metadata_locked = true;
+        assert!(!stream.can_update_metadata(true, false));
+        assert!(!stream.can_update_metadata(false, true));
+        assert!(stream.can_update_metadata(true, true));
+    }
+
+    #[test]
+    fn test_topup_requires_can_topup() {
+        let mut stream = StreamBuilder::new().build();
+        assert_eq!(
+            stream.ensure_topup_allowed().unwrap_err(),
+            StreamFlowError::StreamModificationNotAllowed.into()
+        );
+
+        stream.can_topup = true;
+        assert!(stream.ensure_topup_allowed().is_ok());
+
+        stream.status = StreamStatus::Cancelled;
+        assert!(stream.ensure_topup_allowed().is_err());
+    }
+
+    #[test]
+    fn test_cancel_authorization_matrix() {
+        let statuses = [
+            (StreamStatus::Scheduled, true),
+            (StreamStatus::Streaming, true),
+            (StreamStatus::Paused, true),
+            (StreamStatus::Cancelled, false),
+            (StreamStatus::Completed, false),
+        ];
+
+        for (status, cancellable) in statuses {
+            for (by_sender, by_recipient) in [(false, false), (true, false), (false, true), (true, true)] {
+                let mut stream = StreamBuilder::new().status(status).build();
+                stream.cancelable_by_sender = by_sender;
+                stream.cancelable_by_recipient = by_recipient;
+                let outsider = Pubkey::new_unique();
+
+                assert_eq!(stream.can_cancel(&stream.sender), cancellable && by_sender);
+                assert_eq!(stream.can_cancel(&stream.recipient), cancellable && by_recipient);
+                assert!(!stream.can_cancel(&outsider));
+            }
+        }
+    }
+
+    #[test]
+    fn test_cancel_scheduled_and_paused_streams() {
+        let scheduled = StreamBuilder::new().status(StreamStatus::Scheduled).build();
+        assert!(scheduled.can_cancel(&scheduled.sender));
+
+        let paused = StreamBuilder::new().status(StreamStatus::Paused).build();
+        assert!(paused.can_cancel(&paused.sender));
+    }
+
+    #[test]
+    fn test_rent_destination_defaults_to_sender() {
+        let stream = StreamBuilder::new().build();
+        assert!(stream.is_valid_rent_destination(&stream.sender));
+        assert!(!stream.is_valid_rent_destination(&stream.recipient));
+        assert!(!stream.is_valid_rent_destination(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn test_rent_destination_authorized_treasury() {
+        let mut stream = StreamBuilder::new().build();
+        let treasury = Pubkey::new_unique();
+        stream.rent_treasury = Some(treasury);
+
+        assert!(stream.is_valid_rent_destination(&treasury));
+        assert!(stream.is_valid_rent_destination(&stream.sender));
+        assert!(!stream.is_valid_rent_destination(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn test_reentrant_hook_is_blocked() {
+        let mut stream = StreamBuilder::new().build();
+
+        // A malicious hook invoked mid-withdraw tries to withdraw again
+        let hook = |stream: &mut Stream| stream.begin_processing();
+
+        stream.begin_processing().unwrap();
+        assert_eq!(hook(&mut stream).unwrap_err(), StreamError::ReentrancyDetected.into());
+        stream.end_processing();
+
+        // Once the outer call completes, the next call proceeds normally
+        assert!(hook(&mut stream).is_ok());
+    }
+
+    #[test]
+    fn test_each_amendment_appends_journal_entry() {
+        let mut stream = StreamBuilder::new().build();
+        let key = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let entry = stream
+            .record_amendment(key, AmendmentKind::Reschedule, &200i64.to_le_bytes(), &300i64.to_le_bytes(), authority, 1_000)
+            .unwrap();
+        assert_eq!(stream.amendment_count, 1);
+        assert_eq!(stream.journal_entries(), vec![entry]);
+        assert_eq!(entry.authority, authority);
+        assert_ne!(entry.old_value_hash, entry.new_value_hash);
+
+        stream
+            .record_amendment(key, AmendmentKind::FeeChange, &50u16.to_le_bytes(), &75u16.to_le_bytes(), authority, 2_000)
+            .unwrap();
+        let kinds: Vec<_> = stream.journal_entries().iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![AmendmentKind::Reschedule, AmendmentKind::FeeChange]);
+    }
+
+    #[test]
+    fn test_journal_is_capped() {
+        let mut stream = StreamBuilder::new().build();
+        let key = Pubkey::new_unique();
+
+        for i in 0..(MAX_JOURNAL_ENTRIES as i64 + 3) {
+            stream
+                .record_amendment(key, AmendmentKind::RateChange, &[], &[], key, i)
+                .unwrap();
+        }
+
+        let entries = stream.journal_entries();
+        assert_eq!(stream.amendment_count as usize, MAX_JOURNAL_ENTRIES + 3);
+        assert_eq!(entries.len(), MAX_JOURNAL_ENTRIES);
+        assert_eq!(entries.first().unwrap().timestamp, 3);
+        assert_eq!(entries.last().unwrap().timestamp, MAX_JOURNAL_ENTRIES as i64 + 2);
+    }
+
+    #[test]
+    fn test_shift_schedule() {
+        let mut stream = StreamBuilder::new().cliff(150, 0).build();
+        stream.shift_schedule(50).unwrap();
+        assert_eq!((stream.start_time, stream.cliff_time, stream.end_time), (150, 200, 250));
+    }
+
+    #[test]
+    fn test_shift_near_i64_max_is_rejected() {
+        let mut stream = StreamBuilder::new().build();
+        stream.end_time = i64::MAX - 10;
+
+        assert!(stream.shift_schedule(11).is_err());
+        assert!(stream.extend_end_time(11).is_err());
+        assert!(stream.extend_end_time(-1).is_err());
+
+        // Rejected shifts leave the schedule untouched
+        assert_eq!((stream.start_time, stream.end_time), (100, i64::MAX - 10));
+
+        stream.extend_end_time(10).unwrap();
+        assert_eq!(stream.end_time, i64::MAX);
+    }
+
+    #[test]
+    fn test_negative_current_time_accrues_nothing() {
+        let stream = StreamBuilder::new().start_time(0).duration(100).build();
+        assert_eq!(stream.calculate_streamed_amount(-1).unwrap(), 0);
+        assert_eq!(stream.calculate_streamed_amount(i64::MIN).unwrap(), 0);
+        assert_eq!(stream.withdrawable_amount(-50).unwrap(), 0);
+        assert_eq!(stream.get_progress(-50).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_cliff_step_stream() {
+        // One "year" cliff releasing 25%, then monthly-style steps
+        let stream = StreamBuilder::new()
+            .amount(1_200)
+            .start_time(0)
+            .duration(1_000)
+            .stream_type(StreamType::CliffStep)
+            .cliff(400, 300)
+            .rate(150, 100)
+            .build();
+
+        assert_eq!(stream.calculate_streamed_amount(0).unwrap(), 0);
+        assert_eq!(stream.calculate_streamed_amount(399).unwrap(), 0);
+        assert_eq!(stream.calculate_streamed_amount(400).unwrap(), 300);
+        assert_eq!(stream.calculate_streamed_amount(499).unwrap(), 300);
+        assert_eq!(stream.calculate_streamed_amount(500).unwrap(), 450);
+        assert_eq!(stream.calculate_streamed_amount(750).unwrap(), 750);
+        assert_eq!(stream.calculate_streamed_amount(1_000).unwrap(), 1_200);
+    }
+
+    #[test]
+    fn test_step_stream_with_zero_interval() {
+        for stream_type in [StreamType::Step, StreamType::CliffStep] {
+            let stream = StreamBuilder::new()
+                .start_time(0)
+                .stream_type(stream_type)
+                .cliff(0, 0)
+                .rate(100, 0)
+                .build();
+
+            // Rejected at creation
+            assert_eq!(
+                stream.validate().unwrap_err(),
+                StreamFlowError::InvalidVestingSchedule.into()
+            );
+            // And a clean error rather than a panic if one slips through
+            assert!(matches!(
+                stream.calculate_streamed_amount(50),
+                Err(StreamFlowError::DivisionByZero)
+            ));
+        }
+
+        let valid = StreamBuilder::new().stream_type(StreamType::Step).rate(100, 10).build();
+        assert!(valid.validate().is_ok());
+    }
+
+    #[test]
+    fn test_updated_at_advances_on_mutation() {
+        let mut stream = StreamBuilder::new().build();
+        stream.set_created_at(1_000);
+        assert_eq!(stream.metadata.updated_at, 1_000);
+
+        stream.record_pause(1_200).unwrap();
+        assert_eq!(stream.metadata.updated_at, 1_200);
+        stream.record_resume(1_500).unwrap();
+        assert_eq!(stream.metadata.updated_at, 1_500);
+
+        // A stale clock reading never moves it backwards
+        stream.touch(1_400);
+        assert_eq!(stream.metadata.updated_at, 1_500);
+        assert!(stream.validate().is_ok());
+    }
+
+    fn rate_updatable_step_stream() -> Stream {
+        let mut stream = StreamBuilder::new()
+            .amount(1_000)
+            .start_time(1_000)
+            .duration(100)
+            .stream_type(StreamType::Step)
+            .rate(100, 10)
+            .build();
+        stream.can_update_rate = true;
+        stream
+    }
+
+    #[test]
+    fn test_update_rate_before_start_recomputes_end_time() {
+        let mut stream = rate_updatable_step_stream();
+        stream.update_rate(200, 10, 500).unwrap();
+
+        assert_eq!(stream.stream_type, StreamType::Step);
+        assert_eq!(stream.end_time, 1_050);
+        assert_eq!(stream.calculate_streamed_amount(1_049).unwrap(), 800);
+        assert_eq!(stream.calculate_streamed_amount(1_050).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn test_update_rate_mid_stream_applies_to_remaining_balance() {
+        let mut stream = rate_updatable_step_stream();
+        assert_eq!(stream.calculate_streamed_amount(1_035).unwrap(), 300);
+
+        stream.update_rate(50, 20, 1_035).unwrap();
+
+        // 700 left at 50 per 20s: 14 steps from now
+        assert_eq!(stream.end_time, 1_035 + 280);
+        assert_eq!(stream.calculate_streamed_amount(1_035).unwrap(), 300);
+        assert_eq!(stream.calculate_streamed_amount(1_054).unwrap(), 300);
+        assert_eq!(stream.calculate_streamed_amount(1_055).unwrap(), 350);
+        assert_eq!(stream.calculate_streamed_amount(1_314).unwrap(), 950);
+        assert_eq!(stream.calculate_streamed_amount(1_315).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn test_update_rate_rejections() {
+        let mut stream = rate_updatable_step_stream();
+        assert_eq!(
+            stream.update_rate(100, 0, 500).unwrap_err(),
+            ErrorCode::InvalidTimeParams.into()
+        );
+
+        let mut cancelled = rate_updatable_step_stream();
+        cancelled.status = StreamStatus::Cancelled;
+        assert_eq!(
+            cancelled.update_rate(100, 10, 500).unwrap_err(),
+            StreamError::StreamCanceled.into()
+        );
+
+        let mut locked = rate_updatable_step_stream();
+        locked.can_update_rate = false;
+        assert_eq!(
+            locked.update_rate(100, 10, 500).unwrap_err(),
+            StreamFlowError::StreamModificationNotAllowed.into()
+        );
+
+        let mut linear = rate_updatable_step_stream();
+        linear.stream_type = StreamType::Linear;
+        assert_eq!(
+            linear.update_rate(100, 10, 500).unwrap_err(),
+            StreamFlowError::InvalidStreamType.into()
+        );
+    }
+
+    #[test]
+    fn test_rate_update_requires_can_update_rate() {
+        let stream = StreamBuilder::new().start_time(100).build();
+        assert_eq!(
+            stream.ensure_rate_update_allowed(50).unwrap_err(),
+            StreamFlowError::StreamModificationNotAllowed.into()
+        );
+    }
+
+    #[test]
+    fn test_rate_lock_after_start() {
+        let mut stream = StreamBuilder::new().start_time(100).build();
+        stream.can_update_rate = true;
+
+        // Without the lock, updates are allowed at any time
+        assert!(stream.ensure_rate_update_allowed(50).is_ok());
+        assert!(stream.ensure_rate_update_allowed(150).is_ok());
+
+        stream.lock_rate_after_start = true;
+        assert!(stream.ensure_rate_update_allowed(99).is_ok());
+        assert_eq!(
+            stream.ensure_rate_update_allowed(100).unwrap_err(),
+            StreamFlowError::StreamModificationNotAllowed.into()
+        );
+        assert_eq!(
+            stream.ensure_rate_update_allowed(150).unwrap_err(),
+            StreamFlowError::StreamModificationNotAllowed.into()
+        );
+    }
+
+    #[test]
+    fn test_withdrawn_within_vested_invariant() {
+        let mut stream = StreamBuilder::new().amount(1000).start_time(100).duration(100).build();
+        stream.withdrawn_amount = 500;
+        assert!(stream.check_withdrawn_within_vested(150).is_ok());
+
+        // One token beyond what has vested is caught
+        stream.withdrawn_amount = 501;
+        assert!(matches!(
+            stream.check_withdrawn_within_vested(150),
+            Err(StreamFlowError::DataIntegrityCheckFailed)
+        ));
+    }
+
+    #[test]
+    fn test_escrow_surplus() {
+        let mut stream = StreamBuilder::new().amount(1000).build();
+        stream.withdrawn_amount = 300;
+
+        // 50 tokens were transferred into the escrow from outside
+        assert_eq!(stream.escrow_obligation(), 700);
+        assert_eq!(stream.escrow_surplus(750), 50);
+        assert_eq!(stream.escrow_surplus(700), 0);
+        assert_eq!(stream.escrow_surplus(600), 0);
+
+        // After a cancel only parked payouts remain owed
+        stream.status = StreamStatus::Cancelled;
+        stream.parked_amount = 100;
+        assert_eq!(stream.escrow_surplus(150), 50);
+    }
+
+    #[test]
+    fn test_paused_time_does_not_vest() {
+        let mut stream = StreamBuilder::new().amount(1000).start_time(100).duration(100).build();
+
+        // Paused from 130 to 150
+        stream.record_pause(130).unwrap();
+        assert_eq!(stream.calculate_streamed_amount(130).unwrap(), 300);
+        assert_eq!(stream.calculate_streamed_amount(145).unwrap(), 300);
+        stream.record_resume(150).unwrap();
+        assert_eq!(stream.total_paused_duration, 20);
+        assert_eq!(stream.paused_at, None);
+
+        // 70s wall clock but only 50s of vesting
+        assert_eq!(stream.calculate_streamed_amount(170).unwrap(), 500);
+        assert_eq!(stream.withdrawable_amount(170).unwrap(), 500);
+
+        // The schedule finishes 20s late
+        assert_eq!(stream.calculate_streamed_amount(200).unwrap(), 800);
+        assert_eq!(stream.calculate_streamed_amount(220).unwrap(), 1000);
+    }
+
+    #[test]
+    fn test_pause_bookkeeping_rejects_mismatched_calls() {
+        let mut stream = StreamBuilder::new().build();
+        assert_eq!(stream.record_resume(10).unwrap_err(), StreamError::StreamNotPaused.into());
+        stream.record_pause(10).unwrap();
+        assert_eq!(stream.record_pause(11).unwrap_err(), StreamError::StreamAlreadyPaused.into());
+    }
+
+    #[test]
+    fn test_derive_status_branches() {
+        let scheduled = StreamBuilder::new()
+            .amount(1000)
+            .start_time(100)
+            .duration(100)
+            .status(StreamStatus::Scheduled)
+            .build();
+        assert_eq!(scheduled.derive_status(50).unwrap(), StreamStatus::Scheduled);
+        assert_eq!(scheduled.derive_status(150).unwrap(), StreamStatus::Streaming);
+        assert_eq!(scheduled.derive_status(200).unwrap(), StreamStatus::Completed);
+
+        let mut withdrawn = scheduled.clone();
+        withdrawn.status = StreamStatus::Streaming;
+        withdrawn.withdrawn_amount = 1000;
+        assert_eq!(withdrawn.derive_status(150).unwrap(), StreamStatus::Completed);
+
+        let mut paused = scheduled.clone();
+        paused.status = StreamStatus::Paused;
+        paused.paused_at = Some(120);
+        assert_eq!(paused.derive_status(150).unwrap(), StreamStatus::Paused);
+
+        let mut cancelled = scheduled.clone();
+        cancelled.status = StreamStatus::Cancelled;
+        assert_eq!(cancelled.derive_status(150).unwrap(), StreamStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_derive_status_cliff_before_cliff() {
+        let stream = StreamBuilder::new()
+            .amount(1000)
+            .start_time(100)
+            .duration(100)
+            .stream_type(StreamType::Cliff)
+            .cliff(150, 200)
+            .build();
+
+        // Started but nothing unlocked until the cliff
+        assert_eq!(stream.derive_status(120).unwrap(), StreamStatus::Streaming);
+        assert_eq!(stream.withdrawable_amount(120).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_derive_status_rejects_unreachable_status() {
+        let completed = StreamBuilder::new()
+            .start_time(100)
+            .duration(100)
+            .status(StreamStatus::Completed)
+            .build();
+        assert_eq!(
+            completed.derive_status(50).unwrap_err(),
+            StateError::InvalidStatusTransition.into()
+        );
+    }
+
+    #[test]
+    fn test_projected_completion_linear() {
+        let stream = StreamBuilder::new().amount(1000).start_time(100).duration(100).build();
+        assert_eq!(stream.projected_completion(150), 200);
+    }
+
+    #[test]
+    fn test_projected_completion_includes_pauses() {
+        let mut stream = StreamBuilder::new().amount(1000).start_time(100).duration(100).build();
+        stream.total_paused_duration = 30;
+        assert_eq!(stream.projected_completion(150), 230);
+
+        // An ongoing pause keeps pushing the date out
+        stream.paused_at = Some(160);
+        assert_eq!(stream.projected_completion(170), 240);
+        assert_eq!(stream.projected_completion(180), 250);
+
+        // Resuming fixes the date, which matches when vesting actually completes
+        stream.record_resume(180).unwrap();
+        assert_eq!(stream.projected_completion(180), 250);
+        assert!(stream.calculate_streamed_amount(249).unwrap() < 1000);
+        assert_eq!(stream.calculate_streamed_amount(250).unwrap(), 1000);
+    }
+
+    #[test]
+    fn test_projected_completion_piecewise() {
+        // 1000 in steps of 300 every 10s: the fourth step at t=140 completes it
+        let step = StreamBuilder::new()
+            .amount(1000)
+            .start_time(100)
+            .duration(1000)
+            .stream_type(StreamType::Step)
+            .rate(300, 10)
+            .build();
+        assert_eq!(step.projected_completion(100), 140);
+        assert_eq!(step.calculate_streamed_amount(139).unwrap(), 900);
+        assert_eq!(step.calculate_streamed_amount(140).unwrap(), 1000);
+
+        // Cliff lump of 400, then 200 every 50s from the cliff
+        let cliff_step = StreamBuilder::new()
+            .amount(1000)
+            .start_time(100)
+            .duration(1000)
+            .stream_type(StreamType::CliffStep)
+            .cliff(300, 400)
+            .rate(200, 50)
+            .build();
+        assert_eq!(cliff_step.projected_completion(100), 450);
+
+        let stalled = StreamBuilder::new().stream_type(StreamType::Step).rate(0, 10).build();
+        assert_eq!(stalled.projected_completion(100), i64::MAX);
+    }
+
+    #[test]
+    fn test_calculator_overflow_surfaces_unified_error() {
+        let step = StreamBuilder::new()
+            .amount(u64::MAX)
+            .stream_type(StreamType::Step)
+            .rate(u64::MAX, 1)
+            .build();
+        assert!(matches!(
+            step.calculate_streamed_amount(102),
+            Err(StreamFlowError::ArithmeticOverflow)
+        ));
+
+        let mut huge = StreamBuilder::new()
+            .amount(u64::MAX)
+            .start_time(0)
+            .duration(i64::MAX / 2)
+            .build();
+        huge.interest_rate_bps = 10000;
+        assert!(matches!(
+            huge.accrued_interest(i64::MAX / 2),
+            Err(StreamFlowError::ArithmeticOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_fees_round_down() {
+        let stream = StreamBuilder::new().fees(50, 100).build(); // 0.5% and 1%
+
+        // 199 * 0.5% = 0.995 and 199 * 1% = 1.99 both floor
+        assert_eq!(stream.calculate_fees(199).unwrap(), (0, 1));
+        assert_eq!(stream.calculate_fees(10_000).unwrap(), (50, 100));
+    }
+
+    #[test]
+    fn test_partner_fee_waits_for_activation_amount() {
+        let mut stream = StreamBuilder::new().amount(100_000).fees(0, 100).build();
+        stream.partner_fee_activation_amount = 20_000;
+
+        // Early withdrawals, including the one crossing the threshold, pay no partner fee
+        for _ in 0..3 {
+            assert_eq!(stream.calculate_fees(10_000).unwrap(), (0, 0));
+            stream.withdrawn_amount += 10_000;
+            stream.update_partner_fee_activation();
+        }
+        assert!(stream.partner_fee_active);
+
+        // Subsequent withdrawals are charged
+        assert_eq!(stream.calculate_fees(10_000).unwrap(), (0, 100));
+    }
+
+    #[test]
+    fn test_fee_recipient_equal_to_recipient_rejected() {
+        let mut stream = StreamBuilder::new().build();
+        let config = FeeConfig {
+            fee_percentage: 50,
+            fee_recipient: Some(stream.recipient),
+            ..Default::default()
+        };
+        assert_eq!(
+            stream.apply_fee_config(&config).unwrap_err(),
+            StreamFlowError::InvalidFeeConfiguration.into()
+        );
+        assert_eq!(stream.fee_percentage, 0);
+    }
+
+    #[test]
+    fn test_partner_fee_recipient_equal_to_sender_rejected() {
+        let mut stream = StreamBuilder::new().build();
+        let config = FeeConfig {
+            partner_fee_percentage: 100,
+            partner_fee_recipient: Some(stream.sender),
+            ..Default::default()
+        };
+        assert_eq!(
+            stream.apply_fee_config(&config).unwrap_err(),
+            StreamFlowError::InvalidFeeConfiguration.into()
+        );
+    }
+
+    #[test]
+    fn test_nonzero_fee_requires_recipient() {
+        let mut stream = StreamBuilder::new().build();
+        let platform_only = FeeConfig {
+            fee_percentage: 50,
+            ..Default::default()
+        };
+        assert_eq!(
+            stream.apply_fee_config(&platform_only).unwrap_err(),
+            StreamFlowError::InvalidFeeConfiguration.into()
+        );
+
+        let partner_only = FeeConfig {
+            partner_fee_percentage: 100,
+            fee_recipient: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+        assert_eq!(
+            stream.apply_fee_config(&partner_only).unwrap_err(),
+            StreamFlowError::InvalidFeeConfiguration.into()
+        );
+
+        // The override does not excuse a fee with nowhere to go
+        let overridden = FeeConfig {
+            fee_percentage: 50,
+            allow_self_routing: true,
+            ..Default::default()
+        };
+        assert!(stream.apply_fee_config(&overridden).is_err());
+    }
+
+    #[test]
+    fn test_fee_above_full_amount_rejected() {
+        let mut stream = StreamBuilder::new().build();
+        let config = FeeConfig {
+            fee_percentage: MAX_BPS + 1,
+            fee_recipient: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+        assert_eq!(
+            stream.apply_fee_config(&config).unwrap_err(),
+            StreamFlowError::InvalidFeeConfiguration.into()
+        );
+    }
+
+    #[test]
+    fn test_fee_recipient_without_fee_is_accepted() {
+        let mut stream = StreamBuilder::new().build();
+        let config = FeeConfig {
+            fee_recipient: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+        assert!(stream.apply_fee_config(&config).is_ok());
+        assert!(stream.apply_fee_config(&FeeConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn test_self_fee_routing_allowed_with_override() {
+        let mut stream = StreamBuilder::new().build();
+        let config = FeeConfig {
+            fee_percentage: 50,
+            fee_recipient: Some(stream.recipient),
+            partner_fee_percentage: 100,
+            partner_fee_recipient: Some(stream.sender),
+            allow_self_routing: true,
+        };
+        stream.apply_fee_config(&config).unwrap();
+        assert_eq!(stream.fee_recipient, Some(stream.recipient));
+        assert_eq!(stream.partner_fee_recipient, Some(stream.sender));
+    }
+
+    #[test]
+    fn test_distinct_fee_recipients_accepted() {
+        let mut stream = StreamBuilder::new().build();
+        let platform = Pubkey::new_unique();
+        let config = FeeConfig {
+            fee_percentage: 50,
+            fee_recipient: Some(platform),
+            ..Default::default()
+        };
+        stream.apply_fee_config(&config).unwrap();
+        assert_eq!(stream.fee_percentage, 50);
+        assert_eq!(stream.fee_recipient, Some(platform));
+    }
+
+    fn capped_fees(fee_percentage: u16, partner_fee_percentage: u16) -> FeeConfig {
+        FeeConfig {
+            fee_percentage,
+            fee_recipient: Some(Pubkey::new_unique()),
+            partner_fee_percentage,
+            partner_fee_recipient: Some(Pubkey::new_unique()),
+            allow_self_routing: false,
+        }
+    }
+
+    #[test]
+    fn test_fees_at_cap_are_accepted() {
+        let mut stream = StreamBuilder::new().build();
+        stream.apply_fee_config(&capped_fees(MAX_PLATFORM_FEE_BPS, 0)).unwrap();
+        stream.apply_fee_config(&capped_fees(300, 200)).unwrap();
+        assert!(FeeConfig::default().validate_cap().is_ok());
+    }
+
+    #[test]
+    fn test_fees_over_cap_are_rejected() {
+        let mut stream = StreamBuilder::new().build();
+        for config in [
+            capped_fees(MAX_PLATFORM_FEE_BPS + 1, 0),
+            capped_fees(300, 201),
+            capped_fees(MAX_BPS, MAX_BPS),
+        ] {
+            assert_eq!(
+                stream.apply_fee_config(&config).unwrap_err(),
+                StateError::PlatformFeeExceedsMaximum.into()
+            );
+        }
+        assert_eq!(stream.fee_percentage, 0);
+        assert_eq!(
+            capped_fees(u16::MAX, u16::MAX).validate_cap().unwrap_err(),
+            StateError::PlatformFeeExceedsMaximum.into()
+        );
+    }
+
+    #[test]
+    fn test_scheduled_fee_applies_only_after_effective_time() {
+        let mut stream = StreamBuilder::new().fees(50, 0).build();
+        stream.fee_recipient = Some(Pubkey::new_unique());
+        let effective_at = 100 + MIN_FEE_CHANGE_NOTICE;
+        stream.schedule_fee_change(100, effective_at, 100).unwrap();
+
+        // Old fee until the effective time
+        assert_eq!(stream.apply_pending_fee(effective_at - 1), None);
+        assert_eq!(stream.calculate_fees(10_000).unwrap(), (50, 0));
+
+        // New fee from the effective time on
+        assert_eq!(stream.apply_pending_fee(effective_at), Some((50, 100)));
+        assert_eq!(stream.calculate_fees(10_000).unwrap(), (100, 0));
+        assert_eq!(stream.pending_fee, None);
+        assert_eq!(stream.apply_pending_fee(effective_at + 100), None);
+    }
+
+    #[test]
+    fn test_fee_change_must_be_announced_in_advance() {
+        let mut stream = StreamBuilder::new().build();
+        stream.fee_recipient = Some(Pubkey::new_unique());
+        assert_eq!(
+            stream.schedule_fee_change(100, 100, 100).unwrap_err(),
+            ErrorCode::InvalidTimeParams.into()
+        );
+        // A change needs the full notice period
+        assert_eq!(
+            stream.schedule_fee_change(100, 100 + MIN_FEE_CHANGE_NOTICE - 1, 100).unwrap_err(),
+            ErrorCode::InvalidTimeParams.into()
+        );
+        assert!(stream.schedule_fee_change(100, 100 + MIN_FEE_CHANGE_NOTICE, 100).is_ok());
+    }
+
+    #[test]
+    fn test_scheduled_fee_is_capped_with_partner_fee() {
+        let mut stream = StreamBuilder::new().fees(50, 200).build();
+        stream.fee_recipient = Some(Pubkey::new_unique());
+        let effective_at = 100 + MIN_FEE_CHANGE_NOTICE;
+
+        assert!(stream.schedule_fee_change(300, effective_at, 100).is_ok());
+        assert_eq!(
+            stream.schedule_fee_change(301, effective_at, 100).unwrap_err(),
+            StateError::PlatformFeeExceedsMaximum.into()
+        );
+        assert_eq!(
+            stream.schedule_fee_change(MAX_BPS, effective_at, 100).unwrap_err(),
+            StateError::PlatformFeeExceedsMaximum.into()
+        );
+    }
+
+    #[test]
+    fn test_interest_disabled_by_default() {
+        let stream = StreamBuilder::new().build();
+        assert_eq!(stream.accrued_interest(200).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_interest_over_holding_period() {
+        // One year linear stream, claimed once at the end
+        let year = SECONDS_PER_YEAR as i64;
+        let mut stream = StreamBuilder::new()
+            .amount(1_000_000)
+            .start_time(0)
+            .duration(year)
+            .build();
+        stream.interest_rate_bps = 1000; // 10% APR
+
+        // Average unclaimed balance over the year is 500_000, so 10% of that
+        assert_eq!(stream.accrued_interest(year).unwrap(), 50_000);
+
+        // After a claim at mid-year, interest only accrues from then on
+        stream.withdrawn_amount = 500_000;
+        stream.last_withdrawn_at = year / 2;
+        assert_eq!(stream.accrued_interest(year / 2).unwrap(), 0);
+        assert_eq!(stream.accrued_interest(year).unwrap(), 12_500);
+    }
+
+    #[test]
+    fn test_split_withdrawal_sums_to_gross() {
+        let stream = StreamBuilder::new().fees(33, 77).build();
+
+        for gross in [0, 1, 99, 101, 303, 9_999, 10_001, 123_456_789, u64::MAX / 10_000] {
+            let (net, platform_fee, partner_fee) = stream.split_withdrawal(gross).unwrap();
+            assert_eq!(net + platform_fee + partner_fee, gross);
+            assert_eq!(platform_fee, gross * 33 / 10_000);
+            assert_eq!(partner_fee, gross * 77 / 10_000);
+        }
+    }
+
+    #[test]
+    fn test_withdrawable_amounts_on_unified_stream() {
+        let mut stream = StreamBuilder::new()
+            .amount(1000)
+            .start_time(100)
+            .duration(100)
+            .status(StreamStatus::Scheduled)
+            .build();
+
+        // The schedule vests regardless of status, but nothing is withdrawable
+        // until the stream is streaming
+        assert_eq!(stream.calculate_withdrawable_amount(150).unwrap(), 500);
+        assert_eq!(stream.withdrawable_amount(150).unwrap(), 0);
+
+        assert!(!stream.activate_if_started(99));
+        assert!(stream.activate_if_started(150));
+        assert_eq!(stream.status, StreamStatus::Streaming);
+        assert_eq!(stream.withdrawable_amount(150).unwrap(), 500);
+
+        // Both account for what has been withdrawn
+        stream.withdrawn_amount = 300;
+        assert_eq!(stream.calculate_withdrawable_amount(150).unwrap(), 200);
+        assert_eq!(stream.withdrawable_amount(150).unwrap(), 200);
+        assert_eq!(stream.calculate_withdrawable_amount(250).unwrap(), 700);
+    }
+
+    #[test]
+    fn test_activate_ignores_non_scheduled_streams() {
+        let mut stream = StreamBuilder::new().status(StreamStatus::Paused).build();
+        assert!(!stream.activate_if_started(150));
+        assert_eq!(stream.status, StreamStatus::Paused);
+        assert_eq!(stream.withdrawable_amount(150).unwrap(), 0);
+        assert_eq!(stream.calculate_withdrawable_amount(150).unwrap(), 500);
+    }
+
+    #[test]
+    fn test_set_name() {
+        let mut stream = StreamBuilder::new().build();
+        stream.set_name("payroll").unwrap();
+        assert_eq!(&stream.name[..7], b"payroll");
+        assert!(stream.name[7..].iter().all(|b| *b == 0));
+
+        assert!(matches!(
+            stream.set_name(&"x".repeat(65)),
+            Err(StreamFlowError::StreamMetadataTooLarge)
+        ));
+        assert_eq!(&stream.name[..7], b"payroll");
+    }
+
+    #[test]
+    fn test_cliff_remainder_vests_from_cliff_time() {
+        // 400 at the cliff, the other 600 linear over [150, 200]
+        let stream = StreamBuilder::new()
+            .amount(1000)
+            .start_time(100)
+            .duration(100)
+            .stream_type(StreamType::Cliff)
+            .cliff(150, 400)
+            .build();
+
+        assert_eq!(stream.calculate_streamed_amount(149).unwrap(), 0);
+        assert_eq!(stream.calculate_streamed_amount(150).unwrap(), 400);
+        assert_eq!(stream.calculate_streamed_amount(175).unwrap(), 400 + 300);
+        assert_eq!(stream.calculate_streamed_amount(200).unwrap(), 1000);
+    }
+
+    #[test]
+    fn test_top_up_spreads_over_existing_schedule() {
+        // 1000 over [100, 200], half withdrawn at the midpoint
+        let mut stream = StreamBuilder::new().amount(1_000).withdrawn(500).build();
+        stream.top_up(1_000, None, 150).unwrap();
+
+        assert_eq!(stream.deposited_amount, 2_000);
+        assert_eq!(stream.end_time, 200);
+        assert_eq!(stream.calculate_withdrawable_amount(150).unwrap(), 500);
+        assert_eq!(stream.calculate_withdrawable_amount(200).unwrap(), 1_500);
+    }
+
+    #[test]
+    fn test_top_up_with_extension_vests_over_added_window() {
+        let mut stream = StreamBuilder::new().amount(1_000).withdrawn(500).build();
+        stream.top_up(1_000, Some(100), 150).unwrap();
+
+        // Nothing already vested changes; the other 1500 vests over [150, 300]
+        assert_eq!(stream.end_time, 300);
+        assert_eq!(stream.calculate_withdrawable_amount(150).unwrap(), 0);
+        assert_eq!(stream.calculate_withdrawable_amount(225).unwrap(), 750);
+        assert_eq!(stream.calculate_withdrawable_amount(300).unwrap(), 1_500);
+    }
+
+    #[test]
+    fn test_top_up_with_extension_recomputes_step_rate() {
+        // 100 every 10s from 100, so 1000 is released by 200
+        let mut stream = StreamBuilder::new()
+            .amount(1_000)
+            .stream_type(StreamType::Step)
+            .rate(100, 10)
+            .build();
+        stream.top_up(1_000, Some(100), 150).unwrap();
+
+        assert_eq!(stream.stream_type, StreamType::CliffStep);
+        assert_eq!(stream.rate_amount, 100);
+        assert_eq!(stream.end_time, 300);
+        assert_eq!(stream.calculate_streamed_amount(150).unwrap(), 500);
+        assert_eq!(stream.calculate_streamed_amount(225).unwrap(), 1_200);
+        assert_eq!(stream.calculate_streamed_amount(300).unwrap(), 2_000);
+    }
+
+    #[test]
+    fn test_top_up_overflow_is_rejected() {
+        let mut stream = StreamBuilder::new().amount(u64::MAX).build();
+        assert_eq!(
+            stream.top_up(1, None, 150).unwrap_err(),
+            StreamError::MathOverflow.into()
+        );
+        assert_eq!(stream.deposited_amount, u64::MAX);
+    }
+
+    fn monthly_step_stream() -> Stream {
+        // 250 every 30s from 0, so 1000 is released by 120
+        StreamBuilder::new()
+            .amount(1_000)
+            .start_time(0)
+            .duration(120)
+            .stream_type(StreamType::Step)
+            .rate(250, 30)
+            .build()
+    }
+
+    #[test]
+    fn test_step_release_at_interval_boundaries() {
+        let stream = monthly_step_stream();
+        assert!(stream.check_step_schedule().is_ok());
+
+        assert_eq!(stream.calculate_streamed_amount(29).unwrap(), 0);
+        assert_eq!(stream.calculate_streamed_amount(30).unwrap(), 250);
+        assert_eq!(stream.calculate_streamed_amount(59).unwrap(), 250);
+        assert_eq!(stream.calculate_streamed_amount(60).unwrap(), 500);
+        assert_eq!(stream.calculate_streamed_amount(119).unwrap(), 750);
+        assert_eq!(stream.calculate_streamed_amount(120).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn test_step_releases_full_deposit_at_end_time() {
+        // 1000 in steps of 300: the steps alone would stop at 900 until t=40
+        let mut stream = StreamBuilder::new()
+            .amount(1_000)
+            .start_time(0)
+            .duration(35)
+            .stream_type(StreamType::Step)
+            .rate(300, 10)
+            .build();
+        assert_eq!(stream.calculate_streamed_amount(34).unwrap(), 900);
+        assert_eq!(stream.calculate_streamed_amount(35).unwrap(), 1_000);
+        assert_eq!(stream.calculate_streamed_amount(10_000).unwrap(), 1_000);
+
+        // Never more than the deposit, however far past the end
+        stream.withdrawn_amount = 400;
+        assert_eq!(stream.calculate_withdrawable_amount(i64::MAX).unwrap(), 600);
+
+        let cliff_step = StreamBuilder::new()
+            .amount(1_000)
+            .start_time(0)
+            .duration(220)
+            .stream_type(StreamType::CliffStep)
+            .cliff(100, 400)
+            .rate(200, 40)
+            .build();
+        assert!(cliff_step.check_step_schedule().is_ok());
+        assert_eq!(cliff_step.calculate_streamed_amount(219).unwrap(), 800);
+        assert_eq!(cliff_step.calculate_streamed_amount(220).unwrap(), 1_000);
+        assert_eq!(cliff_step.calculate_streamed_amount(500).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn test_inconsistent_step_schedule_is_rejected() {
+        // Steps would release everything by 120, long before `end_time`
+        let early = StreamBuilder::new()
+            .amount(1_000)
+            .start_time(0)
+            .duration(1_000)
+            .stream_type(StreamType::Step)
+            .rate(250, 30)
+            .build();
+        // Steps would still owe 250 at `end_time`
+        let late = StreamBuilder::new()
+            .amount(1_000)
+            .start_time(0)
+            .duration(90)
+            .stream_type(StreamType::Step)
+            .rate(250, 30)
+            .build();
+        let stalled = StreamBuilder::new().stream_type(StreamType::Step).rate(0, 10).build();
+
+        for stream in [early, late, stalled] {
+            assert!(matches!(
+                stream.check_step_schedule(),
+                Err(StreamFlowError::StreamRateCalculationFailed)
+            ));
+        }
+
+        // Within a step of `end_time` is close enough
+        let mut rounded = monthly_step_stream();
+        rounded.end_time = 149;
+        assert!(rounded.check_step_schedule().is_ok());
+        assert!(StreamBuilder::new().build().check_step_schedule().is_ok());
+    }
+
+    #[test]
+    fn test_pause_and_resume_follow_status_transitions() {
+        let mut stream = StreamBuilder::new().status(StreamStatus::Scheduled).build();
+        stream.pause(50).unwrap();
+        assert_eq!(stream.status, StreamStatus::Paused);
+        assert_eq!(
+            stream.pause(60).unwrap_err(),
+            StateError::InvalidStatusTransition.into()
+        );
+        // Still before the start, so back to scheduled
+        stream.resume(70).unwrap();
+        assert_eq!(stream.status, StreamStatus::Scheduled);
+
+        stream.pause(120).unwrap();
+        stream.resume(150).unwrap();
+        assert_eq!(stream.status, StreamStatus::Streaming);
+        assert_eq!(stream.total_paused_duration, 50);
+    }
+
+    #[test]
+    fn test_terminal_statuses_reject_transitions() {
+        for status in [StreamStatus::Completed, StreamStatus::Cancelled] {
+            let mut stream = StreamBuilder::new().status(status).build();
+            assert_eq!(stream.pause(150).unwrap_err(), StateError::InvalidStatusTransition.into());
+            assert_eq!(stream.resume(150).unwrap_err(), StateError::InvalidStatusTransition.into());
+            assert_eq!(
+                stream.check_transition(StreamStatus::Cancelled).unwrap_err(),
+                StateError::InvalidStatusTransition.into()
+            );
+            assert_eq!(stream.status, status);
+            assert!(stream.paused_at.is_none());
+        }
+
+        let streaming = StreamBuilder::new().build();
+        assert!(streaming.check_transition(StreamStatus::Cancelled).is_ok());
+    }
+
+    #[test]
+    fn test_force_resume_after_pause_limit() {
+        let mut stream = StreamBuilder::new().build();
+        stream.max_pause_duration = 30;
+        stream.pause(120).unwrap();
+
+        assert_eq!(stream.force_resume(140).unwrap_err(), StreamError::PauseLimitNotReached.into());
+        // Exactly at the limit is still allowed to be paused
+        assert_eq!(stream.force_resume(150).unwrap_err(), StreamError::PauseLimitNotReached.into());
+        assert_eq!(stream.status, StreamStatus::Paused);
+
+        stream.force_resume(151).unwrap();
+        assert_eq!(stream.status, StreamStatus::Streaming);
+        assert_eq!(stream.total_paused_duration, 31);
+        assert_eq!(stream.force_resume(200).unwrap_err(), StreamError::StreamNotPaused.into());
+    }
+
+    #[test]
+    fn test_force_resume_without_pause_limit() {
+        let mut stream = StreamBuilder::new().build();
+        stream.pause(120).unwrap();
+        assert_eq!(stream.force_resume(i64::MAX).unwrap_err(), StreamError::PauseLimitNotReached.into());
+        // The sender can still resume at any time
+        stream.resume(130).unwrap();
+    }
+}
+```
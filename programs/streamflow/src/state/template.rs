@@ -0,0 +1,295 @@
+use anchor_lang::prelude::*;
+use super::{FeeConfig, Stream, StreamStatus, StreamType};
+use crate::errors::{StreamFlowError, StreamFlowResult};
+
+/// Default parameters for streams created in bulk, e.g. when onboarding hires
+#[account]
+#[derive(Debug)]
+pub struct StreamTemplate {
+    /// Authority allowed to create streams from the template
+    pub authority: Pubkey,
+    /// Identifier distinguishing the authority's templates in the PDA seeds
+    pub template_id: u64,
+    /// Token streamed by instances of the template
+    pub mint: Pubkey,
+    /// Tokens deposited into each stream
+    pub deposit_amount: u64,
+    /// Seconds from start to end of each stream
+    pub duration: i64,
+    /// Vesting curve of each stream
+    pub stream_type: StreamType,
+    /// Seconds from start to the cliff; ignored when `cliff_amount` is zero
+    pub cliff_offset: i64,
+    /// Tokens released at the cliff
+    pub cliff_amount: u64,
+    /// Step size for step-based curves
+    pub rate_amount: u64,
+    /// Step interval for step-based curves
+    pub rate_interval_in_seconds: u64,
+    pub cancelable_by_sender: bool,
+    pub cancelable_by_recipient: bool,
+    /// Platform fee (basis points)
+    pub fee_percentage: u16,
+    pub fee_recipient: Option<Pubkey>,
+    /// Partner fee (basis points)
+    pub partner_fee_percentage: u16,
+    pub partner_fee_recipient: Option<Pubkey>,
+    /// Lamports sent to a recipient on onboarding so they can pay for claims
+    pub onboarding_lamports: u64,
+    /// Streams created from this template that have not been closed
+    pub active_stream_count: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+/// Defaults supplied when creating a template
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TemplateParams {
+    pub deposit_amount: u64,
+    pub duration: i64,
+    pub stream_type: StreamType,
+    pub cliff_offset: i64,
+    pub cliff_amount: u64,
+    pub rate_amount: u64,
+    pub rate_interval_in_seconds: u64,
+    pub cancelable_by_sender: bool,
+    pub cancelable_by_recipient: bool,
+    pub fee_percentage: u16,
+    pub fee_recipient: Option<Pubkey>,
+    pub partner_fee_percentage: u16,
+    pub partner_fee_recipient: Option<Pubkey>,
+    pub onboarding_lamports: u64,
+}
+
+impl StreamTemplate {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // authority
+        8 + // template_id
+        32 + // mint
+        8 + // deposit_amount
+        8 + // duration
+        1 + // stream_type
+        8 + // cliff_offset
+        8 + // cliff_amount
+        8 + // rate_amount
+        8 + // rate_interval_in_seconds
+        1 + // cancelable_by_sender
+        1 + // cancelable_by_recipient
+        2 + // fee_percentage
+        33 + // fee_recipient (Option<Pubkey>)
+        2 + // partner_fee_percentage
+        33 + // partner_fee_recipient (Option<Pubkey>)
+        8 + // onboarding_lamports
+        8 + // active_stream_count
+        1; // bump
+
+    /// Fee routing carried by the template
+    pub fn fee_config(&self) -> FeeConfig {
+        FeeConfig {
+            fee_percentage: self.fee_percentage,
+            fee_recipient: self.fee_recipient,
+            partner_fee_percentage: self.partner_fee_percentage,
+            partner_fee_recipient: self.partner_fee_recipient,
+            allow_self_routing: false,
+        }
+    }
+
+    /// Count a stream created from the template
+    pub fn record_stream(&mut self) -> StreamFlowResult<()> {
+        self.active_stream_count = self
+            .active_stream_count
+            .checked_add(1)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Stop counting a stream once it is closed
+    pub fn release_stream(&mut self) {
+        self.active_stream_count = self.active_stream_count.saturating_sub(1);
+    }
+
+    /// A template can only be deleted once none of its streams remain
+    pub fn ensure_deletable(&self) -> StreamFlowResult<()> {
+        if self.active_stream_count > 0 {
+            return Err(StreamFlowError::CannotDeleteActiveTemplate);
+        }
+        Ok(())
+    }
+
+    /// Overwrite the template's defaults with `params`
+    pub fn configure(&mut self, params: &TemplateParams) {
+        self.deposit_amount = params.deposit_amount;
+        self.duration = params.duration;
+        self.stream_type = params.stream_type.clone();
+        self.cliff_offset = params.cliff_offset;
+        self.cliff_amount = params.cliff_amount;
+        self.rate_amount = params.rate_amount;
+        self.rate_interval_in_seconds = params.rate_interval_in_seconds;
+        self.cancelable_by_sender = params.cancelable_by_sender;
+        self.cancelable_by_recipient = params.cancelable_by_recipient;
+        self.fee_percentage = params.fee_percentage;
+        self.fee_recipient = params.fee_recipient;
+        self.partner_fee_percentage = params.partner_fee_percentage;
+        self.partner_fee_recipient = params.partner_fee_recipient;
+        self.onboarding_lamports = params.onboarding_lamports;
+    }
+
+    /// Copy the template's schedule, permissions and fees onto a stream
+    /// starting at `start_time`. Parties, mint and deposit are set by the caller.
+    pub fn apply_to(&self, stream: &mut Stream, start_time: i64) -> Result<()> {
+        stream.start_time = start_time;
+        stream.end_time = start_time
+            .checked_add(self.duration)
+            .ok_or(StreamFlowError::ArithmeticOverflow)?;
+        stream.last_withdrawn_at = start_time;
+        stream.stream_type = self.stream_type.clone();
+        stream.cliff_amount = self.cliff_amount;
+        stream.cliff_time = if self.cliff_amount > 0 {
+            start_time
+                .checked_add(self.cliff_offset)
+                .ok_or(StreamFlowError::ArithmeticOverflow)?
+        } else {
+            0
+        };
+        stream.rate_amount = self.rate_amount;
+        stream.rate_interval_in_seconds = self.rate_interval_in_seconds;
+        stream.cancelable_by_sender = self.cancelable_by_sender;
+        stream.cancelable_by_recipient = self.cancelable_by_recipient;
+        stream.allow_partial_withdraw = true;
+        stream.status = StreamStatus::Scheduled;
+        stream.apply_fee_config(&self.fee_config())?;
+        Ok(stream.check_step_schedule()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::stream::StreamBuilder;
+
+    fn template() -> StreamTemplate {
+        StreamTemplate {
+            authority: Pubkey::new_unique(),
+            template_id: 0,
+            mint: Pubkey::new_unique(),
+            deposit_amount: 12_000,
+            duration: 1_000,
+            stream_type: StreamType::Cliff,
+            cliff_offset: 250,
+            cliff_amount: 3_000,
+            rate_amount: 0,
+            rate_interval_in_seconds: 0,
+            cancelable_by_sender: true,
+            cancelable_by_recipient: false,
+            fee_percentage: 0,
+            fee_recipient: None,
+            partner_fee_percentage: 0,
+            partner_fee_recipient: None,
+            onboarding_lamports: 0,
+            active_stream_count: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_apply_template_offsets_from_start() {
+        let mut stream = StreamBuilder::new().build();
+        template().apply_to(&mut stream, 5_000).unwrap();
+
+        assert_eq!(stream.start_time, 5_000);
+        assert_eq!(stream.end_time, 6_000);
+        assert_eq!(stream.cliff_time, 5_250);
+        assert_eq!(stream.cliff_amount, 3_000);
+        assert_eq!(stream.stream_type, StreamType::Cliff);
+        assert!(stream.cancelable_by_sender);
+        assert!(!stream.cancelable_by_recipient);
+    }
+
+    #[test]
+    fn test_apply_template_without_cliff() {
+        let mut template = template();
+        template.cliff_amount = 0;
+        let mut stream = StreamBuilder::new().build();
+        template.apply_to(&mut stream, 5_000).unwrap();
+        assert_eq!(stream.cliff_time, 0);
+    }
+
+    #[test]
+    fn test_apply_template_validates_fees() {
+        let mut template = template();
+        template.fee_percentage = 50;
+        let mut stream = StreamBuilder::new().build();
+        assert_eq!(
+            template.apply_to(&mut stream, 5_000).unwrap_err(),
+            StreamFlowError::InvalidFeeConfiguration.into()
+        );
+    }
+
+    #[test]
+    fn test_apply_template_checks_step_schedule() {
+        let mut template = template();
+        template.stream_type = StreamType::Step;
+        template.cliff_amount = 0;
+        template.rate_amount = 1_000;
+        template.rate_interval_in_seconds = 100;
+
+        // 12 steps of 1000 every 100s cannot fit in 1000s
+        let mut stream = StreamBuilder::new().amount(12_000).build();
+        assert_eq!(
+            template.apply_to(&mut stream, 5_000).unwrap_err(),
+            StreamFlowError::StreamRateCalculationFailed.into()
+        );
+
+        template.rate_amount = 1_200;
+        template.apply_to(&mut stream, 5_000).unwrap();
+        assert_eq!(stream.calculate_streamed_amount(5_999).unwrap(), 10_800);
+        assert_eq!(stream.calculate_streamed_amount(6_000).unwrap(), 12_000);
+    }
+
+    #[test]
+    fn test_configured_template_instantiates_stream() {
+        let mut template = template();
+        template.configure(&TemplateParams {
+            deposit_amount: 5_000,
+            duration: 3_600,
+            stream_type: StreamType::Linear,
+            cliff_offset: 0,
+            cliff_amount: 0,
+            rate_amount: 0,
+            rate_interval_in_seconds: 0,
+            cancelable_by_sender: false,
+            cancelable_by_recipient: true,
+            fee_percentage: 0,
+            fee_recipient: None,
+            partner_fee_percentage: 0,
+            partner_fee_recipient: None,
+            onboarding_lamports: 0,
+        });
+
+        let mut stream = StreamBuilder::new().amount(5_000).build();
+        template.apply_to(&mut stream, 10_000).unwrap();
+        template.record_stream().unwrap();
+
+        assert_eq!(stream.end_time, 13_600);
+        assert_eq!(stream.stream_type, StreamType::Linear);
+        assert!(!stream.cancelable_by_sender);
+        assert!(stream.cancelable_by_recipient);
+        assert_eq!(stream.status, StreamStatus::Scheduled);
+        assert_eq!(template.active_stream_count, 1);
+    }
+
+    #[test]
+    fn test_template_with_streams_cannot_be_deleted() {
+        let mut template = template();
+        template.ensure_deletable().unwrap();
+
+        template.record_stream().unwrap();
+        assert!(matches!(
+            template.ensure_deletable(),
+            Err(StreamFlowError::CannotDeleteActiveTemplate)
+        ));
+
+        template.release_stream();
+        template.ensure_deletable().unwrap();
+    }
+}
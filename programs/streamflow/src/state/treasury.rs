@@ -0,0 +1,186 @@
+use anchor_lang::prelude::*;
+use super::constants::MAX_TREASURY_MEMBERS;
+use super::utils::has_treasury_permission;
+use super::{StateError, TreasuryRole};
+
+/// Shared pool of one mint's tokens, held in a treasury-owned escrow and
+/// managed by role
+#[account]
+#[derive(Debug)]
+pub struct Treasury {
+    /// Creator of the treasury; always holds the `Owner` role
+    pub owner: Pubkey,
+    /// Identifier distinguishing the owner's treasuries in the PDA seeds
+    pub treasury_id: u64,
+    /// Token held by the treasury
+    pub mint: Pubkey,
+    /// Escrow token account owned by the treasury PDA
+    pub escrow: Pubkey,
+    /// Members other than the owner and their roles
+    pub members: Vec<(Pubkey, TreasuryRole)>,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Bump seed for the escrow PDA
+    pub escrow_bump: u8,
+}
+
+impl Treasury {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // owner
+        8 + // treasury_id
+        32 + // mint
+        32 + // escrow
+        4 + MAX_TREASURY_MEMBERS * (32 + 1) + // members
+        1 + // bump
+        1; // escrow_bump
+
+    /// Role of `key` in the treasury, if it has one
+    pub fn role_of(&self, key: &Pubkey) -> Option<TreasuryRole> {
+        if *key == self.owner {
+            return Some(TreasuryRole::Owner);
+        }
+        self.members
+            .iter()
+            .find(|(member, _)| member == key)
+            .map(|(_, role)| *role)
+    }
+
+    /// Ensure `authority` holds at least `required`
+    pub fn require_role(&self, authority: &Pubkey, required: TreasuryRole) -> Result<()> {
+        let permitted = self
+            .role_of(authority)
+            .map_or(false, |role| has_treasury_permission(role, required));
+        require!(permitted, StateError::UnauthorizedTreasuryOperation);
+        Ok(())
+    }
+
+    /// Validate a withdrawal of `amount` by `authority` from an escrow holding `held`
+    pub fn authorize_withdrawal(&self, authority: &Pubkey, amount: u64, held: u64) -> Result<()> {
+        self.require_role(authority, TreasuryRole::Admin)?;
+        require!(amount <= held, StateError::InsufficientTreasuryBalance);
+        Ok(())
+    }
+
+    /// Grant `member` a role; only an `Owner` may change membership
+    pub fn add_member(&mut self, authority: &Pubkey, member: Pubkey, role: TreasuryRole) -> Result<()> {
+        self.require_role(authority, TreasuryRole::Owner)?;
+        require!(self.role_of(&member).is_none(), StateError::DuplicateTreasuryMember);
+        require!(
+            self.members.len() < MAX_TREASURY_MEMBERS,
+            StateError::TreasuryMemberLimitExceeded
+        );
+        self.members.push((member, role));
+        Ok(())
+    }
+
+    /// Revoke `member`'s role; only an `Owner` may change membership
+    pub fn remove_member(&mut self, authority: &Pubkey, member: &Pubkey) -> Result<()> {
+        self.require_role(authority, TreasuryRole::Owner)?;
+        let index = self
+            .members
+            .iter()
+            .position(|(key, _)| key == member)
+            .ok_or(StateError::TreasuryMemberNotFound)?;
+        self.members.remove(index);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn treasury(members: Vec<(Pubkey, TreasuryRole)>) -> Treasury {
+        Treasury {
+            owner: Pubkey::new_unique(),
+            treasury_id: 0,
+            mint: Pubkey::new_unique(),
+            escrow: Pubkey::new_unique(),
+            members,
+            bump: 255,
+            escrow_bump: 254,
+        }
+    }
+
+    #[test]
+    fn test_viewer_cannot_withdraw() {
+        let viewer = Pubkey::new_unique();
+        let treasury = treasury(vec![(viewer, TreasuryRole::Viewer)]);
+        assert_eq!(
+            treasury.authorize_withdrawal(&viewer, 100, 1_000).unwrap_err(),
+            StateError::UnauthorizedTreasuryOperation.into()
+        );
+        assert_eq!(
+            treasury.authorize_withdrawal(&Pubkey::new_unique(), 100, 1_000).unwrap_err(),
+            StateError::UnauthorizedTreasuryOperation.into()
+        );
+    }
+
+    #[test]
+    fn test_admin_and_owner_can_withdraw() {
+        let admin = Pubkey::new_unique();
+        let treasury = treasury(vec![(admin, TreasuryRole::Admin)]);
+        treasury.authorize_withdrawal(&admin, 1_000, 1_000).unwrap();
+        treasury.authorize_withdrawal(&treasury.owner, 1, 1_000).unwrap();
+    }
+
+    #[test]
+    fn test_withdraw_beyond_balance_is_rejected() {
+        let admin = Pubkey::new_unique();
+        let treasury = treasury(vec![(admin, TreasuryRole::Admin)]);
+        assert_eq!(
+            treasury.authorize_withdrawal(&admin, 1_001, 1_000).unwrap_err(),
+            StateError::InsufficientTreasuryBalance.into()
+        );
+    }
+
+    #[test]
+    fn test_member_cap() {
+        let mut treasury = treasury(Vec::new());
+        let owner = treasury.owner;
+        for _ in 0..MAX_TREASURY_MEMBERS {
+            treasury.add_member(&owner, Pubkey::new_unique(), TreasuryRole::Member).unwrap();
+        }
+        assert_eq!(
+            treasury.add_member(&owner, Pubkey::new_unique(), TreasuryRole::Member).unwrap_err(),
+            StateError::TreasuryMemberLimitExceeded.into()
+        );
+
+        // Removing a member frees a slot
+        let removed = treasury.members[0].0;
+        treasury.remove_member(&owner, &removed).unwrap();
+        assert_eq!(treasury.role_of(&removed), None);
+        treasury.add_member(&owner, Pubkey::new_unique(), TreasuryRole::Viewer).unwrap();
+    }
+
+    #[test]
+    fn test_duplicate_member_is_rejected() {
+        let member = Pubkey::new_unique();
+        let mut treasury = treasury(vec![(member, TreasuryRole::Viewer)]);
+        let owner = treasury.owner;
+        assert_eq!(
+            treasury.add_member(&owner, member, TreasuryRole::Admin).unwrap_err(),
+            StateError::DuplicateTreasuryMember.into()
+        );
+        assert_eq!(
+            treasury.add_member(&owner, owner, TreasuryRole::Admin).unwrap_err(),
+            StateError::DuplicateTreasuryMember.into()
+        );
+        assert_eq!(treasury.role_of(&member), Some(TreasuryRole::Viewer));
+    }
+
+    #[test]
+    fn test_only_owner_manages_members() {
+        let admin = Pubkey::new_unique();
+        let mut treasury = treasury(vec![(admin, TreasuryRole::Admin)]);
+        assert_eq!(
+            treasury.add_member(&admin, Pubkey::new_unique(), TreasuryRole::Member).unwrap_err(),
+            StateError::UnauthorizedTreasuryOperation.into()
+        );
+        assert_eq!(
+            treasury.remove_member(&admin, &admin).unwrap_err(),
+            StateError::UnauthorizedTreasuryOperation.into()
+        );
+        assert_eq!(treasury.members.len(), 1);
+    }
+}
@@ -0,0 +1,217 @@
+use anchor_lang::prelude::*;
+use super::constants::MAX_VESTING_SCHEDULES;
+use super::utils::calculate_vested_amount;
+use super::{StateError, VestingType};
+
+/// Number of vesting schedules ever granted to a beneficiary
+///
+/// Each schedule PDA is seeded with the count at its creation, so a
+/// beneficiary's schedules can be derived without `getProgramAccounts`.
+#[account]
+#[derive(Debug, Default)]
+pub struct VestingRegistry {
+    /// Beneficiary the schedules belong to
+    pub beneficiary: Pubkey,
+    /// Index of the next schedule to be created
+    pub schedule_count: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl VestingRegistry {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // beneficiary
+        8 + // schedule_count
+        1; // bump
+
+    /// Take the index for a new schedule, initializing the registry on first
+    /// use. A beneficiary is granted at most `MAX_VESTING_SCHEDULES` schedules.
+    pub fn claim_index(&mut self, beneficiary: Pubkey, bump: u8) -> Result<u64> {
+        if self.schedule_count == 0 {
+            self.beneficiary = beneficiary;
+            self.bump = bump;
+        }
+        require!(
+            (self.schedule_count as usize) < MAX_VESTING_SCHEDULES,
+            StateError::InvalidVestingSchedule
+        );
+        let index = self.schedule_count;
+        self.schedule_count += 1;
+        Ok(index)
+    }
+}
+
+/// Tokens granted to a beneficiary and released by `calculate_vested_amount`
+#[account]
+#[derive(Debug, Default)]
+pub struct VestingSchedule {
+    /// Account that funded the schedule
+    pub grantor: Pubkey,
+    /// Account the tokens vest to
+    pub beneficiary: Pubkey,
+    /// Token being vested
+    pub mint: Pubkey,
+    /// Escrow token account owned by the schedule PDA
+    pub escrow: Pubkey,
+    /// Index of the schedule in the beneficiary's registry
+    pub index: u64,
+    /// Tokens granted
+    pub total_amount: u64,
+    /// Tokens claimed so far
+    pub claimed_amount: u64,
+    pub vesting_type: VestingType,
+    pub start_time: i64,
+    /// Unlock time for `Cliff` schedules
+    pub cliff_time: Option<i64>,
+    pub end_time: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Bump seed for the escrow PDA
+    pub escrow_bump: u8,
+}
+
+impl VestingSchedule {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // grantor
+        32 + // beneficiary
+        32 + // mint
+        32 + // escrow
+        8 + // index
+        8 + // total_amount
+        8 + // claimed_amount
+        1 + // vesting_type
+        8 + // start_time
+        9 + // cliff_time (Option<i64>)
+        8 + // end_time
+        1 + // bump
+        1; // escrow_bump
+
+    /// Check the parameters of a new schedule
+    pub fn validate_params(
+        total_amount: u64,
+        vesting_type: VestingType,
+        start_time: i64,
+        cliff_time: Option<i64>,
+        end_time: i64,
+    ) -> Result<()> {
+        require!(total_amount > 0, StateError::ZeroStreamAmount);
+        require!(end_time > start_time, StateError::InvalidEndTime);
+        if let Some(cliff_time) = cliff_time {
+            require!(
+                cliff_time >= start_time && cliff_time <= end_time,
+                StateError::InvalidCliffDate
+            );
+        }
+        require!(
+            vesting_type != VestingType::Cliff || cliff_time.is_some(),
+            StateError::InvalidVestingSchedule
+        );
+        Ok(())
+    }
+
+    /// Tokens vested at `current_time`
+    pub fn vested_amount(&self, current_time: i64) -> u64 {
+        calculate_vested_amount(
+            self.total_amount,
+            self.vesting_type,
+            self.start_time,
+            self.cliff_time,
+            self.end_time,
+            current_time,
+        )
+    }
+
+    /// Tokens vested at `current_time` and not yet claimed
+    pub fn claimable_amount(&self, current_time: i64) -> u64 {
+        self.vested_amount(current_time).saturating_sub(self.claimed_amount)
+    }
+
+    /// Mark everything claimable at `current_time` as claimed and return it
+    pub fn record_claim(&mut self, current_time: i64) -> Result<u64> {
+        let amount = self.claimable_amount(current_time);
+        require!(amount > 0, StateError::VestingNotUnlocked);
+        self.claimed_amount += amount;
+        Ok(amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(vesting_type: VestingType, cliff_time: Option<i64>) -> VestingSchedule {
+        VestingSchedule {
+            total_amount: 1_000,
+            vesting_type,
+            start_time: 100,
+            cliff_time,
+            end_time: 200,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_linear_vesting() {
+        let mut schedule = schedule(VestingType::Linear, None);
+        assert_eq!(schedule.vested_amount(50), 0);
+        assert_eq!(schedule.vested_amount(100), 0);
+        assert_eq!(schedule.vested_amount(125), 250);
+        assert_eq!(schedule.vested_amount(200), 1_000);
+        assert_eq!(schedule.vested_amount(10_000), 1_000);
+
+        assert_eq!(schedule.record_claim(150).unwrap(), 500);
+        assert_eq!(schedule.claimable_amount(150), 0);
+        assert_eq!(schedule.record_claim(150).unwrap_err(), StateError::VestingNotUnlocked.into());
+        assert_eq!(schedule.record_claim(300).unwrap(), 500);
+        assert_eq!(schedule.claimed_amount, 1_000);
+    }
+
+    #[test]
+    fn test_cliff_vesting() {
+        let mut schedule = schedule(VestingType::Cliff, Some(150));
+        assert_eq!(schedule.vested_amount(100), 0);
+        assert_eq!(schedule.vested_amount(149), 0);
+        assert_eq!(schedule.vested_amount(150), 1_000);
+        assert_eq!(schedule.vested_amount(200), 1_000);
+
+        assert_eq!(schedule.record_claim(149).unwrap_err(), StateError::VestingNotUnlocked.into());
+        assert_eq!(schedule.record_claim(150).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn test_schedule_params() {
+        assert!(VestingSchedule::validate_params(1_000, VestingType::Linear, 100, None, 200).is_ok());
+        assert!(VestingSchedule::validate_params(1_000, VestingType::Cliff, 100, Some(150), 200).is_ok());
+        assert_eq!(
+            VestingSchedule::validate_params(1_000, VestingType::Cliff, 100, None, 200).unwrap_err(),
+            StateError::InvalidVestingSchedule.into()
+        );
+        assert_eq!(
+            VestingSchedule::validate_params(1_000, VestingType::Cliff, 100, Some(250), 200).unwrap_err(),
+            StateError::InvalidCliffDate.into()
+        );
+        assert_eq!(
+            VestingSchedule::validate_params(1_000, VestingType::Linear, 200, None, 200).unwrap_err(),
+            StateError::InvalidEndTime.into()
+        );
+        assert_eq!(
+            VestingSchedule::validate_params(0, VestingType::Linear, 100, None, 200).unwrap_err(),
+            StateError::ZeroStreamAmount.into()
+        );
+    }
+
+    #[test]
+    fn test_schedule_cap_per_beneficiary() {
+        let beneficiary = Pubkey::new_unique();
+        let mut registry = VestingRegistry::default();
+        for expected in 0..MAX_VESTING_SCHEDULES as u64 {
+            assert_eq!(registry.claim_index(beneficiary, 254).unwrap(), expected);
+        }
+        assert_eq!(
+            registry.claim_index(beneficiary, 254).unwrap_err(),
+            StateError::InvalidVestingSchedule.into()
+        );
+        assert_eq!(registry.beneficiary, beneficiary);
+        assert_eq!(registry.schedule_count, MAX_VESTING_SCHEDULES as u64);
+    }
+}
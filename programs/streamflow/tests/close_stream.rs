@@ -0,0 +1,130 @@
+use anchor_lang::{AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas};
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use streamflow::state::{Stream, StreamStatus};
+
+const DEPOSIT: u64 = 1_000;
+
+struct Fixture {
+    banks: BanksClient,
+    payer: Keypair,
+    blockhash: Hash,
+    sender: Keypair,
+    stream: Pubkey,
+    escrow: Pubkey,
+}
+
+/// A stream PDA and its escrow, with `withdrawn` of the deposit paid out and
+/// `escrow_balance` tokens left in the escrow
+async fn setup(status: StreamStatus, withdrawn: u64, escrow_balance: u64) -> Fixture {
+    let sender = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    let (stream, stream_bump) = streamflow::state::stream_address(&sender.pubkey(), &recipient, 0, &streamflow::ID);
+    let (escrow, escrow_bump) = Pubkey::find_program_address(&[b"escrow", stream.as_ref()], &streamflow::ID);
+
+    // Start from a zeroed account and fill in what close_stream looks at
+    let zeroed = vec![0u8; Stream::LEN];
+    let mut state = Stream::try_deserialize_unchecked(&mut zeroed.as_slice()).unwrap();
+    state.sender = sender.pubkey();
+    state.recipient = recipient;
+    state.mint = mint;
+    state.escrow_tokens = escrow;
+    state.deposited_amount = DEPOSIT;
+    state.withdrawn_amount = withdrawn;
+    state.status = status;
+    state.bump = stream_bump;
+    state.escrow_bump = escrow_bump;
+    let mut data = Vec::with_capacity(Stream::LEN);
+    state.try_serialize(&mut data).unwrap();
+    data.resize(Stream::LEN, 0);
+
+    let mut escrow_data = vec![0u8; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint,
+        owner: stream,
+        amount: escrow_balance,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut escrow_data);
+
+    let mut program_test = ProgramTest::new("streamflow", streamflow::ID, processor!(streamflow::entry));
+    program_test.add_account(
+        stream,
+        Account { lamports: 10_000_000, data, owner: streamflow::ID, executable: false, rent_epoch: 0 },
+    );
+    program_test.add_account(
+        escrow,
+        Account { lamports: 2_039_280, data: escrow_data, owner: spl_token::ID, executable: false, rent_epoch: 0 },
+    );
+    let (banks, payer, blockhash) = program_test.start().await;
+
+    Fixture { banks, payer, blockhash, sender, stream, escrow }
+}
+
+async fn close(fixture: &mut Fixture) -> Result<(), solana_program_test::BanksClientError> {
+    let ix = solana_sdk::instruction::Instruction {
+        program_id: streamflow::ID,
+        accounts: streamflow::accounts::CloseStream {
+            stream: fixture.stream,
+            sender: fixture.sender.pubkey(),
+            escrow_token_account: fixture.escrow,
+            rent_destination: None,
+            template: None,
+            stream_directory: None,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: streamflow::instruction::CloseStream {}.data(),
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&fixture.payer.pubkey()),
+        &[&fixture.payer, &fixture.sender],
+        fixture.blockhash,
+    );
+    fixture.banks.process_transaction(tx).await
+}
+
+#[tokio::test]
+async fn test_close_fully_withdrawn_stream_returns_rent() {
+    let mut fixture = setup(StreamStatus::Completed, DEPOSIT, 0).await;
+    let locked = fixture.banks.get_balance(fixture.stream).await.unwrap()
+        + fixture.banks.get_balance(fixture.escrow).await.unwrap();
+
+    close(&mut fixture).await.unwrap();
+
+    assert!(fixture.banks.get_account(fixture.stream).await.unwrap().is_none());
+    assert!(fixture.banks.get_account(fixture.escrow).await.unwrap().is_none());
+    assert_eq!(fixture.banks.get_balance(fixture.sender.pubkey()).await.unwrap(), locked);
+}
+
+#[tokio::test]
+async fn test_close_cancelled_stream_with_empty_escrow() {
+    let mut fixture = setup(StreamStatus::Cancelled, 400, 0).await;
+    close(&mut fixture).await.unwrap();
+    assert!(fixture.banks.get_account(fixture.stream).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_close_active_stream_with_balance_is_rejected() {
+    let mut fixture = setup(StreamStatus::Streaming, 400, DEPOSIT - 400).await;
+    assert!(close(&mut fixture).await.is_err());
+
+    // Both accounts are still there
+    assert!(fixture.banks.get_account(fixture.stream).await.unwrap().is_some());
+    assert!(fixture.banks.get_account(fixture.escrow).await.unwrap().is_some());
+}
@@ -0,0 +1,246 @@
+use anchor_lang::{AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas};
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use streamflow::state::{FeeConfig, ProgramConfig, Stream, StreamStatus};
+
+const DEPOSIT: u64 = 1_000_000;
+const WITHDRAWN: u64 = 100_000;
+
+fn program_config_account(bump: u8) -> Account {
+    let config = ProgramConfig { bump, ..Default::default() };
+    let mut data = Vec::with_capacity(ProgramConfig::LEN);
+    config.try_serialize(&mut data).unwrap();
+    data.resize(ProgramConfig::LEN, 0);
+
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: streamflow::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+async fn token_balance(banks: &mut BanksClient, account: Pubkey) -> u64 {
+    let account = banks.get_account(account).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+async fn stream_state(banks: &mut BanksClient, stream: Pubkey) -> Stream {
+    let account = banks.get_account(stream).await.unwrap().unwrap();
+    Stream::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+/// Create, withdraw from and cancel one stream, checking that every step
+/// signs for the same escrow PDA
+#[tokio::test]
+async fn test_create_withdraw_cancel_share_one_escrow() {
+    let sender = Keypair::new();
+    let recipient = Keypair::new();
+    let mint = Keypair::new();
+
+    let (program_config, config_bump) = Pubkey::find_program_address(&[b"program_config"], &streamflow::ID);
+
+    let mut program_test = ProgramTest::new("streamflow", streamflow::ID, processor!(streamflow::entry));
+    program_test.add_account(
+        sender.pubkey(),
+        Account::new(10_000_000_000, 0, &solana_sdk::system_program::ID),
+    );
+    program_test.add_account(program_config, program_config_account(config_bump));
+    let (mut banks, payer, blockhash) = program_test.start().await;
+
+    // Fund the sender and give both parties an associated token account
+    let rent = banks.get_rent().await.unwrap();
+    let sender_ata = get_associated_token_address(&sender.pubkey(), &mint.pubkey());
+    let recipient_ata = get_associated_token_address(&recipient.pubkey(), &mint.pubkey());
+    let setup = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), &sender.pubkey(), None, 6).unwrap(),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer.pubkey(),
+                &sender.pubkey(),
+                &mint.pubkey(),
+                &spl_token::ID,
+            ),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer.pubkey(),
+                &recipient.pubkey(),
+                &mint.pubkey(),
+                &spl_token::ID,
+            ),
+            spl_token::instruction::mint_to(&spl_token::ID, &mint.pubkey(), &sender_ata, &sender.pubkey(), &[], DEPOSIT).unwrap(),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &mint, &sender],
+        blockhash,
+    );
+    banks.process_transaction(setup).await.unwrap();
+
+    let (stream_counter, _) = Pubkey::find_program_address(
+        &[b"stream_counter", sender.pubkey().as_ref(), recipient.pubkey().as_ref()],
+        &streamflow::ID,
+    );
+    let (stream, _) = streamflow::state::stream_address(&sender.pubkey(), &recipient.pubkey(), 0, &streamflow::ID);
+    let (escrow, escrow_bump) = Pubkey::find_program_address(&[b"escrow", stream.as_ref()], &streamflow::ID);
+    let (mint_stats, _) = Pubkey::find_program_address(&[b"mint_stats", mint.pubkey().as_ref()], &streamflow::ID);
+    let (stream_directory, _) =
+        Pubkey::find_program_address(&[b"stream_directory", sender.pubkey().as_ref()], &streamflow::ID);
+
+    // Half-way through a backdated stream, so part of it is already vested
+    let clock = banks.get_sysvar::<solana_sdk::clock::Clock>().await.unwrap();
+    let start_time = clock.unix_timestamp - 500;
+    let end_time = clock.unix_timestamp + 500;
+
+    let create = Instruction {
+        program_id: streamflow::ID,
+        accounts: streamflow::accounts::CreateStream {
+            stream_counter,
+            stream,
+            escrow_token_account: escrow,
+            sender_token_account: sender_ata,
+            sender: sender.pubkey(),
+            recipient: recipient.pubkey(),
+            mint: mint.pubkey(),
+            rate_cap: None,
+            mint_stats,
+            stream_directory,
+            program_config,
+            token_program: spl_token::ID,
+            system_program: solana_sdk::system_program::ID,
+            rent: solana_sdk::sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: streamflow::instruction::CreateStream {
+            recipient: recipient.pubkey(),
+            deposit_amount: DEPOSIT,
+            start_time,
+            end_time,
+            cliff_time: 0,
+            cliff_amount: 0,
+            cancelable_by_sender: true,
+            cancelable_by_recipient: false,
+            transferable_by_sender: false,
+            transferable_by_recipient: false,
+            stream_name: "payroll".to_string(),
+            rent_treasury: None,
+            interest_rate_bps: 0,
+            allow_distant_start: false,
+            can_topup: false,
+            fee_config: FeeConfig {
+                fee_percentage: 0,
+                fee_recipient: None,
+                partner_fee_percentage: 0,
+                partner_fee_recipient: None,
+                allow_self_routing: false,
+            },
+            receipt_required: false,
+            can_update_rate: false,
+            lock_rate_after_start: false,
+            allow_backdated: true,
+            allow_partial_withdraw: true,
+            automatic_withdrawal: false,
+            withdrawal_frequency: 0,
+            require_acceptance: false,
+            cancel_approvers: Vec::new(),
+            cancel_threshold: 0,
+            clawback_authority: None,
+            min_withdrawal_interval: 0,
+            usd_rate_per_second: None,
+            price_feed: None,
+            max_price_age: 0,
+            max_pause_duration: 0,
+            start_on_acceptance: false,
+        }
+        .data(),
+    };
+    let tx = Transaction::new_signed_with_payer(&[create], Some(&payer.pubkey()), &[&payer, &sender], blockhash);
+    banks.process_transaction(tx).await.unwrap();
+
+    // The escrow is the `[b"escrow", stream]` PDA, owned by the stream, with its bump stored
+    let state = stream_state(&mut banks, stream).await;
+    assert_eq!(state.escrow_tokens, escrow);
+    assert_eq!(state.escrow_bump, escrow_bump);
+    let escrow_account = banks.get_account(escrow).await.unwrap().unwrap();
+    let escrow_state = spl_token::state::Account::unpack(&escrow_account.data).unwrap();
+    assert_eq!(escrow_state.owner, stream);
+    assert_eq!(escrow_state.amount, DEPOSIT);
+
+    // Withdraw signs for the escrow with the stream seeds
+    let withdraw = Instruction {
+        program_id: streamflow::ID,
+        accounts: streamflow::accounts::Withdraw {
+            stream,
+            stream_token_account: escrow,
+            recipient_token_account: recipient_ata,
+            yield_reserve: None,
+            rate_cap: None,
+            mint_stats,
+            mint: mint.pubkey(),
+            recipient: recipient.pubkey(),
+            sender: sender.pubkey(),
+            program_config,
+            price_feed: None,
+            fee_vault: None,
+            fee_vault_token_account: None,
+            token_program: spl_token::ID,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: streamflow::instruction::Withdraw { amount: Some(WITHDRAWN), receipt_hash: [0; 32] }.data(),
+    };
+    let tx = Transaction::new_signed_with_payer(&[withdraw], Some(&payer.pubkey()), &[&payer, &recipient], blockhash);
+    banks.process_transaction(tx).await.unwrap();
+    assert_eq!(token_balance(&mut banks, recipient_ata).await, WITHDRAWN);
+    assert_eq!(token_balance(&mut banks, escrow).await, DEPOSIT - WITHDRAWN);
+
+    // Cancel drains the same escrow to both parties
+    let cancel = Instruction {
+        program_id: streamflow::ID,
+        accounts: streamflow::accounts::CancelStream {
+            stream,
+            authority: sender.pubkey(),
+            sender: sender.pubkey(),
+            recipient: recipient.pubkey(),
+            sender_token_account: sender_ata,
+            recipient_token_account: recipient_ata,
+            escrow_token_account: escrow,
+            rate_cap: None,
+            mint_stats,
+            cancel_approval: None,
+            mint: mint.pubkey(),
+            price_feed: None,
+            program_config,
+            token_program: spl_token::ID,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: streamflow::instruction::CancelStream {}.data(),
+    };
+    let tx = Transaction::new_signed_with_payer(&[cancel], Some(&payer.pubkey()), &[&payer, &sender], blockhash);
+    banks.process_transaction(tx).await.unwrap();
+
+    let state = stream_state(&mut banks, stream).await;
+    assert_eq!(state.status, StreamStatus::Cancelled);
+    assert_eq!(token_balance(&mut banks, escrow).await, 0);
+
+    let to_recipient = token_balance(&mut banks, recipient_ata).await;
+    let to_sender = token_balance(&mut banks, sender_ata).await;
+    assert!(to_recipient > WITHDRAWN);
+    assert_eq!(to_recipient + to_sender, DEPOSIT);
+}
@@ -0,0 +1,224 @@
+use anchor_lang::{AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas};
+use solana_program_test::{processor, BanksClient, BanksClientError, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use streamflow::state::{MintStats, ProgramConfig, Stream, StreamStatus, StreamTemplate, StreamType};
+
+const DEPOSIT: u64 = 1_000_000;
+const DRIP: u64 = 5_000_000;
+
+fn template_account(authority: Pubkey, mint: Pubkey, bump: u8) -> Account {
+    let template = StreamTemplate {
+        authority,
+        template_id: 0,
+        mint,
+        deposit_amount: DEPOSIT,
+        duration: 86_400,
+        stream_type: StreamType::Linear,
+        cliff_offset: 0,
+        cliff_amount: 0,
+        rate_amount: 0,
+        rate_interval_in_seconds: 0,
+        cancelable_by_sender: true,
+        cancelable_by_recipient: false,
+        fee_percentage: 0,
+        fee_recipient: None,
+        partner_fee_percentage: 0,
+        partner_fee_recipient: None,
+        onboarding_lamports: DRIP,
+        active_stream_count: 0,
+        bump,
+    };
+    let mut data = Vec::with_capacity(StreamTemplate::LEN);
+    template.try_serialize(&mut data).unwrap();
+    data.resize(StreamTemplate::LEN, 0);
+
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: streamflow::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn program_config_account(emergency_paused: bool, bump: u8) -> Account {
+    let config = ProgramConfig { emergency_paused, bump, ..Default::default() };
+    let mut data = Vec::with_capacity(ProgramConfig::LEN);
+    config.try_serialize(&mut data).unwrap();
+    data.resize(ProgramConfig::LEN, 0);
+
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: streamflow::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+struct Onboarding {
+    banks: BanksClient,
+    result: Result<(), BanksClientError>,
+    template: Pubkey,
+    stream: Pubkey,
+    escrow: Pubkey,
+    mint_stats: Pubkey,
+    recipient: Pubkey,
+    recipient_ata: Pubkey,
+    start_time: i64,
+}
+
+/// Onboard a fresh recipient from a template, with the program paused or not
+async fn onboard(paused: bool) -> Onboarding {
+    let authority = Keypair::new();
+    let mint = Keypair::new();
+    let recipient = Keypair::new();
+
+    let (template, template_bump) = Pubkey::find_program_address(
+        &[b"template", authority.pubkey().as_ref(), &0u64.to_le_bytes()],
+        &streamflow::ID,
+    );
+    let (program_config, config_bump) = Pubkey::find_program_address(&[b"program_config"], &streamflow::ID);
+
+    let mut program_test = ProgramTest::new("streamflow", streamflow::ID, processor!(streamflow::entry));
+    program_test.add_account(program_config, program_config_account(paused, config_bump));
+    program_test.add_account(
+        authority.pubkey(),
+        Account::new(10_000_000_000, 0, &solana_sdk::system_program::ID),
+    );
+    program_test.add_account(template, template_account(authority.pubkey(), mint.pubkey(), template_bump));
+    let (mut banks, payer, blockhash) = program_test.start().await;
+
+    // Mint the onboarding budget into the authority's funding account
+    let rent = banks.get_rent().await.unwrap();
+    let funding = get_associated_token_address(&authority.pubkey(), &mint.pubkey());
+    let setup = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), &authority.pubkey(), None, 6).unwrap(),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer.pubkey(),
+                &authority.pubkey(),
+                &mint.pubkey(),
+                &spl_token::ID,
+            ),
+            spl_token::instruction::mint_to(&spl_token::ID, &mint.pubkey(), &funding, &authority.pubkey(), &[], DEPOSIT).unwrap(),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &mint, &authority],
+        blockhash,
+    );
+    banks.process_transaction(setup).await.unwrap();
+
+    let (stream_counter, _) = Pubkey::find_program_address(
+        &[b"stream_counter", authority.pubkey().as_ref(), recipient.pubkey().as_ref()],
+        &streamflow::ID,
+    );
+    let (stream, _) = streamflow::state::stream_address(&authority.pubkey(), &recipient.pubkey(), 0, &streamflow::ID);
+    let (escrow, _) = Pubkey::find_program_address(&[b"escrow", stream.as_ref()], &streamflow::ID);
+    let recipient_ata = get_associated_token_address(&recipient.pubkey(), &mint.pubkey());
+    let (mint_stats, _) = Pubkey::find_program_address(&[b"mint_stats", mint.pubkey().as_ref()], &streamflow::ID);
+
+    let clock = banks.get_sysvar::<solana_sdk::clock::Clock>().await.unwrap();
+    let start_time = clock.unix_timestamp + 60;
+
+    let onboard = solana_sdk::instruction::Instruction {
+        program_id: streamflow::ID,
+        accounts: streamflow::accounts::OnboardRecipient {
+            template,
+            stream_counter,
+            stream,
+            escrow_token_account: escrow,
+            funding_token_account: funding,
+            recipient_token_account: recipient_ata,
+            recipient: recipient.pubkey(),
+            authority: authority.pubkey(),
+            mint: mint.pubkey(),
+            mint_stats,
+            program_config,
+            token_program: spl_token::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: streamflow::instruction::OnboardRecipient { start_time, drip_sol: true }.data(),
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[onboard],
+        Some(&authority.pubkey()),
+        &[&authority],
+        blockhash,
+    );
+    let result = banks.process_transaction(tx).await;
+
+    Onboarding {
+        banks,
+        result,
+        template,
+        stream,
+        escrow,
+        mint_stats,
+        recipient: recipient.pubkey(),
+        recipient_ata,
+        start_time,
+    }
+}
+
+#[tokio::test]
+async fn test_onboard_fresh_recipient_end_to_end() {
+    let Onboarding { mut banks, result, template, stream, escrow, mint_stats, recipient, recipient_ata, start_time } =
+        onboard(false).await;
+    result.unwrap();
+
+    // The recipient's token account now exists and they hold the SOL drip
+    let ata = banks.get_account(recipient_ata).await.unwrap().expect("recipient ATA created");
+    assert_eq!(ata.owner, spl_token::ID);
+    assert_eq!(banks.get_balance(recipient).await.unwrap(), DRIP);
+
+    // The escrow holds the full deposit
+    let escrow_account = banks.get_account(escrow).await.unwrap().unwrap();
+    let escrow_state = spl_token::state::Account::unpack(&escrow_account.data).unwrap();
+    assert_eq!(escrow_state.amount, DEPOSIT);
+
+    // The deposit counts towards the mint's locked total
+    let stats_account = banks.get_account(mint_stats).await.unwrap().unwrap();
+    let stats = MintStats::try_deserialize(&mut stats_account.data.as_slice()).unwrap();
+    assert_eq!(stats.total_value_locked().unwrap(), DEPOSIT);
+
+    // The stream follows the template
+    let stream_account = banks.get_account(stream).await.unwrap().unwrap();
+    let stream_state = Stream::try_deserialize(&mut stream_account.data.as_slice()).unwrap();
+    assert_eq!(stream_state.recipient, recipient);
+    assert_eq!(stream_state.deposited_amount, DEPOSIT);
+    assert_eq!(stream_state.start_time, start_time);
+    assert_eq!(stream_state.end_time, start_time + 86_400);
+    assert_eq!(stream_state.status, StreamStatus::Scheduled);
+
+    let template_account = banks.get_account(template).await.unwrap().unwrap();
+    let template_state = StreamTemplate::try_deserialize(&mut template_account.data.as_slice()).unwrap();
+    assert_eq!(template_state.active_stream_count, 1);
+}
+
+#[tokio::test]
+async fn test_onboarding_rejected_while_paused() {
+    let Onboarding { mut banks, result, stream, recipient, .. } = onboard(true).await;
+    assert!(result.is_err());
+
+    // Nothing was created or dripped
+    assert!(banks.get_account(stream).await.unwrap().is_none());
+    assert_eq!(banks.get_balance(recipient).await.unwrap(), 0);
+}